@@ -0,0 +1,273 @@
+//! Transaction Merkle Tree
+//!
+//! Computes `v1::BlockHeader.transactions_root` and compact inclusion proofs
+//! over v1 transaction hashes.
+//!
+//! ## Tree Construction
+//!
+//! ```text
+//! leaf  = SHA256(0x00 || tx_hash)
+//! node  = SHA256(0x01 || left || right)
+//! empty = [0u8; 32]
+//! ```
+//!
+//! Leaves keep block order. When a level has an odd number of nodes, the last
+//! node is promoted to the next level unchanged. It is never paired with a
+//! copy of itself, so duplicating the last transaction changes the root
+//! (unlike Bitcoin's tree). The distinct leaf and node prefixes prevent an
+//! inner node from being passed off as a leaf.
+//!
+//! ## Inclusion Proofs
+//!
+//! A `MerkleProof` carries the leaf index, the leaf count and the sibling
+//! hashes from leaf to root. Promoted levels contribute no sibling, so a proof
+//! for a block of `n` transactions holds at most `ceil(log2(n))` hashes.
+//!
+//! ```rust,ignore
+//! use self_chain_core::blockchain::merkle::{transactions_root, MerkleProof};
+//!
+//! let root = transactions_root(&block.transactions);
+//! let proof = MerkleProof::for_transaction(&block.transactions, 3).unwrap();
+//! assert!(proof.verify(&root, &block.transactions[3].hash()));
+//! ```
+
+use crate::blockchain::v1::codec::{Canonical, CodecError, CodecResult, Decoder, Encoder};
+use crate::blockchain::v1::Transaction;
+use sha2::{Digest, Sha256};
+
+/// Domain prefix for leaf hashes
+pub const LEAF_PREFIX: u8 = 0x00;
+
+/// Domain prefix for inner node hashes
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Root of a tree with no leaves
+pub const EMPTY_ROOT: [u8; 32] = [0u8; 32];
+
+/// Hash a transaction hash into a leaf
+pub fn leaf_hash(tx_hash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(tx_hash);
+    hasher.finalize().into()
+}
+
+/// Hash two children into an inner node
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Compute the Merkle root over a list of transaction hashes
+pub fn merkle_root(tx_hashes: &[[u8; 32]]) -> [u8; 32] {
+    if tx_hashes.is_empty() {
+        return EMPTY_ROOT;
+    }
+
+    let mut level: Vec<[u8; 32]> = tx_hashes.iter().map(leaf_hash).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Compute the `transactions_root` for a list of v1 transactions
+pub fn transactions_root(transactions: &[Transaction]) -> [u8; 32] {
+    let hashes: Vec<[u8; 32]> = transactions.iter().map(Transaction::hash).collect();
+    merkle_root(&hashes)
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks(2) yields one or two items"),
+        })
+        .collect()
+}
+
+/// Compact inclusion proof for a single transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// Position of the transaction in the block
+    pub index: u64,
+
+    /// Number of transactions in the block
+    pub leaf_count: u64,
+
+    /// Sibling hashes from the leaf level up to the root
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// Build a proof for the transaction hash at `index`
+    ///
+    /// Returns `None` if `index` is out of range.
+    pub fn generate(tx_hashes: &[[u8; 32]], index: usize) -> Option<Self> {
+        if index >= tx_hashes.len() {
+            return None;
+        }
+
+        let mut siblings = Vec::new();
+        let mut level: Vec<[u8; 32]> = tx_hashes.iter().map(leaf_hash).collect();
+        let mut idx = index;
+
+        while level.len() > 1 {
+            let sibling = idx ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            level = next_level(&level);
+            idx /= 2;
+        }
+
+        Some(Self {
+            index: index as u64,
+            leaf_count: tx_hashes.len() as u64,
+            siblings,
+        })
+    }
+
+    /// Build a proof for the transaction at `index` in a block's transaction list
+    pub fn for_transaction(transactions: &[Transaction], index: usize) -> Option<Self> {
+        let hashes: Vec<[u8; 32]> = transactions.iter().map(Transaction::hash).collect();
+        Self::generate(&hashes, index)
+    }
+
+    /// Recompute the root implied by this proof for the given transaction hash
+    ///
+    /// Returns `None` if the proof is malformed (index out of range or wrong
+    /// number of siblings).
+    pub fn compute_root(&self, tx_hash: &[u8; 32]) -> Option<[u8; 32]> {
+        if self.index >= self.leaf_count {
+            return None;
+        }
+
+        let mut siblings = self.siblings.iter();
+        let mut hash = leaf_hash(tx_hash);
+        let mut idx = self.index;
+        let mut count = self.leaf_count;
+
+        while count > 1 {
+            if idx % 2 == 1 {
+                hash = node_hash(siblings.next()?, &hash);
+            } else if idx + 1 < count {
+                hash = node_hash(&hash, siblings.next()?);
+            }
+            idx /= 2;
+            count = count.div_ceil(2);
+        }
+
+        if siblings.next().is_some() {
+            return None;
+        }
+        Some(hash)
+    }
+
+    /// Verify that `tx_hash` is included under `root`
+    pub fn verify(&self, root: &[u8; 32], tx_hash: &[u8; 32]) -> bool {
+        self.compute_root(tx_hash).as_ref() == Some(root)
+    }
+}
+
+impl Canonical for MerkleProof {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_u64(self.index);
+        enc.put_u64(self.leaf_count);
+        enc.put_seq(&self.siblings);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        let index = dec.get_u64()?;
+        let leaf_count = dec.get_u64()?;
+        if index >= leaf_count {
+            return Err(CodecError::NonCanonical(format!(
+                "proof index {} out of range for {} leaves",
+                index, leaf_count
+            )));
+        }
+
+        Ok(Self {
+            index,
+            leaf_count,
+            siblings: dec.get_seq()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| [i; 32]).collect()
+    }
+
+    #[test]
+    fn test_empty_and_single_root() {
+        assert_eq!(merkle_root(&[]), EMPTY_ROOT);
+        assert_eq!(merkle_root(&[[7u8; 32]]), leaf_hash(&[7u8; 32]));
+    }
+
+    #[test]
+    fn test_odd_node_is_promoted() {
+        let h = hashes(3);
+        let expected = node_hash(
+            &node_hash(&leaf_hash(&h[0]), &leaf_hash(&h[1])),
+            &leaf_hash(&h[2]),
+        );
+        assert_eq!(merkle_root(&h), expected);
+
+        // Duplicating the last transaction must change the root
+        let mut dup = h.clone();
+        dup.push(h[2]);
+        assert_ne!(merkle_root(&dup), merkle_root(&h));
+    }
+
+    #[test]
+    fn test_proofs_verify_for_every_index() {
+        for n in 1..=9 {
+            let h = hashes(n);
+            let root = merkle_root(&h);
+            for (i, leaf) in h.iter().enumerate() {
+                let proof = MerkleProof::generate(&h, i).unwrap();
+                assert!(proof.siblings.len() <= 4);
+                assert!(proof.verify(&root, leaf), "n={} i={}", n, i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof_fails() {
+        let h = hashes(5);
+        let root = merkle_root(&h);
+        let mut proof = MerkleProof::generate(&h, 2).unwrap();
+
+        assert!(!proof.verify(&root, &h[3]));
+
+        proof.index = 3;
+        assert!(!proof.verify(&root, &h[2]));
+
+        proof.index = 2;
+        proof.siblings.push([0u8; 32]);
+        assert!(!proof.verify(&root, &h[2]));
+    }
+
+    #[test]
+    fn test_transactions_root_and_proof_encoding() {
+        let txs: Vec<Transaction> = (0..4)
+            .map(|i| Transaction::new(i, "c".to_string(), "s".to_string(), None, vec![], 1, 1))
+            .collect();
+        let root = transactions_root(&txs);
+
+        let proof = MerkleProof::for_transaction(&txs, 1).unwrap();
+        let decoded = MerkleProof::from_canonical_bytes(&proof.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(decoded.verify(&root, &txs[1].hash()));
+    }
+}
@@ -10,7 +10,13 @@
 //!
 //! The `v1` submodule contains spec-compliant wire format types that match
 //! `docs/POAI_SPECIFICATION.md`. See `v1::BlockHeader` for the canonical format.
+//!
+//! ## Merkle Trees
+//!
+//! The `merkle` submodule computes `v1::BlockHeader.transactions_root` and
+//! inclusion proofs for light clients.
 
+pub mod merkle;
 pub mod v1;
 
 use serde::{Deserialize, Serialize};
@@ -8,7 +8,14 @@
 //! ```text
 //! Hash = SHA256("self-chain-block-header-v1" || bincode(header))
 //! ```
+//!
+//! `bincode(header)` here means canonical fields 1-10: `commit_signatures`
+//! sign the block hash, so they cannot be part of it, and `commit_round`
+//! records the round they were cast in. Attaching commit signatures to a
+//! finalized block never changes its hash.
 
+use crate::blockchain::merkle;
+use crate::blockchain::v1::codec::{domain_hash, Canonical, CodecResult, Decoder, Encoder};
 use crate::blockchain::v1::transaction::Transaction;
 
 /// PoAI v1 Block Header (spec-compliant)
@@ -28,6 +35,7 @@ use crate::blockchain::v1::transaction::Transaction;
 /// 9. `efficiency_score` (u64, little-endian)
 /// 10. `point_price` (u64, little-endian)
 /// 11. `commit_signatures` (length-prefixed array)
+/// 12. `commit_round` (u64, little-endian)
 ///
 /// ## Key Differences from Production
 ///
@@ -76,6 +84,12 @@ pub struct BlockHeader {
     
     /// 2/3+ committee signatures for finality
     pub commit_signatures: Vec<CommitSignature>,
+    
+    /// Round of the votes behind `commit_signatures`
+    ///
+    /// Equal to `round` unless validators locked on the block carried it into
+    /// a later round.
+    pub commit_round: u64,
 }
 
 /// Commit signature from a committee member
@@ -108,8 +122,33 @@ impl BlockHeader {
             efficiency_score: 0,
             point_price: 0,
             commit_signatures: vec![],
+            commit_round: 0,
         }
     }
+    
+    /// Block hash (SHA-256, domain-separated)
+    ///
+    /// Covers canonical fields 1-10; `commit_signatures` and `commit_round`
+    /// are excluded.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        domain_hash(Self::DOMAIN_PREFIX, &enc.into_bytes())
+    }
+    
+    /// Encode canonical fields 1-10 (everything except the finality proof)
+    pub(crate) fn encode_unsigned(&self, enc: &mut Encoder) {
+        enc.put_u64(self.height);
+        enc.put_fixed(&self.previous_hash);
+        enc.put_u64(self.timestamp);
+        enc.put_fixed(&self.state_root);
+        enc.put_fixed(&self.transactions_root);
+        enc.put_str(&self.proposer_id);
+        enc.put_u64(self.round);
+        enc.put_str(&self.chain_id);
+        enc.put_u64(self.efficiency_score);
+        enc.put_u64(self.point_price);
+    }
 }
 
 /// PoAI v1 Block (spec-compliant)
@@ -130,6 +169,11 @@ impl Block {
         Self { header, transactions }
     }
     
+    /// Block hash (the header hash)
+    pub fn hash(&self) -> [u8; 32] {
+        self.header.hash()
+    }
+    
     /// Get block height
     pub fn height(&self) -> u64 {
         self.header.height
@@ -144,6 +188,64 @@ impl Block {
     pub fn tx_count(&self) -> usize {
         self.transactions.len()
     }
+    
+    /// Compute the Merkle root of this block's transactions
+    pub fn compute_transactions_root(&self) -> [u8; 32] {
+        merkle::transactions_root(&self.transactions)
+    }
+}
+
+impl Canonical for CommitSignature {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_str(&self.validator_id);
+        enc.put_fixed(&self.signature);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            validator_id: dec.get_string()?,
+            signature: dec.get_fixed()?,
+        })
+    }
+}
+
+impl Canonical for BlockHeader {
+    fn encode(&self, enc: &mut Encoder) {
+        self.encode_unsigned(enc);
+        enc.put_seq(&self.commit_signatures);
+        enc.put_u64(self.commit_round);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            height: dec.get_u64()?,
+            previous_hash: dec.get_fixed()?,
+            timestamp: dec.get_u64()?,
+            state_root: dec.get_fixed()?,
+            transactions_root: dec.get_fixed()?,
+            proposer_id: dec.get_string()?,
+            round: dec.get_u64()?,
+            chain_id: dec.get_string()?,
+            efficiency_score: dec.get_u64()?,
+            point_price: dec.get_u64()?,
+            commit_signatures: dec.get_seq()?,
+            commit_round: dec.get_u64()?,
+        })
+    }
+}
+
+impl Canonical for Block {
+    fn encode(&self, enc: &mut Encoder) {
+        self.header.encode(enc);
+        enc.put_seq(&self.transactions);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            header: BlockHeader::decode(dec)?,
+            transactions: dec.get_seq()?,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -164,6 +266,7 @@ mod tests {
             efficiency_score: 1000,
             point_price: 100,
             commit_signatures: vec![],
+            commit_round: 1,
         };
         
         assert_eq!(header.height, 1);
@@ -200,4 +303,73 @@ mod tests {
         assert_eq!(block.height(), 0);
         assert_eq!(block.tx_count(), 0);
     }
+    
+    #[test]
+    fn test_header_canonical_roundtrip() {
+        let mut header = BlockHeader::genesis("test-chain");
+        header.height = 9;
+        header.proposer_id = "builder-1".to_string();
+        header.commit_signatures.push(CommitSignature {
+            validator_id: "validator-1".to_string(),
+            signature: [7u8; 64],
+        });
+        header.commit_round = 2;
+        
+        let bytes = header.to_canonical_bytes();
+        assert_eq!(&bytes[..8], &9u64.to_le_bytes());
+        assert_eq!(BlockHeader::from_canonical_bytes(&bytes).unwrap(), header);
+    }
+    
+    #[test]
+    fn test_block_canonical_roundtrip() {
+        let tx = Transaction::new(1, "test-chain".to_string(), "a".to_string(), None, vec![1], 10, 5);
+        let block = Block::new(BlockHeader::genesis("test-chain"), vec![tx.clone(), tx]);
+        
+        let bytes = block.to_canonical_bytes();
+        assert_eq!(Block::from_canonical_bytes(&bytes).unwrap(), block);
+        
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Block::from_canonical_bytes(&trailing).is_err());
+    }
+    
+    #[test]
+    fn test_header_hash_excludes_commit_signatures() {
+        let mut header = BlockHeader::genesis("test-chain");
+        let hash = header.hash();
+        
+        header.commit_signatures.push(CommitSignature {
+            validator_id: "validator-1".to_string(),
+            signature: [1u8; 64],
+        });
+        header.commit_round = 3;
+        assert_eq!(header.hash(), hash);
+        
+        header.efficiency_score = 1;
+        assert_ne!(header.hash(), hash);
+    }
+    
+    #[test]
+    fn test_header_hash_is_domain_separated() {
+        use sha2::{Digest, Sha256};
+        
+        let header = BlockHeader::genesis("test-chain");
+        let mut enc = Encoder::new();
+        header.encode_unsigned(&mut enc);
+        let bytes = enc.into_bytes();
+        
+        let undomained: [u8; 32] = Sha256::digest(&bytes).into();
+        assert_ne!(header.hash(), undomained);
+        assert_eq!(header.hash(), domain_hash(b"self-chain-block-header-v1", &bytes));
+    }
+    
+    #[test]
+    fn test_compute_transactions_root() {
+        let tx = Transaction::new(1, "test-chain".to_string(), "a".to_string(), None, vec![], 10, 5);
+        let mut block = Block::new(BlockHeader::genesis("test-chain"), vec![]);
+        assert_eq!(block.compute_transactions_root(), merkle::EMPTY_ROOT);
+        
+        block.transactions.push(tx.clone());
+        assert_eq!(block.compute_transactions_root(), merkle::leaf_hash(&tx.hash()));
+    }
 }
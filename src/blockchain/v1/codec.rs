@@ -0,0 +1,380 @@
+//! PoAI v1 Canonical Encoding
+//!
+//! Deterministic binary encoding for all v1 wire types, following the
+//! "Canonical Encoding Order" documented on each type.
+//!
+//! ## Layout Rules
+//!
+//! The layout follows bincode's fixed-int little-endian conventions:
+//!
+//! | Value | Encoding |
+//! |-------|----------|
+//! | `u8` | 1 byte |
+//! | `u64` | 8 bytes, little-endian |
+//! | `[u8; N]` | N raw bytes (no length prefix) |
+//! | `String` / `Vec<u8>` | `u64` length prefix, then the bytes |
+//! | `Vec<T>` | `u64` element count, then each element |
+//! | `Option<T>` | `u8` tag (0 = None, 1 = Some), then the value |
+//!
+//! ## Canonical Decoding
+//!
+//! Decoding is strict: every byte sequence decodes to at most one value, and
+//! re-encoding a decoded value yields the original bytes. Truncated input,
+//! trailing bytes, invalid UTF-8 and unknown tags are all rejected.
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Errors returned when decoding canonical bytes
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CodecError {
+    #[error("Unexpected end of input: needed {needed} bytes, {remaining} remaining")]
+    UnexpectedEof { needed: usize, remaining: usize },
+
+    #[error("Trailing bytes after canonical encoding: {0}")]
+    TrailingBytes(usize),
+
+    #[error("Length prefix {0} exceeds remaining input")]
+    LengthOverflow(u64),
+
+    #[error("Invalid UTF-8 in string field")]
+    InvalidUtf8,
+
+    #[error("Invalid {field} tag: {tag}")]
+    InvalidTag { field: &'static str, tag: u8 },
+
+    #[error("Non-canonical encoding: {0}")]
+    NonCanonical(String),
+}
+
+/// Result type for canonical decoding
+pub type CodecResult<T> = Result<T, CodecError>;
+
+/// Types with a canonical v1 binary encoding
+///
+/// ```rust,ignore
+/// use self_chain_core::blockchain::v1::{Canonical, Transaction};
+///
+/// let bytes = tx.to_canonical_bytes();
+/// let decoded = Transaction::from_canonical_bytes(&bytes)?;
+/// assert_eq!(tx, decoded);
+/// ```
+pub trait Canonical: Sized {
+    /// Append the canonical encoding of `self` to the encoder
+    fn encode(&self, enc: &mut Encoder);
+
+    /// Read one value from the decoder
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self>;
+
+    /// Encode to a fresh byte vector
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut enc = Encoder::new();
+        self.encode(&mut enc);
+        enc.into_bytes()
+    }
+
+    /// Decode from bytes, rejecting trailing data
+    fn from_canonical_bytes(bytes: &[u8]) -> CodecResult<Self> {
+        let mut dec = Decoder::new(bytes);
+        let value = Self::decode(&mut dec)?;
+        dec.finish()?;
+        Ok(value)
+    }
+}
+
+/// Canonical byte writer
+#[derive(Debug, Default, Clone)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    /// Create an empty encoder
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    /// Write a single byte
+    pub fn put_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    /// Write a little-endian `u64`
+    pub fn put_u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Write raw bytes without a length prefix (fixed-size arrays)
+    pub fn put_fixed(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Write a length-prefixed byte string
+    pub fn put_bytes(&mut self, bytes: &[u8]) {
+        self.put_u64(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Write a length-prefixed UTF-8 string
+    pub fn put_str(&mut self, value: &str) {
+        self.put_bytes(value.as_bytes());
+    }
+
+    /// Write a boolean as a single byte (0 or 1)
+    pub fn put_bool(&mut self, value: bool) {
+        self.put_u8(value as u8);
+    }
+
+    /// Write an optional value with a presence tag
+    pub fn put_option<T: Canonical>(&mut self, value: Option<&T>) {
+        match value {
+            None => self.put_u8(0),
+            Some(v) => {
+                self.put_u8(1);
+                v.encode(self);
+            }
+        }
+    }
+
+    /// Write an element count followed by each element
+    pub fn put_seq<T: Canonical>(&mut self, items: &[T]) {
+        self.put_u64(items.len() as u64);
+        for item in items {
+            item.encode(self);
+        }
+    }
+
+    /// Number of bytes written so far
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether nothing has been written yet
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Consume the encoder and return the bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Strict canonical byte reader
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Create a decoder over the given input
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    /// Bytes not yet consumed
+    pub fn remaining(&self) -> usize {
+        self.input.len() - self.pos
+    }
+
+    fn take(&mut self, n: usize) -> CodecResult<&'a [u8]> {
+        if n > self.remaining() {
+            return Err(CodecError::UnexpectedEof {
+                needed: n,
+                remaining: self.remaining(),
+            });
+        }
+        let bytes = &self.input[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    /// Read a single byte
+    pub fn get_u8(&mut self) -> CodecResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Read a little-endian `u64`
+    pub fn get_u64(&mut self) -> CodecResult<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Read a fixed-size byte array
+    pub fn get_fixed<const N: usize>(&mut self) -> CodecResult<[u8; N]> {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+
+    /// Read a length prefix, checking it against the remaining input
+    ///
+    /// Every element occupies at least one byte, so a count larger than the
+    /// remaining input can never be valid. Checking up front also prevents
+    /// oversized allocations from hostile length prefixes.
+    fn get_len(&mut self) -> CodecResult<usize> {
+        let len = self.get_u64()?;
+        if len > self.remaining() as u64 {
+            return Err(CodecError::LengthOverflow(len));
+        }
+        Ok(len as usize)
+    }
+
+    /// Read a length-prefixed byte string
+    pub fn get_bytes(&mut self) -> CodecResult<Vec<u8>> {
+        let len = self.get_len()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    /// Read a length-prefixed UTF-8 string
+    pub fn get_string(&mut self) -> CodecResult<String> {
+        String::from_utf8(self.get_bytes()?).map_err(|_| CodecError::InvalidUtf8)
+    }
+
+    /// Read a boolean, rejecting anything other than 0 or 1
+    pub fn get_bool(&mut self) -> CodecResult<bool> {
+        match self.get_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(CodecError::InvalidTag { field: "bool", tag }),
+        }
+    }
+
+    /// Read an optional value, rejecting unknown presence tags
+    pub fn get_option<T: Canonical>(&mut self) -> CodecResult<Option<T>> {
+        match self.get_u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(self)?)),
+            tag => Err(CodecError::InvalidTag { field: "option", tag }),
+        }
+    }
+
+    /// Read an element count followed by each element
+    pub fn get_seq<T: Canonical>(&mut self) -> CodecResult<Vec<T>> {
+        let len = self.get_len()?;
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(T::decode(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Ensure all input was consumed
+    pub fn finish(self) -> CodecResult<()> {
+        match self.remaining() {
+            0 => Ok(()),
+            n => Err(CodecError::TrailingBytes(n)),
+        }
+    }
+}
+
+/// SHA-256 over `prefix || bytes` (domain-separated hash)
+pub fn domain_hash(prefix: &[u8], bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(prefix);
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+impl Canonical for u64 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_u64(*self);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        dec.get_u64()
+    }
+}
+
+impl Canonical for String {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_str(self);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        dec.get_string()
+    }
+}
+
+impl Canonical for [u8; 32] {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_fixed(self);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        dec.get_fixed()
+    }
+}
+
+/// Serde support for `Canonical` types, as their canonical bytes
+///
+/// Use with `#[serde(with = "crate::blockchain::v1::codec::serde_canonical")]`.
+/// Deserializing rejects any bytes `from_canonical_bytes` rejects.
+pub mod serde_canonical {
+    use super::Canonical;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: Canonical, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(&value.to_canonical_bytes())
+    }
+
+    pub fn deserialize<'de, T: Canonical, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let bytes = Vec::<u8>::deserialize(d)?;
+        T::from_canonical_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_layout() {
+        let mut enc = Encoder::new();
+        enc.put_u64(0x0102030405060708);
+        enc.put_str("ab");
+
+        assert_eq!(
+            enc.into_bytes(),
+            vec![8, 7, 6, 5, 4, 3, 2, 1, 2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']
+        );
+    }
+
+    #[test]
+    fn test_trailing_bytes_rejected() {
+        let mut bytes = 42u64.to_canonical_bytes();
+        bytes.push(0);
+
+        assert_eq!(u64::from_canonical_bytes(&bytes), Err(CodecError::TrailingBytes(1)));
+    }
+
+    #[test]
+    fn test_oversized_length_rejected() {
+        let bytes = u64::MAX.to_le_bytes();
+        assert_eq!(
+            String::from_canonical_bytes(&bytes),
+            Err(CodecError::LengthOverflow(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_invalid_option_tag_rejected() {
+        let mut dec = Decoder::new(&[2]);
+        assert_eq!(
+            dec.get_option::<u64>(),
+            Err(CodecError::InvalidTag { field: "option", tag: 2 })
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8_rejected() {
+        let mut enc = Encoder::new();
+        enc.put_bytes(&[0xff, 0xfe]);
+        assert_eq!(
+            String::from_canonical_bytes(&enc.into_bytes()),
+            Err(CodecError::InvalidUtf8)
+        );
+    }
+}
@@ -21,20 +21,42 @@
 //!
 //! ## Wire Format
 //!
-//! All types use **bincode** serialization with deterministic field ordering.
+//! All types use the canonical binary encoding in `codec`: fixed-width
+//! little-endian integers, length-prefixed strings and vectors, and tagged
+//! options, written in each type's documented field order. The
+//! [`Canonical`] trait provides `to_canonical_bytes()` and
+//! `from_canonical_bytes()` for every wire type.
+//!
 //! Signature domain separation prefixes:
 //! - Block: `"self-chain-block-header-v1"`
 //! - Transaction: `"self-chain-transaction-v1"`
 //! - Prevote: `"self-chain-vote-prevote-v1"`
 //! - Precommit: `"self-chain-vote-precommit-v1"`
 //! - Proposal: `"self-chain-proposal-v1"`
+//! - Ranked vote: `"self-chain-ranked-vote-v1"`
+//!
+//! ## Hashing
+//!
+//! Every type exposes `hash()`, computed as
+//! `SHA256(DOMAIN_PREFIX || canonical_bytes_without_signatures)`. Signature
+//! fields are never part of a hash, so a message's identity is fixed before
+//! it is signed.
+//!
+//! ## Signatures
+//!
+//! `sign()` / `verify_signature()` use Ed25519 over
+//! `DOMAIN_PREFIX || payload` (see `signing`). Verification failures return
+//! `consensus::v1::ConsensusError::InvalidSignature`.
 
 pub mod block;
+pub mod codec;
 pub mod transaction;
 pub mod vote;
 pub mod proposal;
+mod signing;
 
 pub use block::{Block, BlockHeader, CommitSignature};
+pub use codec::{domain_hash, Canonical, CodecError, CodecResult, Decoder, Encoder};
 pub use transaction::Transaction;
-pub use vote::{Vote, VoteStep};
-pub use proposal::BlockProposal;
+pub use vote::{RankedVote, Vote, VoteStep};
+pub use proposal::{BlockProposal, ValidatedProposal};
@@ -17,6 +17,11 @@
 //! └─────────────────────────────────────────────────────────────┘
 //! ```
 
+use crate::blockchain::v1::codec::{domain_hash, Canonical, CodecResult, Decoder, Encoder};
+use crate::blockchain::v1::signing::{prefixed, sign_message, verify_message};
+use crate::consensus::v1::ConsensusResult;
+use crate::crypto::common::traits::Signer;
+use crate::crypto::CryptoResult;
 use crate::blockchain::v1::Block;
 
 /// PoAI v1 Block Proposal (spec-compliant)
@@ -41,6 +46,14 @@ use crate::blockchain::v1::Block;
 /// ```
 ///
 /// Validators independently verify this calculation matches the claimed score.
+///
+/// ## Canonical Encoding Order
+///
+/// 1. `height` (u64, little-endian)
+/// 2. `round` (u64, little-endian)
+/// 3. `proposer_id` (string, UTF-8, length-prefixed)
+/// 4. `block` (canonical block: header, then length-prefixed transactions)
+/// 5. `signature` (64 bytes)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BlockProposal {
     /// Block height
@@ -83,6 +96,63 @@ impl BlockProposal {
     pub fn tx_count(&self) -> usize {
         self.block.transactions.len()
     }
+    
+    /// Proposal hash: `SHA256(DOMAIN_PREFIX || canonical fields 1-4)`
+    ///
+    /// Distinct from the block hash; identifies this signed submission.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        domain_hash(Self::DOMAIN_PREFIX, &enc.into_bytes())
+    }
+    
+    /// Message covered by the proposer's signature
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        prefixed(Self::DOMAIN_PREFIX, &enc.into_bytes())
+    }
+    
+    /// Sign with the proposer's Ed25519 key
+    pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> CryptoResult<()> {
+        self.signature = sign_message(signer, &self.signing_message())?;
+        Ok(())
+    }
+    
+    /// Verify the signature against the proposer's public key
+    pub fn verify_signature(&self, public_key: &[u8; 32]) -> ConsensusResult<()> {
+        verify_message(
+            public_key,
+            &self.signing_message(),
+            &self.signature,
+            &format!("proposal from {}", self.proposer_id),
+        )
+    }
+    
+    /// Encode canonical fields 1-4 (everything except the signature)
+    pub(crate) fn encode_unsigned(&self, enc: &mut Encoder) {
+        enc.put_u64(self.height);
+        enc.put_u64(self.round);
+        enc.put_str(&self.proposer_id);
+        self.block.encode(enc);
+    }
+}
+
+impl Canonical for BlockProposal {
+    fn encode(&self, enc: &mut Encoder) {
+        self.encode_unsigned(enc);
+        enc.put_fixed(&self.signature);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            height: dec.get_u64()?,
+            round: dec.get_u64()?,
+            proposer_id: dec.get_string()?,
+            block: Block::decode(dec)?,
+            signature: dec.get_fixed()?,
+        })
+    }
 }
 
 /// Validated proposal ready for voting
@@ -148,6 +218,7 @@ mod tests {
             efficiency_score,
             point_price: 100,
             commit_signatures: vec![],
+            commit_round: 0,
         };
         
         Block::new(header, vec![])
@@ -206,4 +277,38 @@ mod tests {
         let validated_mismatch = ValidatedProposal::new(proposal, 4999, 4000);
         assert!(!validated_mismatch.efficiency_matches_claim());
     }
+    
+    #[test]
+    fn test_proposal_canonical_roundtrip() {
+        let mut proposal = BlockProposal::new(1, 0, "builder-1".to_string(), create_test_block(5000));
+        proposal.signature = [9u8; 64];
+        
+        let bytes = proposal.to_canonical_bytes();
+        assert_eq!(BlockProposal::from_canonical_bytes(&bytes).unwrap(), proposal);
+        assert!(BlockProposal::from_canonical_bytes(&bytes[..bytes.len() - 64]).is_err());
+    }
+    
+    #[test]
+    fn test_proposal_hash_differs_from_block_hash() {
+        let proposal = BlockProposal::new(1, 0, "builder-1".to_string(), create_test_block(5000));
+        
+        assert_ne!(proposal.hash(), proposal.block.hash());
+        
+        let mut signed = proposal.clone();
+        signed.signature = [1u8; 64];
+        assert_eq!(signed.hash(), proposal.hash());
+    }
+    
+    #[test]
+    fn test_proposal_sign_and_verify() {
+        use crate::crypto::{Ed25519Keys, KeyPair};
+        
+        let keys = Ed25519Keys::new().unwrap();
+        let other = Ed25519Keys::new().unwrap();
+        let mut proposal = BlockProposal::new(1, 0, "builder-1".to_string(), create_test_block(5000));
+        proposal.sign(&keys).unwrap();
+        
+        assert!(proposal.verify_signature(&keys.public_key_bytes()).is_ok());
+        assert!(proposal.verify_signature(&other.public_key_bytes()).is_err());
+    }
 }
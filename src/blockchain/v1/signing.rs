@@ -0,0 +1,62 @@
+//! PoAI v1 Message Signing
+//!
+//! Ed25519 signing and verification shared by all v1 wire types.
+//!
+//! ## Signed Messages
+//!
+//! ```text
+//! Transaction:  "self-chain-transaction-v1"   || tx_hash
+//! Proposal:     "self-chain-proposal-v1"      || canonical(proposal_without_signature)
+//! Vote:         "self-chain-vote-<step>-v1"   || canonical(vote_without_signature)
+//! RankedVote:   "self-chain-ranked-vote-v1"   || canonical(vote_without_signature)
+//! ```
+//!
+//! Signing accepts any `crypto::Signer` whose algorithm is Ed25519 (normally
+//! `crypto::Ed25519Keys`), so browser and hardware signers can plug in through
+//! the same trait.
+
+use crate::consensus::v1::{ConsensusError, ConsensusResult};
+use crate::crypto::common::traits::{Signer, Verifier};
+use crate::crypto::{CryptoAlgorithm, CryptoError, CryptoResult, Ed25519Keys};
+
+/// Sign a domain-prefixed message, returning the 64-byte Ed25519 signature
+pub(crate) fn sign_message<S: Signer + ?Sized>(signer: &S, message: &[u8]) -> CryptoResult<[u8; 64]> {
+    if signer.algorithm_id() != CryptoAlgorithm::Ed25519 as u8 {
+        return Err(CryptoError::InvalidAlgorithm(format!(
+            "v1 messages require Ed25519, got algorithm {}",
+            signer.algorithm_id()
+        )));
+    }
+
+    signer
+        .sign(message)?
+        .try_into()
+        .map_err(|_| CryptoError::InvalidSignatureFormat("Ed25519 signature must be 64 bytes".into()))
+}
+
+/// Verify an Ed25519 signature over a domain-prefixed message
+///
+/// `what` names the message in the error (e.g. `"ranked vote from validator-1"`).
+pub(crate) fn verify_message(
+    public_key: &[u8; 32],
+    message: &[u8],
+    signature: &[u8; 64],
+    what: &str,
+) -> ConsensusResult<()> {
+    let keys = Ed25519Keys::from_public_key(public_key)
+        .map_err(|e| ConsensusError::InvalidSignature(format!("{}: {}", what, e)))?;
+
+    match keys.verify(message, signature) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ConsensusError::InvalidSignature(what.to_string())),
+        Err(e) => Err(ConsensusError::InvalidSignature(format!("{}: {}", what, e))),
+    }
+}
+
+/// Concatenate a domain prefix and payload into a signing message
+pub(crate) fn prefixed(prefix: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(prefix.len() + payload.len());
+    message.extend_from_slice(prefix);
+    message.extend_from_slice(payload);
+    message
+}
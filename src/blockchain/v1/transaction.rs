@@ -12,6 +12,12 @@
 //! The signature and public_key fields are excluded from the hash to allow
 //! signature verification.
 
+use crate::blockchain::v1::codec::{domain_hash, Canonical, CodecResult, Decoder, Encoder};
+use crate::blockchain::v1::signing::{prefixed, sign_message, verify_message};
+use crate::consensus::v1::ConsensusResult;
+use crate::crypto::common::traits::{KeyPair, Signer};
+use crate::crypto::{CryptoError, CryptoResult};
+
 /// PoAI v1 Transaction (spec-compliant)
 ///
 /// This is the canonical transaction format for the v1 protocol.
@@ -21,7 +27,7 @@
 /// 1. `nonce` (u64, little-endian)
 /// 2. `chain_id` (string, UTF-8, length-prefixed)
 /// 3. `sender` (string, hex, length-prefixed)
-/// 4. `recipient` (Option<string>, hex, length-prefixed, None = empty)
+/// 4. `recipient` (Option<string>, hex, u8 presence tag then length-prefixed)
 /// 5. `data` (length-prefixed bytes)
 /// 6. `point_price` (u64, little-endian)
 /// 7. `timestamp` (u64, little-endian)
@@ -118,6 +124,50 @@ impl Transaction {
         self.recipient.is_some()
     }
     
+    /// Transaction hash (SHA-256, domain-separated)
+    ///
+    /// Covers canonical fields 1-7; `public_key` and `signature` are excluded.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        domain_hash(Self::DOMAIN_PREFIX, &enc.into_bytes())
+    }
+    
+    /// Message covered by the sender's signature: `DOMAIN_PREFIX || tx_hash`
+    pub fn signing_message(&self) -> Vec<u8> {
+        prefixed(Self::DOMAIN_PREFIX, &self.hash())
+    }
+    
+    /// Sign with the sender's Ed25519 key, filling `public_key` and `signature`
+    pub fn sign<K: KeyPair + Signer>(&mut self, keys: &K) -> CryptoResult<()> {
+        self.public_key = keys.public_key().try_into().map_err(|_| {
+            CryptoError::InvalidKeyFormat("Ed25519 public key must be 32 bytes".into())
+        })?;
+        self.signature = sign_message(keys, &self.signing_message())?;
+        Ok(())
+    }
+    
+    /// Verify `signature` against the embedded `public_key`
+    pub fn verify_signature(&self) -> ConsensusResult<()> {
+        verify_message(
+            &self.public_key,
+            &self.signing_message(),
+            &self.signature,
+            &format!("transaction from {}", self.sender),
+        )
+    }
+    
+    /// Encode canonical fields 1-7 (everything except key and signature)
+    pub(crate) fn encode_unsigned(&self, enc: &mut Encoder) {
+        enc.put_u64(self.nonce);
+        enc.put_str(&self.chain_id);
+        enc.put_str(&self.sender);
+        enc.put_option(self.recipient.as_ref());
+        enc.put_bytes(&self.data);
+        enc.put_u64(self.point_price);
+        enc.put_u64(self.timestamp);
+    }
+    
     /// Get estimated size in bytes
     pub fn estimated_size(&self) -> usize {
         // Fixed fields: nonce(8) + timestamp(8) + point_price(8) + pubkey(32) + sig(64) = 120
@@ -129,6 +179,33 @@ impl Transaction {
     }
 }
 
+impl Canonical for Transaction {
+    fn encode(&self, enc: &mut Encoder) {
+        self.encode_unsigned(enc);
+        enc.put_fixed(&self.public_key);
+        enc.put_fixed(&self.signature);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        let nonce = dec.get_u64()?;
+        let chain_id = dec.get_string()?;
+        let sender = dec.get_string()?;
+        let recipient = dec.get_option()?;
+
+        Ok(Self {
+            nonce,
+            chain_id,
+            sender,
+            recipient,
+            data: dec.get_bytes()?,
+            point_price: dec.get_u64()?,
+            timestamp: dec.get_u64()?,
+            public_key: dec.get_fixed()?,
+            signature: dec.get_fixed()?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,4 +280,98 @@ mod tests {
         // 120 (fixed) + 5 + 6 + 9 + 5 = 145
         assert_eq!(tx.estimated_size(), 145);
     }
+    
+    #[test]
+    fn test_canonical_roundtrip() {
+        let mut tx = Transaction::new(
+            7,
+            "test-chain".to_string(),
+            "sender".to_string(),
+            Some("recipient".to_string()),
+            vec![9, 8, 7],
+            250,
+            1704067200,
+        );
+        tx.public_key = [3u8; 32];
+        tx.signature = [4u8; 64];
+        
+        let bytes = tx.to_canonical_bytes();
+        // 8 + (8+10) + (8+6) + (1+8+9) + (8+3) + 8 + 8 + 32 + 64
+        assert_eq!(bytes.len(), 181);
+        assert_eq!(&bytes[..8], &7u64.to_le_bytes());
+        assert_eq!(Transaction::from_canonical_bytes(&bytes).unwrap(), tx);
+    }
+    
+    #[test]
+    fn test_canonical_recipient_is_tagged() {
+        let tx = Transaction::new(0, "c".to_string(), "s".to_string(), None, vec![], 1, 1);
+        let bytes = tx.to_canonical_bytes();
+        
+        // None is a single zero tag
+        assert_eq!(bytes[8 + 9 + 9], 0);
+        assert_eq!(Transaction::from_canonical_bytes(&bytes).unwrap().recipient, None);
+        
+        // An empty recipient stays distinct from None
+        let mut empty = tx.clone();
+        empty.recipient = Some(String::new());
+        assert_ne!(empty.hash(), tx.hash());
+        let decoded = Transaction::from_canonical_bytes(&empty.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded.recipient, Some(String::new()));
+    }
+    
+    #[test]
+    fn test_canonical_rejects_truncated() {
+        let tx = Transaction::new(0, "c".to_string(), "s".to_string(), None, vec![], 1, 1);
+        let bytes = tx.to_canonical_bytes();
+        
+        assert!(Transaction::from_canonical_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+    
+    #[test]
+    fn test_hash_excludes_key_and_signature() {
+        let mut tx = Transaction::new(1, "c".to_string(), "s".to_string(), None, vec![], 5, 1);
+        let unsigned_hash = tx.hash();
+        
+        tx.public_key = [1u8; 32];
+        tx.signature = [2u8; 64];
+        assert_eq!(tx.hash(), unsigned_hash);
+        
+        tx.nonce = 2;
+        assert_ne!(tx.hash(), unsigned_hash);
+    }
+    
+    #[test]
+    fn test_hash_spec_vector() {
+        // Test vector from docs/POAI_SPECIFICATION.md
+        let tx = Transaction::new(
+            1,
+            "self-chain-mainnet".to_string(),
+            "a1b2c3d4e5f6".to_string(),
+            Some("f6e5d4c3b2a1".to_string()),
+            vec![],
+            1000,
+            1704067200,
+        );
+        
+        assert_eq!(hex::encode(tx.hash()), "e1289d1a89e3e93149def31cd3a884f8da487c464de6caae55cf7ec872914128");
+    }
+    
+    #[test]
+    fn test_sign_and_verify() {
+        use crate::consensus::v1::ConsensusError;
+        use crate::crypto::Ed25519Keys;
+        
+        let keys = Ed25519Keys::new().unwrap();
+        let mut tx = Transaction::new(1, "c".to_string(), "s".to_string(), None, vec![], 5, 1);
+        tx.sign(&keys).unwrap();
+        
+        assert_eq!(tx.public_key.as_slice(), keys.public_key());
+        assert!(tx.verify_signature().is_ok());
+        
+        tx.point_price = 6;
+        assert!(matches!(
+            tx.verify_signature(),
+            Err(ConsensusError::InvalidSignature(_))
+        ));
+    }
 }
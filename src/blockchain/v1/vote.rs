@@ -11,6 +11,14 @@
 //!
 //! This module defines the wire format for both.
 
+use crate::blockchain::v1::codec::{
+    domain_hash, Canonical, CodecError, CodecResult, Decoder, Encoder,
+};
+use crate::blockchain::v1::signing::{prefixed, sign_message, verify_message};
+use crate::consensus::v1::ConsensusResult;
+use crate::crypto::common::traits::Signer;
+use crate::crypto::CryptoResult;
+
 /// Vote step in Tendermint-style consensus
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -31,6 +39,17 @@ impl VoteStep {
     }
 }
 
+impl VoteStep {
+    /// Parse a vote step from its wire byte
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(VoteStep::Prevote),
+            2 => Some(VoteStep::Precommit),
+            _ => None,
+        }
+    }
+}
+
 /// PoAI v1 Vote (spec-compliant)
 ///
 /// Used in Tendermint-style consensus for Prevote/Precommit phases.
@@ -130,6 +149,45 @@ impl Vote {
     pub fn domain_prefix(&self) -> &'static [u8] {
         self.step.domain_prefix()
     }
+    
+    /// Vote hash: `SHA256(step_prefix || canonical fields 1-5)`
+    pub fn hash(&self) -> [u8; 32] {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        domain_hash(self.domain_prefix(), &enc.into_bytes())
+    }
+    
+    /// Message covered by the validator's signature (step-specific prefix)
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        prefixed(self.domain_prefix(), &enc.into_bytes())
+    }
+    
+    /// Sign with the validator's Ed25519 key
+    pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> CryptoResult<()> {
+        self.signature = sign_message(signer, &self.signing_message())?;
+        Ok(())
+    }
+    
+    /// Verify the signature against the validator's public key
+    pub fn verify_signature(&self, public_key: &[u8; 32]) -> ConsensusResult<()> {
+        verify_message(
+            public_key,
+            &self.signing_message(),
+            &self.signature,
+            &format!("{:?} from {}", self.step, self.validator_id),
+        )
+    }
+    
+    /// Encode canonical fields 1-5 (everything except the signature)
+    pub(crate) fn encode_unsigned(&self, enc: &mut Encoder) {
+        enc.put_u64(self.height);
+        enc.put_u64(self.round);
+        enc.put_u8(self.step as u8);
+        enc.put_fixed(&self.block_hash);
+        enc.put_str(&self.validator_id);
+    }
 }
 
 /// PoAI Ranked Vote (competition model)
@@ -144,6 +202,15 @@ impl Vote {
 /// | Vote content | Yes/No/Nil | Block hash of best proposal |
 /// | Rounds | Prevote → Precommit | Single round |
 /// | Selection | First valid block | Highest efficiency |
+///
+/// ## Canonical Encoding Order
+///
+/// 1. `height` (u64, little-endian)
+/// 2. `round` (u64, little-endian)
+/// 3. `block_hash` (32 bytes)
+/// 4. `efficiency_score` (u64, little-endian)
+/// 5. `validator_id` (string, UTF-8, length-prefixed)
+/// 6. `signature` (64 bytes)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RankedVote {
     /// Block height
@@ -186,6 +253,89 @@ impl RankedVote {
             signature: [0u8; 64],
         }
     }
+    
+    /// Ranked vote hash: `SHA256(DOMAIN_PREFIX || canonical fields 1-5)`
+    pub fn hash(&self) -> [u8; 32] {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        domain_hash(Self::DOMAIN_PREFIX, &enc.into_bytes())
+    }
+    
+    /// Message covered by the validator's signature
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut enc = Encoder::new();
+        self.encode_unsigned(&mut enc);
+        prefixed(Self::DOMAIN_PREFIX, &enc.into_bytes())
+    }
+    
+    /// Sign with the validator's Ed25519 key
+    pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> CryptoResult<()> {
+        self.signature = sign_message(signer, &self.signing_message())?;
+        Ok(())
+    }
+    
+    /// Verify the signature against the validator's public key
+    ///
+    /// Typically called with `ValidatorInfo.public_key` for `validator_id`.
+    pub fn verify_signature(&self, public_key: &[u8; 32]) -> ConsensusResult<()> {
+        verify_message(
+            public_key,
+            &self.signing_message(),
+            &self.signature,
+            &format!("ranked vote from {}", self.validator_id),
+        )
+    }
+    
+    /// Encode canonical fields 1-5 (everything except the signature)
+    pub(crate) fn encode_unsigned(&self, enc: &mut Encoder) {
+        enc.put_u64(self.height);
+        enc.put_u64(self.round);
+        enc.put_fixed(&self.block_hash);
+        enc.put_u64(self.efficiency_score);
+        enc.put_str(&self.validator_id);
+    }
+}
+
+impl Canonical for Vote {
+    fn encode(&self, enc: &mut Encoder) {
+        self.encode_unsigned(enc);
+        enc.put_fixed(&self.signature);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        let height = dec.get_u64()?;
+        let round = dec.get_u64()?;
+        let tag = dec.get_u8()?;
+        let step = VoteStep::from_u8(tag)
+            .ok_or(CodecError::InvalidTag { field: "vote step", tag })?;
+
+        Ok(Self {
+            height,
+            round,
+            step,
+            block_hash: dec.get_fixed()?,
+            validator_id: dec.get_string()?,
+            signature: dec.get_fixed()?,
+        })
+    }
+}
+
+impl Canonical for RankedVote {
+    fn encode(&self, enc: &mut Encoder) {
+        self.encode_unsigned(enc);
+        enc.put_fixed(&self.signature);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            height: dec.get_u64()?,
+            round: dec.get_u64()?,
+            block_hash: dec.get_fixed()?,
+            efficiency_score: dec.get_u64()?,
+            validator_id: dec.get_string()?,
+            signature: dec.get_fixed()?,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -237,4 +387,86 @@ mod tests {
         assert_eq!(ranked.efficiency_score, 5000);
         assert_eq!(ranked.block_hash, [0xAB; 32]);
     }
+    
+    #[test]
+    fn test_vote_canonical_layout() {
+        let vote = Vote::precommit(1, 2, [0xAA; 32], "v".to_string());
+        let bytes = vote.to_canonical_bytes();
+        
+        // height(8) + round(8) + step(1) + hash(32) + id(8+1) + sig(64)
+        assert_eq!(bytes.len(), 122);
+        assert_eq!(bytes[16], 2);
+        assert_eq!(Vote::from_canonical_bytes(&bytes).unwrap(), vote);
+    }
+    
+    #[test]
+    fn test_vote_invalid_step_rejected() {
+        let mut bytes = Vote::prevote(1, 0, [0u8; 32], "v".to_string()).to_canonical_bytes();
+        bytes[16] = 3;
+        
+        assert_eq!(
+            Vote::from_canonical_bytes(&bytes),
+            Err(CodecError::InvalidTag { field: "vote step", tag: 3 })
+        );
+    }
+    
+    #[test]
+    fn test_ranked_vote_canonical_roundtrip() {
+        let ranked = RankedVote::new(3, 1, [0xCD; 32], 4200, "validator-9".to_string());
+        let bytes = ranked.to_canonical_bytes();
+        
+        assert_eq!(RankedVote::from_canonical_bytes(&bytes).unwrap(), ranked);
+    }
+    
+    #[test]
+    fn test_vote_hash_depends_on_step() {
+        let prevote = Vote::prevote(1, 0, [1u8; 32], "v".to_string());
+        let precommit = Vote::precommit(1, 0, [1u8; 32], "v".to_string());
+        
+        assert_ne!(prevote.hash(), precommit.hash());
+    }
+    
+    #[test]
+    fn test_ranked_vote_hash_excludes_signature() {
+        let mut ranked = RankedVote::new(1, 0, [1u8; 32], 10, "v".to_string());
+        let hash = ranked.hash();
+        
+        ranked.signature = [5u8; 64];
+        assert_eq!(ranked.hash(), hash);
+    }
+    
+    #[test]
+    fn test_vote_sign_and_verify() {
+        use crate::crypto::{Ed25519Keys, KeyPair};
+        
+        let keys = Ed25519Keys::new().unwrap();
+        let mut vote = Vote::precommit(1, 0, [1u8; 32], "v".to_string());
+        vote.sign(&keys).unwrap();
+        assert!(vote.verify_signature(&keys.public_key_bytes()).is_ok());
+        
+        // A precommit signature must not verify as a prevote
+        vote.step = VoteStep::Prevote;
+        assert!(vote.verify_signature(&keys.public_key_bytes()).is_err());
+    }
+    
+    #[test]
+    fn test_ranked_vote_sign_and_verify() {
+        use crate::consensus::v1::ConsensusError;
+        use crate::crypto::{ECDSAKeys, Ed25519Keys, KeyPair};
+        
+        let keys = Ed25519Keys::new().unwrap();
+        let mut ranked = RankedVote::new(1, 0, [1u8; 32], 10, "v".to_string());
+        ranked.sign(&keys).unwrap();
+        assert!(ranked.verify_signature(&keys.public_key_bytes()).is_ok());
+        
+        ranked.efficiency_score = 11;
+        assert!(matches!(
+            ranked.verify_signature(&keys.public_key_bytes()),
+            Err(ConsensusError::InvalidSignature(_))
+        ));
+        
+        // Non-Ed25519 signers are rejected
+        let ecdsa = ECDSAKeys::new().unwrap();
+        assert!(ranked.sign(&ecdsa).is_err());
+    }
 }
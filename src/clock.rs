@@ -0,0 +1,124 @@
+//! # Clock
+//!
+//! Wall-clock time source shared by consensus, key management and nodes.
+//!
+//! ## Implementations
+//!
+//! - `SystemClock`: reads `SystemTime::now()`
+//! - `MockClock`: starts at a fixed time and only moves when `advance` or
+//!   `set` is called; clones share the same time, so a test can keep one
+//!   handle and hand another to the component under test
+//!
+//! ## Injection
+//!
+//! Components hold a `SharedClock` and take it through a `with_clock`
+//! constructor. The plain constructors use `system_clock()`.
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of the current wall-clock time
+pub trait Clock: Send + Sync + Debug {
+    /// Time elapsed since the Unix epoch
+    fn now(&self) -> Duration;
+
+    /// Whole seconds since the Unix epoch
+    fn unix_secs(&self) -> u64 {
+        self.now().as_secs()
+    }
+
+    /// Whole milliseconds since the Unix epoch
+    fn unix_millis(&self) -> u64 {
+        self.now().as_millis() as u64
+    }
+}
+
+/// Clock handle shared between components
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Shared handle to the system clock
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Clock backed by the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        // A clock set before 1970 reads as the epoch rather than panicking
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// Manually advanced clock for tests and simulations
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    /// Create a clock reading `start` since the Unix epoch
+    pub fn new(start: Duration) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Create a clock reading `secs` seconds since the Unix epoch
+    pub fn from_unix_secs(secs: u64) -> Self {
+        Self::new(Duration::from_secs(secs))
+    }
+
+    /// Move the clock forward by `by`
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().expect("mock clock poisoned");
+        *now += by;
+    }
+
+    /// Set the clock to `to` since the Unix epoch, forwards or backwards
+    pub fn set(&self, to: Duration) {
+        *self.now.lock().expect("mock clock poisoned") = to;
+    }
+
+    /// Shared handle to this clock
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().expect("mock clock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_moves_only_when_told() {
+        let clock = MockClock::from_unix_secs(1_704_067_200);
+        let shared = clock.shared();
+        assert_eq!(shared.unix_secs(), 1_704_067_200);
+
+        clock.advance(Duration::from_millis(1_500));
+        assert_eq!(shared.unix_secs(), 1_704_067_201);
+        assert_eq!(shared.unix_millis(), 1_704_067_201_500);
+
+        clock.set(Duration::from_secs(10));
+        assert_eq!(shared.unix_secs(), 10);
+    }
+
+    #[test]
+    fn test_system_clock_is_after_epoch() {
+        let clock = system_clock();
+        // 2024-01-01
+        assert!(clock.unix_secs() > 1_704_067_200);
+    }
+}
@@ -1,9 +1,9 @@
 
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
 use std::num::NonZeroUsize;
 use serde::{Serialize, Deserialize};
 use crate::blockchain::{Block, Transaction};
+use crate::clock::{system_clock, SharedClock};
 
 use crate::consensus::metrics::ConsensusMetrics;
 use anyhow::Result;
@@ -32,10 +32,16 @@ pub struct ValidationCache {
     color_cache: Arc<RwLock<LruCache<String, CacheEntry>>>,
     config: CacheConfig,
     metrics: Arc<ConsensusMetrics>,
+    clock: SharedClock,
 }
 
 impl ValidationCache {
     pub fn new(metrics: Arc<ConsensusMetrics>) -> Self {
+        Self::with_clock(metrics, system_clock())
+    }
+
+    /// Create a cache that ages entries against `clock`
+    pub fn with_clock(metrics: Arc<ConsensusMetrics>, clock: SharedClock) -> Self {
         Self {
             block_cache: Arc::new(RwLock::new(LruCache::new(
                 NonZeroUsize::new(1000).unwrap()
@@ -53,6 +59,7 @@ impl ValidationCache {
                 validation_window: 3600, // 1 hour
             },
             metrics,
+            clock,
         }
     }
 
@@ -62,7 +69,7 @@ impl ValidationCache {
         
         let entry = CacheEntry {
             value: is_valid,
-            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            timestamp: self.clock.unix_secs(),
             score,
         };
         
@@ -82,7 +89,7 @@ impl ValidationCache {
         
         let entry = CacheEntry {
             value: is_valid,
-            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            timestamp: self.clock.unix_secs(),
             score,
         };
         
@@ -102,7 +109,7 @@ impl ValidationCache {
         
         let entry = CacheEntry {
             value: is_valid,
-            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            timestamp: self.clock.unix_secs(),
             score,
         };
         
@@ -116,7 +123,7 @@ impl ValidationCache {
     }
 
     pub async fn cleanup_cache(&self) -> Result<()> {
-        let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let current_time = self.clock.unix_secs();
         
         // Clean block cache
         let mut block_cache = self.block_cache.write().await;
@@ -158,7 +165,36 @@ impl ValidationCache {
     }
 
     pub async fn is_cache_valid(&self, entry: &CacheEntry) -> Result<bool, anyhow::Error> {
-        let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let current_time = self.clock.unix_secs();
         Ok(entry.timestamp + self.config.validation_window >= current_time)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_entries_expire_after_validation_window() {
+        let registry = prometheus::Registry::new();
+        let metrics = Arc::new(ConsensusMetrics::new(&registry).unwrap());
+        let clock = MockClock::from_unix_secs(1704067200);
+        let cache = ValidationCache::with_clock(metrics, clock.shared());
+
+        cache.cache_color_validation("a1b2c3", true, 90).await.unwrap();
+        let entry = cache.get_cached_color_validation("a1b2c3").await.unwrap();
+        assert_eq!(entry.timestamp, 1704067200);
+
+        clock.advance(Duration::from_secs(3600));
+        assert!(cache.is_cache_valid(&entry).await.unwrap());
+        cache.cleanup_cache().await.unwrap();
+        assert!(cache.get_cached_color_validation("a1b2c3").await.is_some());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(!cache.is_cache_valid(&entry).await.unwrap());
+        cache.cleanup_cache().await.unwrap();
+        assert!(cache.get_cached_color_validation("a1b2c3").await.is_none());
+    }
+}
@@ -40,7 +40,7 @@ pub use error::ConsensusError;
 pub use metrics::ConsensusMetrics;
 pub use transaction_selector::{
     TransactionSelector, TransactionSelectorConfig, TransactionWithMetadata,
-    SelectedTransactions, BlockEfficiency,
+    SelectableTransaction, SelectedTransactions, BlockEfficiency,
 };
 pub use vote::{Vote, VotingResult};
 pub use voting::VotingSystem;
@@ -9,8 +9,18 @@
 //! - 10% oldest transactions
 //!
 //! This creates efficient, fair blocks optimized for affordability.
-use crate::blockchain::Transaction;
+//!
+//! ## Transaction Types
+//!
+//! The selector works with any type implementing `SelectableTransaction`.
+//! `v1::Transaction` supplies the PointPrice the sender actually paid; the
+//! production `Transaction` has no fee field, so its PointPrice is estimated
+//! from size and amount.
+use crate::blockchain::v1::codec::Canonical;
+use crate::blockchain::{v1, Transaction};
 use anyhow::Result;
+use std::collections::HashSet;
+use std::hash::Hash;
 
 /// PoAI Point system constants
 const POINT_TO_COIN_RATIO: f64 = 0.001; // 1 point = 0.001 coins
@@ -44,27 +54,83 @@ impl Default for TransactionSelectorConfig {
     }
 }
 
+/// Transaction fields the 20/20/50/10 algorithm selects on
+pub trait SelectableTransaction: Clone {
+    /// Identifier used to avoid selecting a transaction twice and to break
+    /// sort ties, so every node selects the same transactions
+    type Id: Eq + Hash + Ord;
+
+    /// Unique transaction identifier
+    fn id(&self) -> Self::Id;
+
+    /// Fee in points
+    fn point_price(&self) -> u64;
+
+    /// Size/data volume in points (bytes)
+    fn point_data(&self) -> u64;
+
+    /// Creation time (Unix seconds)
+    fn timestamp(&self) -> u64;
+}
+
+impl SelectableTransaction for Transaction {
+    type Id = String;
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn point_price(&self) -> u64 {
+        calculate_point_price(self)
+    }
+
+    fn point_data(&self) -> u64 {
+        self.calculate_size()
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+impl SelectableTransaction for v1::Transaction {
+    type Id = [u8; 32];
+
+    fn id(&self) -> [u8; 32] {
+        self.hash()
+    }
+
+    fn point_price(&self) -> u64 {
+        self.point_price
+    }
+
+    fn point_data(&self) -> u64 {
+        self.to_canonical_bytes().len() as u64
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
 /// Transaction with PoAI-specific metadata
 #[derive(Debug, Clone)]
-pub struct TransactionWithMetadata {
-    pub transaction: Transaction,
+pub struct TransactionWithMetadata<T = Transaction> {
+    pub transaction: T,
     pub point_price: u64,      // Fee in points
     pub point_data: u64,       // Size/data volume in points
     pub timestamp: u64,        // When transaction was created
     pub priority_score: f64,   // Calculated priority for sorting
 }
 
-impl TransactionWithMetadata {
+impl<T: SelectableTransaction> TransactionWithMetadata<T> {
     /// Create from a base transaction
-    pub fn from_transaction(tx: Transaction) -> Self {
-        let point_data = tx.calculate_size(); // Size in bytes = points
-        let point_price = calculate_point_price(&tx);
-        
+    pub fn from_transaction(tx: T) -> Self {
         Self {
-            timestamp: tx.timestamp,
+            point_price: tx.point_price(),
+            point_data: tx.point_data(), // Size in bytes = points
+            timestamp: tx.timestamp(),
             transaction: tx,
-            point_price,
-            point_data,
             priority_score: 0.0,
         }
     }
@@ -78,11 +144,11 @@ impl TransactionWithMetadata {
     }
 }
 
-/// Calculate PointPrice from transaction
-/// In a real implementation, this would come from the transaction fee field
+/// Estimate PointPrice for a production transaction
+///
+/// The production `Transaction` has no fee field, so the fee is estimated
+/// from size and amount. `v1::Transaction` carries the real `point_price`.
 fn calculate_point_price(tx: &Transaction) -> u64 {
-    // TODO: Once Transaction has a fee field, use that
-    // For now, estimate based on transaction size and amount
     let base_fee = (tx.calculate_size() / 100).max(1); // Minimum 1 point per 100 bytes
     let amount_fee = tx.amount / 1000000; // 1 point per million in amount
     base_fee + amount_fee
@@ -105,47 +171,67 @@ impl TransactionSelector {
     ///
     /// # Returns
     /// * Selected transactions organized by priority category
-    pub fn select_transactions(
+    pub fn select_transactions<T: SelectableTransaction>(
         &self,
-        mempool: Vec<Transaction>,
-    ) -> Result<SelectedTransactions> {
+        mempool: Vec<T>,
+    ) -> Result<SelectedTransactions<T>> {
+        Ok(self.select(mempool))
+    }
+
+    /// Category sizes `[high, low, avg, oldest]` for a block of `n`
+    /// transactions
+    ///
+    /// High, low and oldest round down and avg takes the remainder, so the
+    /// sizes always add up to `n`.
+    pub fn category_counts(n: usize) -> [usize; 4] {
+        let high = n * 20 / 100;
+        let low = n * 20 / 100;
+        let oldest = n * 10 / 100;
+        [high, low, n - high - low - oldest, oldest]
+    }
+
+    /// Infallible core of `select_transactions`
+    ///
+    /// Categories are filled without overlap in the order high, low,
+    /// closest to the average PointPrice, oldest, with the transaction ID
+    /// breaking every tie. Their sizes follow `category_counts`, so at most
+    /// `max_transactions_per_block` are selected.
+    pub fn select<T: SelectableTransaction>(&self, mempool: Vec<T>) -> SelectedTransactions<T> {
         if mempool.is_empty() {
-            return Ok(SelectedTransactions::empty());
+            return SelectedTransactions::empty();
         }
         
         // Convert to metadata format
-        let tx_with_meta: Vec<TransactionWithMetadata> = mempool
+        let tx_with_meta: Vec<TransactionWithMetadata<T>> = mempool
             .into_iter()
             .map(TransactionWithMetadata::from_transaction)
             .collect();
         
         // Calculate target counts for each category
         let total_count = self.config.max_transactions_per_block.min(tx_with_meta.len());
-        let high_price_count = (total_count as f64 * 0.20).ceil() as usize;
-        let low_price_count = (total_count as f64 * 0.20).ceil() as usize;
-        let avg_price_count = (total_count as f64 * 0.50).ceil() as usize;
-        let oldest_count = (total_count as f64 * 0.10).ceil() as usize;
+        let [high_price_count, low_price_count, avg_price_count, oldest_count] =
+            Self::category_counts(total_count);
         
         // Calculate average PointPrice
         let avg_point_price = if !tx_with_meta.is_empty() {
-            tx_with_meta.iter().map(|t| t.point_price).sum::<u64>() / tx_with_meta.len() as u64
+            let total: u128 = tx_with_meta.iter().map(|t| t.point_price as u128).sum();
+            (total / tx_with_meta.len() as u128) as u64
         } else {
             0
         };
         
         // Select unique transactions per category (no overlap) in this order:
         // high (20%), low (20%), avg (50%), oldest (10%)
-        use std::collections::HashSet;
-        let mut selected_ids: HashSet<String> = HashSet::new();
+        let mut selected_ids: HashSet<T::Id> = HashSet::new();
 
         // Helper: take up to `count` items from `candidates` that aren't already selected.
-        let mut take_unique = |mut candidates: Vec<TransactionWithMetadata>, count: usize| {
+        let mut take_unique = |mut candidates: Vec<TransactionWithMetadata<T>>, count: usize| {
             let mut out = Vec::new();
             for tx in candidates.drain(..) {
                 if out.len() >= count {
                     break;
                 }
-                let id = tx.transaction.id.clone();
+                let id = tx.transaction.id();
                 if selected_ids.insert(id) {
                     out.push(tx);
                 }
@@ -155,39 +241,52 @@ impl TransactionSelector {
 
         // 1) High price: sort by PointPrice desc
         let mut high_candidates = tx_with_meta.clone();
-        high_candidates.sort_by(|a, b| b.point_price.cmp(&a.point_price));
+        high_candidates.sort_by(|a, b| {
+            b.point_price
+                .cmp(&a.point_price)
+                .then_with(|| a.transaction.id().cmp(&b.transaction.id()))
+        });
         let high_price = take_unique(high_candidates, high_price_count);
 
         // 2) Low price: sort by PointPrice asc
         let mut low_candidates = tx_with_meta.clone();
-        low_candidates.sort_by(|a, b| a.point_price.cmp(&b.point_price));
+        low_candidates.sort_by(|a, b| {
+            a.point_price
+                .cmp(&b.point_price)
+                .then_with(|| a.transaction.id().cmp(&b.transaction.id()))
+        });
         let low_price = take_unique(low_candidates, low_price_count);
 
         // 3) Avg price: sort by absolute diff to avg_point_price (closest first)
         let mut avg_candidates = tx_with_meta.clone();
         avg_candidates.sort_by(|a, b| {
-            let da = (a.point_price as i64 - avg_point_price as i64).abs();
-            let db = (b.point_price as i64 - avg_point_price as i64).abs();
+            let da = a.point_price.abs_diff(avg_point_price);
+            let db = b.point_price.abs_diff(avg_point_price);
             da.cmp(&db)
                 .then_with(|| a.point_price.cmp(&b.point_price))
+                .then_with(|| a.transaction.id().cmp(&b.transaction.id()))
         });
         let avg_price = take_unique(avg_candidates, avg_price_count);
 
         // 4) Oldest: sort by timestamp asc
         let mut oldest_candidates = tx_with_meta;
-        oldest_candidates.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        oldest_candidates.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then_with(|| a.transaction.id().cmp(&b.transaction.id()))
+        });
         let oldest = take_unique(oldest_candidates, oldest_count);
 
         let total_selected = high_price.len() + low_price.len() + avg_price.len() + oldest.len();
         
-        Ok(SelectedTransactions {
+        SelectedTransactions {
             high_price,
             low_price,
             avg_price,
             oldest,
             avg_point_price,
             total_selected,
-        })
+        }
     }
     
     /// Calculate block efficiency
@@ -195,9 +294,9 @@ impl TransactionSelector {
     /// Efficiency is measured as:
     /// - How close the average PointPrice is to the target
     /// - How much PointData (useful information) is included
-    pub fn calculate_block_efficiency(
+    pub fn calculate_block_efficiency<T>(
         &self,
-        selected: &SelectedTransactions,
+        selected: &SelectedTransactions<T>,
     ) -> Result<BlockEfficiency> {
         let all_tx = selected.all_transactions();
         
@@ -208,11 +307,12 @@ impl TransactionSelector {
         // Calculate total PointData (useful information)
         let total_point_data: u64 = all_tx.iter().map(|t| t.point_data).sum();
         
-        // Calculate total PointPrice (fees)
-        let total_point_price: u64 = all_tx.iter().map(|t| t.point_price).sum();
+        // Calculate total PointPrice (fees), summed wide so the average is exact
+        let total_point_price: u128 = all_tx.iter().map(|t| t.point_price as u128).sum();
         
         // Calculate average PointPrice
-        let avg_point_price = total_point_price / all_tx.len() as u64;
+        let avg_point_price = (total_point_price / all_tx.len() as u128) as u64;
+        let total_point_price = u64::try_from(total_point_price).unwrap_or(u64::MAX);
         
         // Calculate fill percentage (how full the block is)
         let fill_percentage = (total_point_data as f64 / self.config.target_block_size as f64)
@@ -236,7 +336,7 @@ impl TransactionSelector {
     }
     
     /// Calculate price stability score
-    fn calculate_price_stability(&self, transactions: &[&TransactionWithMetadata]) -> f64 {
+    fn calculate_price_stability<T>(&self, transactions: &[&TransactionWithMetadata<T>]) -> f64 {
         if transactions.is_empty() {
             return 0.0;
         }
@@ -246,15 +346,16 @@ impl TransactionSelector {
         
         let median = if prices.len() % 2 == 0 {
             let mid = prices.len() / 2;
-            (prices[mid - 1] + prices[mid]) / 2
+            ((prices[mid - 1] as u128 + prices[mid] as u128) / 2) as u64
         } else {
             prices[prices.len() / 2]
         };
         
-        let avg: u64 = prices.iter().sum::<u64>() / prices.len() as u64;
+        let total: u128 = prices.iter().map(|&p| p as u128).sum();
+        let avg = (total / prices.len() as u128) as u64;
         
         // Stability is higher when median and average are close
-        let diff = (avg as i64 - median as i64).abs();
+        let diff = avg.abs_diff(median);
         let max_expected_diff = avg.max(1);
         
         let stability = 1.0 - (diff as f64 / max_expected_diff as f64).min(1.0);
@@ -275,16 +376,16 @@ impl TransactionSelector {
 
 /// Selected transactions organized by priority category
 #[derive(Debug, Clone)]
-pub struct SelectedTransactions {
-    pub high_price: Vec<TransactionWithMetadata>,
-    pub low_price: Vec<TransactionWithMetadata>,
-    pub avg_price: Vec<TransactionWithMetadata>,
-    pub oldest: Vec<TransactionWithMetadata>,
+pub struct SelectedTransactions<T = Transaction> {
+    pub high_price: Vec<TransactionWithMetadata<T>>,
+    pub low_price: Vec<TransactionWithMetadata<T>>,
+    pub avg_price: Vec<TransactionWithMetadata<T>>,
+    pub oldest: Vec<TransactionWithMetadata<T>>,
     pub avg_point_price: u64,
     pub total_selected: usize,
 }
 
-impl SelectedTransactions {
+impl<T> SelectedTransactions<T> {
     pub fn empty() -> Self {
         Self {
             high_price: vec![],
@@ -297,7 +398,7 @@ impl SelectedTransactions {
     }
     
     /// Get all selected transactions as a flat list
-    pub fn all_transactions(&self) -> Vec<&TransactionWithMetadata> {
+    pub fn all_transactions(&self) -> Vec<&TransactionWithMetadata<T>> {
         let mut all = Vec::new();
         all.extend(self.high_price.iter());
         all.extend(self.low_price.iter());
@@ -307,7 +408,7 @@ impl SelectedTransactions {
     }
     
     /// Get all transactions as owned values
-    pub fn into_transactions(self) -> Vec<Transaction> {
+    pub fn into_transactions(self) -> Vec<T> {
         let mut all = Vec::new();
         all.extend(self.high_price.into_iter().map(|t| t.transaction));
         all.extend(self.low_price.into_iter().map(|t| t.transaction));
@@ -321,7 +422,7 @@ impl SelectedTransactions {
 #[derive(Debug, Clone, Default)]
 pub struct BlockEfficiency {
     pub total_point_data: u64,     // Total useful information (bytes)
-    pub total_point_price: u64,    // Total fees collected
+    pub total_point_price: u64,    // Total fees collected (saturating)
     pub avg_point_price: u64,      // Average fee per transaction
     pub fill_percentage: f64,      // How full the block is (0.0-1.0)
     pub price_stability: f64,      // Price stability score (0-100)
@@ -401,6 +502,35 @@ mod tests {
         assert!((old_pct - 0.10).abs() < 0.05);
     }
     
+    #[test]
+    fn test_category_counts_fill_the_block() {
+        assert_eq!(TransactionSelector::category_counts(10), [2, 2, 5, 1]);
+        assert_eq!(TransactionSelector::category_counts(7), [1, 1, 5, 0]);
+        
+        for (n, expected) in [(7, [1, 1, 5, 0]), (10, [2, 2, 5, 1])] {
+            let selector = TransactionSelector::new(TransactionSelectorConfig {
+                max_transactions_per_block: n,
+                ..Default::default()
+            });
+            let mempool: Vec<v1::Transaction> = (0..20)
+                .map(|i| {
+                    let sender = format!("s{}", i);
+                    v1::Transaction::new(i, "c".to_string(), sender, None, vec![], 100 + i, i)
+                })
+                .collect();
+            
+            let result = selector.select(mempool);
+            let counts = [
+                result.high_price.len(),
+                result.low_price.len(),
+                result.avg_price.len(),
+                result.oldest.len(),
+            ];
+            assert_eq!(counts, expected);
+            assert_eq!(result.total_selected, n);
+        }
+    }
+    
     #[test]
     fn test_block_efficiency_calculation() {
         let config = TransactionSelectorConfig::default();
@@ -448,6 +578,53 @@ mod tests {
         assert!(oldest_timestamp < 1000000 - 90);
     }
     
+    #[test]
+    fn test_v1_transactions_use_point_price() {
+        let config = TransactionSelectorConfig {
+            max_transactions_per_block: 10,
+            ..Default::default()
+        };
+        let selector = TransactionSelector::new(config);
+        
+        // Identical sizes, so only the paid PointPrice distinguishes them
+        let mempool: Vec<v1::Transaction> = (0..10)
+            .map(|i| {
+                let price = (i + 1) * 100;
+                v1::Transaction::new(i, "c".to_string(), "s".to_string(), None, vec![], price, i)
+            })
+            .collect();
+        
+        let result = selector.select_transactions(mempool).unwrap();
+        
+        assert_eq!(result.total_selected, 10);
+        assert_eq!(result.high_price[0].point_price, 1000);
+        assert_eq!(result.low_price[0].point_price, 100);
+        assert_eq!(result.avg_point_price, 550);
+        assert_eq!(result.high_price[0].point_data, 147);
+    }
+    
+    #[test]
+    fn test_extreme_point_prices_do_not_overflow() {
+        let selector = TransactionSelector::new(TransactionSelectorConfig::default());
+        let prices = [u64::MAX, u64::MAX - 1, u64::MAX / 2, 1];
+        let mempool: Vec<v1::Transaction> = (0..4)
+            .map(|i| {
+                let price = prices[i as usize];
+                v1::Transaction::new(i, "c".to_string(), "s".to_string(), None, vec![], price, 1)
+            })
+            .collect();
+        
+        let result = selector.select(mempool);
+        assert_eq!(result.total_selected, 4);
+        let total: u128 = prices.iter().map(|&p| p as u128).sum();
+        let expected = (total / 4) as u64;
+        assert_eq!(result.avg_point_price, expected);
+        
+        let efficiency = selector.calculate_block_efficiency(&result).unwrap();
+        assert_eq!(efficiency.total_point_price, u64::MAX);
+        assert_eq!(efficiency.avg_point_price, expected);
+    }
+    
     #[test]
     fn test_point_to_coin_ratio_halving() {
         let mut config = TransactionSelectorConfig::default();
@@ -473,7 +650,7 @@ mod tests {
         let config = TransactionSelectorConfig::default();
         let selector = TransactionSelector::new(config);
         
-        let result = selector.select_transactions(vec![]).unwrap();
+        let result = selector.select_transactions(Vec::<Transaction>::new()).unwrap();
         assert_eq!(result.total_selected, 0);
         assert!(result.all_transactions().is_empty());
     }
@@ -0,0 +1,311 @@
+//! PoAI v1 Commit Certificate
+//!
+//! Portable finality proof: the committee's ranked-vote signatures for the
+//! winning block.
+//!
+//! ## Signatures
+//!
+//! Each `CommitSignature` is the signature of a `RankedVote` for
+//! `(height, round, block_hash, efficiency_score)` under the
+//! `self-chain-ranked-vote-v1` domain prefix. In the PoAI single-round model
+//! the ranked vote doubles as the precommit, so the certificate needs no
+//! extra signing step.
+//!
+//! ## Verification
+//!
+//! Against a committee snapshot, a certificate is valid when every signer is
+//! a member, no member signs twice, every signature verifies, and the number
+//! of signers reaches `ConsensusConfig::quorum_threshold`.
+//!
+//! Signatures are stored sorted by validator ID, so the same votes always
+//! produce the same certificate. `commit_signatures` are excluded from the
+//! block hash, so attaching a certificate does not change the block.
+//!
+//! A certificate's round is the round of its votes. Validators locked on a
+//! block keep voting for it in later rounds, so that round may be later than
+//! the block's own `round`; it is carried in the header's `commit_round`.
+//!
+//! ## Canonical Encoding Order
+//!
+//! 1. `height` (u64, little-endian)
+//! 2. `round` (u64, little-endian)
+//! 3. `block_hash` (32 bytes)
+//! 4. `efficiency_score` (u64, little-endian)
+//! 5. `signatures` (length-prefixed sequence of commit signatures)
+
+use super::types::{
+    CommitSignatureMsg, ConsensusConfig, ConsensusError, ConsensusMessage, ConsensusResult,
+    ValidatorInfo,
+};
+use crate::blockchain::v1::codec::{Canonical, CodecResult, Decoder, Encoder};
+use crate::blockchain::v1::{Block, CommitSignature, RankedVote};
+use std::collections::HashSet;
+
+/// 2/3+ committee signatures for one block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitCertificate {
+    /// Finalized height
+    pub height: u64,
+
+    /// Round of the votes, no earlier than the block's own round
+    pub round: u64,
+
+    /// Hash of the finalized block
+    pub block_hash: [u8; 32],
+
+    /// Verified efficiency score the votes were cast for
+    pub efficiency_score: u64,
+
+    /// Ranked-vote signatures, sorted by validator ID
+    pub signatures: Vec<CommitSignature>,
+}
+
+impl CommitCertificate {
+    /// Assemble a certificate from the ranked votes for `block_hash`
+    ///
+    /// Votes for other blocks, heights, rounds or scores are ignored, and
+    /// only the first vote of each validator is kept.
+    pub fn from_votes<'a>(
+        height: u64,
+        round: u64,
+        block_hash: [u8; 32],
+        efficiency_score: u64,
+        votes: impl IntoIterator<Item = &'a RankedVote>,
+    ) -> Self {
+        let mut seen = HashSet::new();
+        let mut signatures: Vec<CommitSignature> = votes
+            .into_iter()
+            .filter(|v| {
+                v.height == height
+                    && v.round == round
+                    && v.block_hash == block_hash
+                    && v.efficiency_score == efficiency_score
+            })
+            .filter(|v| seen.insert(v.validator_id.clone()))
+            .map(|v| CommitSignature {
+                validator_id: v.validator_id.clone(),
+                signature: v.signature,
+            })
+            .collect();
+        signatures.sort_by(|a, b| a.validator_id.cmp(&b.validator_id));
+
+        Self {
+            height,
+            round,
+            block_hash,
+            efficiency_score,
+            signatures,
+        }
+    }
+
+    /// Certificate carried in a finalized block's header
+    pub fn from_block(block: &Block) -> Self {
+        Self {
+            height: block.header.height,
+            round: block.header.commit_round,
+            block_hash: block.hash(),
+            efficiency_score: block.header.efficiency_score,
+            signatures: block.header.commit_signatures.clone(),
+        }
+    }
+
+    /// Certificate carried in the fields of a `Commit` message, for a block
+    /// scoring `efficiency_score`
+    pub fn from_commit(
+        height: u64,
+        round: u64,
+        block_hash: [u8; 32],
+        efficiency_score: u64,
+        signatures: &[CommitSignatureMsg],
+    ) -> Self {
+        Self {
+            height,
+            round,
+            block_hash,
+            efficiency_score,
+            signatures: signatures
+                .iter()
+                .map(|s| CommitSignature {
+                    validator_id: s.validator_id.clone(),
+                    signature: s.signature,
+                })
+                .collect(),
+        }
+    }
+
+    /// `Commit` message carrying this certificate
+    pub fn to_message(&self) -> ConsensusMessage {
+        ConsensusMessage::Commit {
+            height: self.height,
+            round: self.round,
+            block_hash: self.block_hash,
+            signatures: self
+                .signatures
+                .iter()
+                .map(|s| CommitSignatureMsg {
+                    validator_id: s.validator_id.clone(),
+                    signature: s.signature,
+                })
+                .collect(),
+        }
+    }
+
+    /// Attach the signatures and their round to `block`'s header
+    pub fn attach_to(&self, block: &mut Block) {
+        block.header.commit_signatures = self.signatures.clone();
+        block.header.commit_round = self.round;
+    }
+
+    /// IDs of the signing validators
+    pub fn signers(&self) -> impl Iterator<Item = &str> {
+        self.signatures.iter().map(|s| s.validator_id.as_str())
+    }
+
+    /// The signed ranked votes the certificate is made of
+    pub fn votes(&self) -> impl Iterator<Item = RankedVote> + '_ {
+        self.signatures.iter().map(|sig| RankedVote {
+            height: self.height,
+            round: self.round,
+            block_hash: self.block_hash,
+            efficiency_score: self.efficiency_score,
+            validator_id: sig.validator_id.clone(),
+            signature: sig.signature,
+        })
+    }
+
+    /// Verify the certificate against a committee snapshot
+    pub fn verify(
+        &self,
+        committee: &[ValidatorInfo],
+        config: &ConsensusConfig,
+    ) -> ConsensusResult<()> {
+        let mut signers = HashSet::with_capacity(self.signatures.len());
+        for vote in self.votes() {
+            if !signers.insert(vote.validator_id.clone()) {
+                return Err(ConsensusError::DuplicateVote(vote.validator_id));
+            }
+            let member = committee
+                .iter()
+                .find(|v| v.validator_id == vote.validator_id)
+                .ok_or_else(|| ConsensusError::NotInCommittee(vote.validator_id.clone()))?;
+            vote.verify_signature(&member.public_key)?;
+        }
+
+        if signers.len() < config.quorum_threshold(committee.len()) {
+            return Err(ConsensusError::QuorumNotReached);
+        }
+        Ok(())
+    }
+}
+
+impl Canonical for CommitCertificate {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_u64(self.height);
+        enc.put_u64(self.round);
+        enc.put_fixed(&self.block_hash);
+        enc.put_u64(self.efficiency_score);
+        enc.put_seq(&self.signatures);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            height: dec.get_u64()?,
+            round: dec.get_u64()?,
+            block_hash: dec.get_fixed()?,
+            efficiency_score: dec.get_u64()?,
+            signatures: dec.get_seq()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::v1::test_support::committee;
+    use crate::crypto::Ed25519Keys;
+
+    fn votes(keys: &[Ed25519Keys], block_hash: [u8; 32]) -> Vec<RankedVote> {
+        keys.iter()
+            .enumerate()
+            .map(|(i, k)| {
+                let mut vote = RankedVote::new(5, 1, block_hash, 42, format!("v{}", i));
+                vote.sign(k).unwrap();
+                vote
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_assemble_and_verify() {
+        let (infos, keys) = committee(4);
+        let config = ConsensusConfig::default();
+        let mut all = votes(&keys[..3], [7u8; 32]);
+        all.extend(votes(&keys[3..], [8u8; 32]));
+        all.reverse();
+
+        let cert = CommitCertificate::from_votes(5, 1, [7u8; 32], 42, &all);
+        assert_eq!(cert.signers().collect::<Vec<_>>(), vec!["v0", "v1", "v2"]);
+        cert.verify(&infos, &config).unwrap();
+
+        let decoded = CommitCertificate::from_canonical_bytes(&cert.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, cert);
+        let ConsensusMessage::Commit { height, round, block_hash, signatures } = cert.to_message()
+        else {
+            unreachable!()
+        };
+        let carried = CommitCertificate::from_commit(height, round, block_hash, 42, &signatures);
+        assert_eq!(carried, cert);
+    }
+
+    #[test]
+    fn test_rejects_short_duplicate_and_foreign_signatures() {
+        let (infos, keys) = committee(4);
+        let config = ConsensusConfig::default();
+        let all = votes(&keys, [7u8; 32]);
+
+        let short = CommitCertificate::from_votes(5, 1, [7u8; 32], 42, &all[..2]);
+        assert!(matches!(short.verify(&infos, &config), Err(ConsensusError::QuorumNotReached)));
+
+        let mut duplicated = CommitCertificate::from_votes(5, 1, [7u8; 32], 42, &all[..3]);
+        duplicated.signatures.push(duplicated.signatures[0].clone());
+        assert!(matches!(
+            duplicated.verify(&infos, &config),
+            Err(ConsensusError::DuplicateVote(_))
+        ));
+
+        // Snapshot without v3: v3's signature is a non-member
+        let full = CommitCertificate::from_votes(5, 1, [7u8; 32], 42, &all);
+        assert!(matches!(
+            full.verify(&infos[..3], &config),
+            Err(ConsensusError::NotInCommittee(_))
+        ));
+
+        // A certificate re-labelled for another block fails signature checks
+        let mut moved = full.clone();
+        moved.block_hash = [9u8; 32];
+        assert!(matches!(
+            moved.verify(&infos, &config),
+            Err(ConsensusError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_block_round_trip() {
+        let (infos, keys) = committee(3);
+        let mut header = crate::blockchain::v1::BlockHeader::genesis("c");
+        header.height = 5;
+        header.efficiency_score = 42;
+        let mut block = Block::new(header, vec![]);
+        let hash = block.hash();
+
+        let cert = CommitCertificate::from_votes(5, 1, hash, 42, &votes(&keys, hash));
+        cert.attach_to(&mut block);
+        assert_eq!(block.hash(), hash);
+        // Decided in round 1 although proposed in round 0
+        assert_eq!(block.header.commit_round, 1);
+        assert_eq!(CommitCertificate::from_block(&block), cert);
+        CommitCertificate::from_block(&block)
+            .verify(&infos, &ConsensusConfig::default())
+            .unwrap();
+    }
+}
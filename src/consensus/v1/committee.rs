@@ -0,0 +1,226 @@
+//! PoAI v1 Committee Selection
+//!
+//! Samples the voting committee for a height from the eligible validator set.
+//!
+//! ## Seed
+//!
+//! Each draw hashes the previous block hash, the height and the draw index:
+//!
+//! ```text
+//! SHA256(DOMAIN_PREFIX_COMMITTEE || previous_hash || height || draw)
+//! ```
+//!
+//! Every node with the same validator set and parent block therefore selects
+//! the same committee, regardless of the order validators were learned in.
+//!
+//! ## Sampling
+//!
+//! Candidates are the validators with `is_eligible` set, sorted by
+//! `validator_id`. Identical entries are collapsed; two different entries
+//! with the same ID are a `DuplicateValidator` error, since picking either
+//! would depend on input order. Without weights every candidate has weight
+//! 1; `with_weights` supplies stake or reputation instead, and candidates with
+//! no or zero weight are skipped. Each draw picks a candidate with probability
+//! proportional to its weight and removes it, until
+//! `min(candidates, committee_size_max)` members are chosen.
+//!
+//! Fewer than `committee_size_min` candidates is an
+//! `InsufficientValidators` error.
+
+use super::types::{constants, ConsensusConfig, ConsensusError, ConsensusResult, ValidatorInfo};
+use crate::blockchain::v1::codec::domain_hash;
+use std::collections::HashMap;
+
+/// Deterministic committee sampler
+#[derive(Debug, Clone)]
+pub struct CommitteeSelector {
+    config: ConsensusConfig,
+    weights: Option<HashMap<String, u64>>,
+}
+
+impl CommitteeSelector {
+    /// Create a selector that samples eligible validators uniformly
+    pub fn new(config: ConsensusConfig) -> Self {
+        Self {
+            config,
+            weights: None,
+        }
+    }
+
+    /// Weight the sampling by stake or reputation, keyed by validator ID
+    pub fn with_weights(mut self, weights: HashMap<String, u64>) -> Self {
+        self.weights = Some(weights);
+        self
+    }
+
+    /// Select the committee for `height`, sorted by validator ID
+    pub fn select(
+        &self,
+        validators: &[ValidatorInfo],
+        previous_hash: &[u8; 32],
+        height: u64,
+    ) -> ConsensusResult<Vec<ValidatorInfo>> {
+        let mut candidates: Vec<(&ValidatorInfo, u64)> = validators
+            .iter()
+            .filter(|v| v.is_eligible)
+            .map(|v| (v, self.weight(&v.validator_id)))
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
+        candidates.sort_by_key(|&(v, _)| (&v.validator_id, v.public_key));
+        candidates.dedup_by(|(a, _), (b, _)| a == b);
+        if let Some(pair) = candidates
+            .windows(2)
+            .find(|pair| pair[0].0.validator_id == pair[1].0.validator_id)
+        {
+            return Err(ConsensusError::DuplicateValidator(pair[0].0.validator_id.clone()));
+        }
+
+        if candidates.len() < self.config.committee_size_min {
+            return Err(ConsensusError::InsufficientValidators {
+                eligible: candidates.len(),
+                required: self.config.committee_size_min,
+            });
+        }
+
+        let size = candidates.len().min(self.config.committee_size_max);
+        let mut total: u128 = candidates.iter().map(|(_, w)| *w as u128).sum();
+        let mut committee = Vec::with_capacity(size);
+        for draw in 0..size as u64 {
+            let mut target = seed(previous_hash, height, draw) % total;
+            let index = candidates
+                .iter()
+                .position(|(_, weight)| {
+                    if target < *weight as u128 {
+                        return true;
+                    }
+                    target -= *weight as u128;
+                    false
+                })
+                .expect("target is below the total weight");
+            let (chosen, weight) = candidates.remove(index);
+            total -= weight as u128;
+            committee.push(chosen.clone());
+        }
+
+        committee.sort_by(|a, b| a.validator_id.cmp(&b.validator_id));
+        Ok(committee)
+    }
+
+    fn weight(&self, validator_id: &str) -> u64 {
+        match &self.weights {
+            Some(weights) => weights.get(validator_id).copied().unwrap_or(0),
+            None => 1,
+        }
+    }
+}
+
+/// Random value for one draw
+fn seed(previous_hash: &[u8; 32], height: u64, draw: u64) -> u128 {
+    let mut bytes = Vec::with_capacity(48);
+    bytes.extend_from_slice(previous_hash);
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.extend_from_slice(&draw.to_le_bytes());
+    let hash = domain_hash(constants::DOMAIN_PREFIX_COMMITTEE, &bytes);
+    u128::from_le_bytes(hash[..16].try_into().expect("16 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators(n: usize) -> Vec<ValidatorInfo> {
+        (0..n)
+            .map(|i| ValidatorInfo::new(format!("v{:02}", i), [i as u8; 32], "c".to_string()))
+            .collect()
+    }
+
+    fn config(min: usize, max: usize) -> ConsensusConfig {
+        ConsensusConfig {
+            committee_size_min: min,
+            committee_size_max: max,
+            ..ConsensusConfig::default()
+        }
+    }
+
+    fn ids(committee: &[ValidatorInfo]) -> Vec<&str> {
+        committee.iter().map(|v| v.validator_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_selection_is_deterministic_and_bounded() {
+        let selector = CommitteeSelector::new(config(3, 5));
+        let mut set = validators(20);
+
+        let committee = selector.select(&set, &[1u8; 32], 7).unwrap();
+        assert_eq!(committee.len(), 5);
+        assert!(ids(&committee).windows(2).all(|w| w[0] < w[1]));
+
+        // Input order does not matter; seed inputs do
+        set.reverse();
+        assert_eq!(selector.select(&set, &[1u8; 32], 7).unwrap(), committee);
+        let other_heights: Vec<_> = (8..12)
+            .map(|h| selector.select(&set, &[1u8; 32], h).unwrap())
+            .collect();
+        assert!(other_heights.iter().any(|c| *c != committee));
+
+        // Small populations are selected in full
+        assert_eq!(selector.select(&set[..4], &[1u8; 32], 7).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_too_few_eligible_validators() {
+        let selector = CommitteeSelector::new(config(3, 5));
+        let mut set = validators(4);
+        set[0].is_eligible = false;
+        set[1].is_eligible = false;
+        set.push(set[2].clone());
+
+        assert!(matches!(
+            selector.select(&set, &[0u8; 32], 1),
+            Err(ConsensusError::InsufficientValidators { eligible: 2, required: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_weights_bias_selection() {
+        let set = validators(10);
+        let weights: HashMap<String, u64> = set
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.validator_id.clone(), if i == 9 { 1_000_000 } else { 1 }))
+            .filter(|(id, _)| id != "v00")
+            .collect();
+        let selector = CommitteeSelector::new(config(1, 1)).with_weights(weights);
+
+        let picks = (0..20)
+            .filter(|h| ids(&selector.select(&set, &[3u8; 32], *h).unwrap()) == ["v09"])
+            .count();
+        assert!(picks >= 19);
+
+        // Unweighted validators are never chosen
+        let selector = CommitteeSelector::new(config(9, 9)).with_weights(
+            set.iter().skip(1).map(|v| (v.validator_id.clone(), 1)).collect(),
+        );
+        assert!(!ids(&selector.select(&set, &[3u8; 32], 1).unwrap()).contains(&"v00"));
+    }
+
+    #[test]
+    fn test_conflicting_duplicate_ids_are_rejected() {
+        let selector = CommitteeSelector::new(config(3, 5));
+        let mut set = validators(4);
+        set.push(set[1].clone());
+        assert_eq!(selector.select(&set, &[0u8; 32], 1).unwrap().len(), 4);
+
+        // Same ID, different key: neither entry wins, in any input order
+        let mut impostor = set[1].clone();
+        impostor.public_key = [0xFF; 32];
+        set.push(impostor);
+        for _ in 0..2 {
+            assert!(matches!(
+                selector.select(&set, &[0u8; 32], 1),
+                Err(ConsensusError::DuplicateValidator(id)) if id == "v01"
+            ));
+            set.reverse();
+        }
+    }
+}
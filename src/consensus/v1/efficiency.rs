@@ -0,0 +1,128 @@
+//! PoAI v1 Efficiency Score
+//!
+//! Bit-exact integer version of the spec's `compute_efficiency_score`, used
+//! for `v1::BlockHeader.efficiency_score` and checked by every validator.
+//!
+//! ## Formula
+//!
+//! The spec defines (in floating point):
+//!
+//! ```text
+//! fill_ratio      = n / MAX_TX_PER_BLOCK
+//! price_stability = 1 - |avg_point_price - target| / target
+//! points_density  = total_points / block_size
+//! efficiency      = fill_ratio * price_stability * points_density * 10000
+//! ```
+//!
+//! Substituting `avg_point_price = total_points / n` and cancelling `n`
+//! gives a single integer division:
+//!
+//! ```text
+//! stability_num = max(0, target * n - |total_points - target * n|)
+//! efficiency    = stability_num * total_points * 10000
+//!                 / (MAX_TX_PER_BLOCK * target * block_size)
+//! ```
+//!
+//! All arithmetic is `u128` and the quotient is truncated, so every node
+//! computes the same value. `block_size` is the sum of the canonical
+//! transaction encodings; header bytes are excluded so the score does not
+//! depend on the proposer ID length. Intermediate products saturate, and the
+//! result is capped at `u64::MAX`.
+//!
+//! An empty block, or a block whose average PointPrice is at least twice the
+//! target, scores 0.
+
+use super::types::{constants, ConsensusConfig};
+use crate::blockchain::v1::codec::Canonical;
+use crate::blockchain::v1::{Block, Transaction};
+
+/// Compute the efficiency score of a list of transactions
+pub fn compute_efficiency_score(
+    transactions: &[Transaction],
+    target_point_price: u64,
+    max_tx_per_block: usize,
+) -> u64 {
+    if transactions.is_empty() || target_point_price == 0 || max_tx_per_block == 0 {
+        return 0;
+    }
+
+    let n = transactions.len() as u128;
+    let target = target_point_price as u128;
+    let total_points: u128 = transactions.iter().map(|tx| tx.point_price as u128).sum();
+    let block_size: u128 = transactions
+        .iter()
+        .map(|tx| tx.to_canonical_bytes().len() as u128)
+        .sum();
+
+    let target_total = target.saturating_mul(n);
+    let stability_num = target_total.saturating_sub(total_points.abs_diff(target_total));
+    if stability_num == 0 {
+        return 0;
+    }
+
+    let numerator = stability_num
+        .saturating_mul(total_points)
+        .saturating_mul(constants::EFFICIENCY_SCALE as u128);
+    let denominator = (max_tx_per_block as u128)
+        .saturating_mul(target)
+        .saturating_mul(block_size);
+
+    u64::try_from(numerator / denominator).unwrap_or(u64::MAX)
+}
+
+/// Compute the efficiency score of a block under the given configuration
+pub fn block_efficiency_score(block: &Block, config: &ConsensusConfig) -> u64 {
+    compute_efficiency_score(
+        &block.transactions,
+        config.target_point_price,
+        config.max_tx_per_block,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txs(prices: &[u64]) -> Vec<Transaction> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| {
+                Transaction::new(i as u64, "c".to_string(), "s".to_string(), None, vec![], price, 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_known_value() {
+        // Each tx encodes to 8 + 9 + 9 + 1 + 8 + 8 + 8 + 32 + 64 = 147 bytes
+        let block = txs(&[1000, 1000]);
+        assert_eq!(block[0].to_canonical_bytes().len(), 147);
+
+        // stability = 2000, total = 2000, size = 294
+        // 2000 * 2000 * 10000 / (1000 * 1000 * 294) = 136 (truncated)
+        assert_eq!(compute_efficiency_score(&block, 1000, 1000), 136);
+    }
+
+    #[test]
+    fn test_off_target_prices_score_lower() {
+        let on_target = compute_efficiency_score(&txs(&[1000, 1000]), 1000, 1000);
+        let skewed = compute_efficiency_score(&txs(&[500, 1500]), 1000, 1000);
+        let cheap = compute_efficiency_score(&txs(&[500, 500]), 1000, 1000);
+
+        // Same average keeps stability; lower total lowers density
+        assert_eq!(skewed, on_target);
+        assert!(cheap < on_target);
+        assert_eq!(compute_efficiency_score(&txs(&[2000, 2000]), 1000, 1000), 0);
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        assert_eq!(compute_efficiency_score(&[], 1000, 1000), 0);
+        assert_eq!(compute_efficiency_score(&txs(&[1000]), 0, 1000), 0);
+
+        // Saturating arithmetic never panics on extreme inputs
+        let huge = compute_efficiency_score(&txs(&[u64::MAX, u64::MAX]), u64::MAX, 1);
+        assert!(huge > 0);
+    }
+}
@@ -0,0 +1,1514 @@
+//! PoAI v1 Consensus Engine
+//!
+//! Drives `RoundState` through the round steps on the configured timers and
+//! turns incoming `ConsensusMessage`s into decided blocks.
+//!
+//! ## Round Lifecycle
+//!
+//! ```text
+//! ProposeWindow ──timeout_propose_window──> Voting ──timeout_voting──> Finalize
+//!       ^                                      │                          │
+//!       │ advance_round (no proposals /        │                          │
+//!       └──────────── no quorum) <─────────────┘          timeout_finalize│
+//!                                                                         v
+//!                             new_height(height + 1) <────────── Decided block
+//! ```
+//!
+//! - **ProposeWindow**: proposals from committee members and builders (see
+//!   `with_builders`) are admitted to a `ProposalPool`, which verifies
+//!   signatures and efficiency claims against the round's
+//!   `reference_efficiency`. With a chain (see `with_chain`), every proposed
+//!   block must first pass `BlockValidator` against the parent header and
+//!   account state; decided blocks are applied to that state, and a block
+//!   that does not apply is not decided.
+//! - **Voting**: a local validator (see `with_signer`) broadcasts a ranked
+//!   vote for the pool's best proposal, or for the block it is locked on (see
+//!   Locking), recorded first in its `LastSignedStore` if one is set (see
+//!   `with_last_signed`). Ranked votes from committee members are counted by a
+//!   `RankedVoteTally`.
+//! - **Finalize**: the tally's `RankedQuorumResult::Winner` wins. Its commit
+//!   proof is broadcast, and the block is decided once the finalize timer
+//!   fires.
+//!
+//! Every proposal and vote also passes through an `EquivocationDetector`;
+//! conflicting signed messages are output as `EngineOutput::Evidence`.
+//!
+//! With a clock (see `with_clock`) and a chain (see `with_chain`), each
+//! round's `RoundSchedule` is derived from the parent's timestamp (see
+//! `RoundSchedule::for_round`), so every node checks the same windows, and
+//! proposals and ranked votes outside them are rejected before they reach the
+//! pool or the tally. Without both, all timing checks are silently skipped:
+//! messages are accepted whenever they arrive and only the step timers bound
+//! a round.
+//!
+//! A proposal window that leaves nothing to vote for at the height, or a vote
+//! without quorum, calls `advance_round`.
+//!
+//! ## Rounds
+//!
+//! Messages for the next `MAX_FUTURE_ROUNDS` rounds of the current height
+//! are buffered and replayed when the engine enters their round. A proposal
+//! or vote is buffered only once its signature verifies against a known
+//! builder or committee member, at most `MAX_FUTURE_MESSAGES_PER_SENDER` per
+//! signer, and a commit only once its certificate verifies, so one peer
+//! cannot crowd out the others.
+//! Votes for an earlier round are dropped; proposals for an earlier round are
+//! still pooled, outside the proposal window.
+//!
+//! ## Locking
+//!
+//! A local validator that votes for a block is locked on it: in every later
+//! round of the height it votes for that block again, even if a better
+//! proposal arrives, and rebroadcasts the block's proposal for validators
+//! that missed it. The lock is released once `quorum_threshold` validators
+//! are counted voting for other blocks, or caught equivocating, in a round no
+//! earlier than its last vote. Two quorums share an honest validator, so a
+//! block certified in one round keeps every honest validator that voted for
+//! it locked, and no other block can be certified at that height in a later
+//! round. An unlocked validator votes for the block that led the latest round
+//! without quorum, so split votes converge.
+//!
+//! With a `LastSignedStore` (see `with_last_signed`), the lock is written
+//! together with each vote and its release is recorded before it takes
+//! effect. A restarted engine restores its voted round and lock from the
+//! store, so a validator that restarts mid-height stays locked.
+//!
+//! ## Catching Up
+//!
+//! A `Commit` message for the current height decides its block as soon as its
+//! `CommitCertificate` verifies against the committee, unless the local
+//! validator has already voted in a later round. A verified commit for a
+//! later round, or buffered votes for it from more validators than can be
+//! faulty, moves the engine straight to that round, and as many votes in the
+//! current round end its proposal window early, so a validator that fell
+//! behind catches up. A validator that missed a whole height catches up with
+//! `adopt_decided`, feeding it the blocks and certificates fetched by block
+//! sync.
+//!
+//! ## Driving the Engine
+//!
+//! `run` owns the engine on a tokio task, reading messages from an mpsc
+//! channel and sending `EngineOutput`s back. The synchronous
+//! `handle_message` / `on_timeout` / `drain_outputs` API is the same state
+//! machine without timers.
+
+use super::certificate::CommitCertificate;
+use super::evidence::{EquivocationDetector, EquivocationEvidence};
+use super::last_signed::LastSignedStore;
+use super::pool::ProposalPool;
+use super::tally::{RankedQuorumResult, RankedVoteTally};
+use super::timing::RoundSchedule;
+use super::types::{
+    ConsensusConfig, ConsensusError, ConsensusMessage, ConsensusResult,
+    RoundState, RoundStep, ValidatorInfo,
+};
+use super::validation::{BlockValidator, ValidationContext};
+use crate::blockchain::v1::codec::Canonical;
+use crate::blockchain::v1::{Block, BlockHeader, BlockProposal, RankedVote};
+use crate::clock::SharedClock;
+use crate::crypto::common::traits::Signer;
+use crate::state::StateMachine;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+
+/// Later rounds of the current height whose messages are buffered
+pub const MAX_FUTURE_ROUNDS: u64 = 8;
+
+/// Proposals and votes buffered per signer for later rounds
+pub const MAX_FUTURE_MESSAGES_PER_SENDER: usize = 16;
+
+/// Block decided by the committee
+#[derive(Debug, Clone)]
+pub struct DecidedBlock {
+    /// Decided height
+    pub height: u64,
+
+    /// Round in which the block was decided
+    pub round: u64,
+
+    /// The winning block, with the certificate's commit signatures attached
+    pub block: Block,
+
+    /// Committee signatures proving 2/3+ support
+    pub certificate: CommitCertificate,
+}
+
+/// Output produced by the engine
+#[derive(Debug, Clone)]
+pub enum EngineOutput {
+    /// Message to gossip to the other validators
+    Broadcast(ConsensusMessage),
+    /// Block finalized at the current height
+    Decided(DecidedBlock),
+    /// A validator signed conflicting messages
+    Evidence(EquivocationEvidence),
+}
+
+/// Local validator identity used to sign votes
+struct LocalValidator {
+    validator_id: String,
+    signer: Box<dyn Signer + Send + Sync>,
+}
+
+/// Parent block and account state proposals are validated against
+struct ChainContext {
+    parent: BlockHeader,
+    state: StateMachine,
+    validator: BlockValidator,
+}
+
+/// Timer-driven PoAI v1 consensus state machine
+pub struct ConsensusEngine {
+    config: ConsensusConfig,
+    committee: Vec<ValidatorInfo>,
+    local: Option<LocalValidator>,
+    last_signed: Option<LastSignedStore>,
+    clock: Option<SharedClock>,
+    chain: Option<ChainContext>,
+    state: RoundState,
+    future: BTreeMap<u64, Vec<ConsensusMessage>>,
+    proposals: ProposalPool,
+    votes: RankedVoteTally,
+    detector: EquivocationDetector,
+    outputs: Vec<EngineOutput>,
+}
+
+impl ConsensusEngine {
+    /// Create an engine that follows `committee`, starting at `height`
+    pub fn new(config: ConsensusConfig, committee: Vec<ValidatorInfo>, height: u64) -> Self {
+        let votes = RankedVoteTally::new(height, 0, &committee, &config);
+        let proposals = ProposalPool::new(config.clone(), &committee);
+        let detector = EquivocationDetector::new(&committee);
+        Self {
+            config,
+            committee,
+            local: None,
+            last_signed: None,
+            clock: None,
+            chain: None,
+            state: RoundState::new(height),
+            future: BTreeMap::new(),
+            proposals,
+            votes,
+            detector,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Participate as `validator_id`, signing ranked votes with `signer`
+    pub fn with_signer(
+        mut self,
+        validator_id: String,
+        signer: impl Signer + Send + Sync + 'static,
+    ) -> Self {
+        self.local = Some(LocalValidator {
+            validator_id,
+            signer: Box::new(signer),
+        });
+        self
+    }
+
+    /// Also accept proposals from `builders` outside the committee
+    ///
+    /// Builders never count toward the quorum.
+    pub fn with_builders(mut self, builders: &[ValidatorInfo]) -> Self {
+        let proposers: Vec<ValidatorInfo> =
+            self.committee.iter().chain(builders).cloned().collect();
+        self.proposals = ProposalPool::new(self.config.clone(), &proposers);
+        self.detector = EquivocationDetector::new(&proposers);
+        self.clear_round();
+        self
+    }
+
+    /// Record local votes in `store` before signing, refusing conflicts
+    ///
+    /// A vote recorded at the current height moves the engine to its round
+    /// and restores the lock on its block, unless the lock was released.
+    pub fn with_last_signed(mut self, store: LastSignedStore) -> Self {
+        let height = self.state.height;
+        if let Some(vote) = store.last_vote().filter(|vote| vote.height == height) {
+            while self.state.round < vote.round {
+                self.state.advance_round();
+            }
+            self.state.voted_round = Some(vote.round);
+            self.clear_round();
+        }
+        self.state.locked_hash = store
+            .locked()
+            .filter(|lock| lock.height == height)
+            .map(|lock| lock.hash);
+        self.last_signed = Some(store);
+        self
+    }
+
+    /// Validate proposed blocks as children of `parent` on top of `state`
+    ///
+    /// `state` must be the account state after `parent`. Each decided block
+    /// is applied to it and becomes the next parent.
+    pub fn with_chain(mut self, parent: BlockHeader, state: StateMachine) -> Self {
+        self.chain = Some(ChainContext {
+            parent,
+            state,
+            validator: BlockValidator::new(self.config.clone()),
+        });
+        self
+    }
+
+    /// Check message times against the round schedule, reading local time
+    /// from `clock`
+    ///
+    /// The schedule comes from the parent block, so this has no effect
+    /// without `with_chain`.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Schedule of the current round, if the engine has a clock and a chain
+    pub fn schedule(&self) -> Option<RoundSchedule> {
+        self.clock.as_ref()?;
+        let chain = self.chain.as_ref()?;
+        Some(RoundSchedule::for_round(&self.config, &chain.parent, self.state.round))
+    }
+
+    /// Current round state
+    pub fn round_state(&self) -> &RoundState {
+        &self.state
+    }
+
+    /// Engine configuration
+    pub fn config(&self) -> &ConsensusConfig {
+        &self.config
+    }
+
+    /// Set the reference efficiency proposals must reach this round
+    pub fn set_reference_efficiency(&mut self, reference_efficiency: u64) {
+        self.state.reference_efficiency = reference_efficiency;
+        self.proposals
+            .set_reference(self.state.height, self.state.round, reference_efficiency);
+    }
+
+    /// Duration of the current step
+    pub fn step_timeout(&self) -> Duration {
+        match self.state.step {
+            RoundStep::ProposeWindow => self.config.timeout_propose_window,
+            RoundStep::Voting => self.config.timeout_voting,
+            RoundStep::Finalize | RoundStep::Committed => self.config.timeout_finalize,
+        }
+    }
+
+    /// Take the outputs produced since the last call
+    pub fn drain_outputs(&mut self) -> Vec<EngineOutput> {
+        std::mem::take(&mut self.outputs)
+    }
+
+    /// Process one consensus message for the current height
+    ///
+    /// Messages for a later round are buffered until the engine gets there.
+    pub fn handle_message(&mut self, message: ConsensusMessage) -> ConsensusResult<()> {
+        if message.height() != self.state.height {
+            return Err(ConsensusError::WrongHeight {
+                expected: self.state.height,
+                got: message.height(),
+            });
+        }
+        let round = message.round();
+        if round > self.state.round {
+            return self.buffer(message);
+        }
+        if round < self.state.round && matches!(message, ConsensusMessage::RankedVote { .. }) {
+            return Err(ConsensusError::WrongRound {
+                expected: self.state.round,
+                got: round,
+            });
+        }
+
+        match message {
+            message @ ConsensusMessage::Proposal { round, .. } => {
+                // An earlier round's proposal comes from a validator locked
+                // on it; its window is long gone
+                let current = round == self.state.round;
+                if current && self.state.step != RoundStep::ProposeWindow {
+                    return Err(ConsensusError::InvalidProposal(format!(
+                        "proposal window closed (step {})",
+                        self.state.step
+                    )));
+                }
+                let proposal = carried_proposal(&message)?;
+                if let Some((schedule, now)) = self.schedule().zip(self.now()).filter(|_| current) {
+                    schedule.check_proposal(&proposal, now)?;
+                }
+                if let Some(evidence) = self.detector.observe_proposal(&proposal)? {
+                    self.outputs.push(EngineOutput::Evidence(evidence));
+                }
+                if let Some(chain) = &self.chain {
+                    // Without a clock the drift check has nothing to compare against
+                    let now = self.now().unwrap_or(proposal.block.header.timestamp);
+                    let ctx = ValidationContext::new(&chain.parent, &chain.state, now);
+                    chain.validator.validate(&proposal.block, &ctx).into_result()?;
+                }
+                if self.proposals.add(proposal)? && current {
+                    self.state.proposals_received += 1;
+                }
+                Ok(())
+            }
+
+            ConsensusMessage::RankedVote {
+                height,
+                round,
+                block_hash,
+                efficiency_score,
+                validator_id,
+                signature,
+            } => {
+                if !matches!(self.state.step, RoundStep::ProposeWindow | RoundStep::Voting) {
+                    return Err(ConsensusError::InvalidVote(format!(
+                        "voting closed (step {})",
+                        self.state.step
+                    )));
+                }
+                if let Some((schedule, now)) = self.schedule().zip(self.now()) {
+                    schedule.check_vote(now)?;
+                }
+                let vote = RankedVote {
+                    height,
+                    round,
+                    block_hash,
+                    efficiency_score,
+                    validator_id,
+                    signature,
+                };
+                if let Some(evidence) = self.detector.observe_vote(&vote)? {
+                    self.outputs.push(EngineOutput::Evidence(evidence));
+                }
+                self.votes.add_vote(vote)?;
+                self.state.votes_received += 1;
+                self.update_lock();
+                // Honest validators are already voting: this window ran late
+                let voters = self.votes.vote_count() + self.votes.equivocators().count();
+                if self.state.step == RoundStep::ProposeWindow
+                    && voters > self.max_faulty()
+                    && self.proposals.proposals_through(height, round).next().is_some()
+                {
+                    self.state.step = RoundStep::Voting;
+                    self.cast_local_vote();
+                }
+                Ok(())
+            }
+
+            ConsensusMessage::Commit {
+                height,
+                round,
+                block_hash,
+                signatures,
+            } => {
+                if let Some(voted) = self.state.voted_round.filter(|&voted| round < voted) {
+                    return Err(ConsensusError::WrongRound {
+                        expected: voted,
+                        got: round,
+                    });
+                }
+                let block = self.proposal_block(&block_hash).ok_or_else(|| {
+                    ConsensusError::BlockValidation(format!(
+                        "commit for unknown block {}",
+                        hex::encode(block_hash)
+                    ))
+                })?;
+                let certificate = CommitCertificate::from_commit(
+                    height,
+                    round,
+                    block_hash,
+                    block.header.efficiency_score,
+                    &signatures,
+                );
+                certificate.verify(&self.committee, &self.config)?;
+                self.decide(block, certificate)
+            }
+        }
+    }
+
+    /// Adopt a block decided without this engine, e.g. one fetched by block
+    /// sync, and move to the next height
+    ///
+    /// The block must be at the current height and `certificate` must verify
+    /// against the committee. It is output as `EngineOutput::Decided`.
+    pub fn adopt_decided(
+        &mut self,
+        block: Block,
+        certificate: CommitCertificate,
+    ) -> ConsensusResult<()> {
+        if block.height() != self.state.height {
+            return Err(ConsensusError::WrongHeight {
+                expected: self.state.height,
+                got: block.height(),
+            });
+        }
+        if certificate.block_hash != block.hash()
+            || certificate.height != block.height()
+            || certificate.round < block.round()
+        {
+            return Err(ConsensusError::BlockValidation(
+                "certificate does not match the block".to_string(),
+            ));
+        }
+        certificate.verify(&self.committee, &self.config)?;
+        if let Some(chain) = &self.chain {
+            let now = self.now().unwrap_or(block.header.timestamp);
+            let ctx = ValidationContext::new(&chain.parent, &chain.state, now);
+            chain.validator.validate(&block, &ctx).into_result()?;
+        }
+        self.decide(block, certificate)
+    }
+
+    /// Fire the timer of the current step
+    pub fn on_timeout(&mut self) {
+        match self.state.step {
+            RoundStep::ProposeWindow => {
+                // Locked validators may still vote for an earlier round's block
+                let (height, round) = (self.state.height, self.state.round);
+                if self.proposals.proposals_through(height, round).next().is_none() {
+                    self.fail_round();
+                    return;
+                }
+                self.state.step = RoundStep::Voting;
+                self.cast_local_vote();
+            }
+            RoundStep::Voting => {
+                let (height, round) = (self.state.height, self.state.round);
+                let proposals = self.proposals.proposals_through(height, round);
+                let RankedQuorumResult::Winner {
+                    block_hash: winner, ..
+                } = self.votes.result(proposals)
+                else {
+                    let proposals = self.proposals.proposals_through(height, round);
+                    if let Some((leader, _)) = self.votes.leader(proposals) {
+                        self.state.leader_hash = Some(leader.proposal.block.hash());
+                    }
+                    self.fail_round();
+                    return;
+                };
+
+                self.state.winner_hash = Some(winner);
+                self.state.step = RoundStep::Finalize;
+                if self.local.is_some() {
+                    let message = self.certificate(&winner).to_message();
+                    self.outputs.push(EngineOutput::Broadcast(message));
+                }
+            }
+            RoundStep::Finalize | RoundStep::Committed => {
+                let winner = self
+                    .state
+                    .winner_hash
+                    .and_then(|hash| self.proposal_block(&hash));
+                match winner {
+                    Some(block) => {
+                        let certificate = self.certificate(&block.hash());
+                        if let Err(e) = self.decide(block, certificate) {
+                            tracing::error!("Failed to decide round {}: {}", self.state.round, e);
+                            self.fail_round();
+                        }
+                    }
+                    None => self.fail_round(),
+                }
+            }
+        }
+    }
+
+    /// Run the engine until `inbound` closes or `outbound` is dropped
+    pub async fn run(
+        mut self,
+        mut inbound: mpsc::Receiver<ConsensusMessage>,
+        outbound: mpsc::Sender<EngineOutput>,
+    ) {
+        loop {
+            let deadline = Instant::now() + self.step_timeout();
+            let position = (self.state.height, self.state.round, self.state.step);
+
+            while position == (self.state.height, self.state.round, self.state.step) {
+                tokio::select! {
+                    _ = sleep_until(deadline) => self.on_timeout(),
+                    message = inbound.recv() => match message {
+                        Some(message) => {
+                            if let Err(e) = self.handle_message(message) {
+                                tracing::debug!("Dropped consensus message: {}", e);
+                            }
+                        }
+                        None => return,
+                    },
+                }
+
+                for output in self.drain_outputs() {
+                    if outbound.send(output).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Local time, if the engine has a clock
+    fn now(&self) -> Option<u64> {
+        self.clock.as_ref().map(|clock| clock.unix_secs())
+    }
+
+    /// Sign and broadcast a vote, and lock on its block
+    ///
+    /// The vote goes to the locked block. An unlocked validator votes for
+    /// the block that led the latest round without a quorum, so split votes
+    /// converge, and otherwise for the best proposal.
+    fn cast_local_vote(&mut self) {
+        let Some(local) = &self.local else { return };
+        let (height, round) = (self.state.height, self.state.round);
+        let choice = match self.state.locked_hash.or(self.state.leader_hash) {
+            Some(hash) => self.proposals.find(height, &hash),
+            None => self.proposals.best(height, round),
+        };
+        let Some(choice) = choice else { return };
+        // Validators that missed an earlier round's block need it to follow
+        let carried = (choice.proposal.round < round).then(|| proposal_message(&choice.proposal));
+
+        let mut vote = RankedVote::new(
+            self.state.height,
+            self.state.round,
+            choice.proposal.block.hash(),
+            choice.verified_efficiency,
+            local.validator_id.clone(),
+        );
+        let signed = match self.last_signed.as_mut() {
+            Some(store) => store.sign_vote(&mut vote, local.signer.as_ref()),
+            None => vote.sign(local.signer.as_ref()).map_err(|e| {
+                ConsensusError::Internal(format!("signing failed: {}", e))
+            }),
+        };
+        if let Err(e) = signed {
+            tracing::warn!("Failed to sign ranked vote: {}", e);
+            return;
+        }
+        self.state.voted_round = Some(vote.round);
+        self.state.locked_hash = Some(vote.block_hash);
+
+        let message = ConsensusMessage::RankedVote {
+            height: vote.height,
+            round: vote.round,
+            block_hash: vote.block_hash,
+            efficiency_score: vote.efficiency_score,
+            validator_id: vote.validator_id.clone(),
+            signature: vote.signature,
+        };
+        if self.votes.add_vote(vote).is_ok() {
+            self.state.votes_received += 1;
+            self.outputs.extend(carried.map(EngineOutput::Broadcast));
+            self.outputs.push(EngineOutput::Broadcast(message));
+        }
+        self.update_lock();
+    }
+
+    /// Release the lock once a quorum of this round voted for other blocks
+    ///
+    /// Validators caught equivocating count toward that quorum: they are
+    /// provably faulty, so they cannot be the honest overlap that keeps a
+    /// certified block locked. The release is recorded in the
+    /// `LastSignedStore` first; if that fails, the lock is kept.
+    fn update_lock(&mut self) {
+        let Some(locked) = self.state.locked_hash else { return };
+        let against = self.votes.votes_against(&locked) + self.votes.equivocators().count();
+        if against < self.votes.threshold() {
+            return;
+        }
+        if let Some(store) = self.last_signed.as_mut() {
+            if let Err(e) = store.release_lock() {
+                tracing::warn!("Failed to record lock release: {}", e);
+                return;
+            }
+        }
+        self.state.locked_hash = None;
+    }
+
+    /// Certificate from the collected votes for `block_hash`
+    fn certificate(&self, block_hash: &[u8; 32]) -> CommitCertificate {
+        let efficiency_score = self
+            .proposals
+            .find(self.state.height, block_hash)
+            .map(|p| p.verified_efficiency)
+            .unwrap_or(0);
+        CommitCertificate::from_votes(
+            self.state.height,
+            self.state.round,
+            *block_hash,
+            efficiency_score,
+            self.votes.votes_for(block_hash),
+        )
+    }
+
+    /// Block of a proposal collected in any round of the current height
+    fn proposal_block(&self, block_hash: &[u8; 32]) -> Option<Block> {
+        self.proposals
+            .find(self.state.height, block_hash)
+            .map(|p| p.proposal.block.clone())
+    }
+
+    /// Hold a message for a later round, jumping there on a verified commit
+    /// or once more validators vote there than can be faulty
+    fn buffer(&mut self, message: ConsensusMessage) -> ConsensusResult<()> {
+        let round = message.round();
+        let refused = ConsensusError::WrongRound {
+            expected: self.state.round,
+            got: round,
+        };
+        if round - self.state.round > MAX_FUTURE_ROUNDS {
+            return Err(refused);
+        }
+        let jump = match signer(&message) {
+            Some(sender) => {
+                self.verify_signer(&message)?;
+                let buffered = self
+                    .future
+                    .values()
+                    .flatten()
+                    .filter(|m| signer(m) == Some(sender))
+                    .count();
+                if buffered >= MAX_FUTURE_MESSAGES_PER_SENDER {
+                    return Err(refused);
+                }
+                false
+            }
+            None if self.is_verified_commit(&message) => true,
+            None => {
+                return Err(ConsensusError::BlockValidation(
+                    "commit for a later round does not verify".to_string(),
+                ))
+            }
+        };
+        self.future.entry(round).or_default().push(message);
+        if jump || self.buffered_voters(round) > self.max_faulty() {
+            while self.state.round < round {
+                self.state.advance_round();
+            }
+            self.clear_round();
+        }
+        Ok(())
+    }
+
+    /// Check that a proposal or vote is signed by a known builder or
+    /// committee member
+    fn verify_signer(&self, message: &ConsensusMessage) -> ConsensusResult<()> {
+        match message {
+            ConsensusMessage::Proposal { .. } => {
+                self.proposals.verify_proposer(&carried_proposal(message)?)
+            }
+            ConsensusMessage::RankedVote {
+                height,
+                round,
+                block_hash,
+                efficiency_score,
+                validator_id,
+                signature,
+            } => {
+                let member = self
+                    .committee
+                    .iter()
+                    .find(|v| &v.validator_id == validator_id)
+                    .ok_or_else(|| ConsensusError::NotInCommittee(validator_id.clone()))?;
+                let vote = RankedVote {
+                    height: *height,
+                    round: *round,
+                    block_hash: *block_hash,
+                    efficiency_score: *efficiency_score,
+                    validator_id: validator_id.clone(),
+                    signature: *signature,
+                };
+                vote.verify_signature(&member.public_key)
+            }
+            ConsensusMessage::Commit { .. } => Ok(()),
+        }
+    }
+
+    /// Number of committee members with a vote buffered for `round`
+    ///
+    /// Buffered votes were verified by `verify_signer`.
+    fn buffered_voters(&self, round: u64) -> usize {
+        let Some(messages) = self.future.get(&round) else { return 0 };
+        let voters: BTreeSet<&str> = messages
+            .iter()
+            .filter_map(|m| match m {
+                ConsensusMessage::RankedVote { validator_id, .. } => Some(validator_id.as_str()),
+                _ => None,
+            })
+            .collect();
+        voters.len()
+    }
+
+    /// Most committee members that can be faulty without breaking quorums
+    fn max_faulty(&self) -> usize {
+        let size = self.committee.len();
+        size.saturating_sub(self.config.quorum_threshold(size))
+    }
+
+    /// Whether `message` is a commit for a pooled or buffered proposal whose
+    /// certificate verifies
+    fn is_verified_commit(&self, message: &ConsensusMessage) -> bool {
+        let ConsensusMessage::Commit {
+            height,
+            round,
+            block_hash,
+            signatures,
+        } = message
+        else {
+            return false;
+        };
+        // The block may be pooled already if locked validators carried it
+        let pooled = self
+            .proposals
+            .find(*height, block_hash)
+            .map(|p| p.verified_efficiency);
+        let proposal = pooled.or_else(|| {
+            self.future
+                .range(..=*round)
+                .flat_map(|(_, messages)| messages)
+                .find_map(|m| match m {
+                    ConsensusMessage::Proposal {
+                        block_hash: hash,
+                        efficiency_score,
+                        ..
+                    } if hash == block_hash => Some(*efficiency_score),
+                    _ => None,
+                })
+        });
+        proposal.is_some_and(|efficiency_score| {
+            let certificate = CommitCertificate::from_commit(
+                *height,
+                *round,
+                *block_hash,
+                efficiency_score,
+                signatures,
+            );
+            certificate.verify(&self.committee, &self.config).is_ok()
+        })
+    }
+
+    /// Emit `block` as decided and move to the next height
+    ///
+    /// With a chain, the block is applied to the account state first; if it
+    /// does not apply, nothing is decided and the chain is left untouched.
+    fn decide(&mut self, mut block: Block, certificate: CommitCertificate) -> ConsensusResult<()> {
+        certificate.attach_to(&mut block);
+        if let Some(chain) = self.chain.as_mut() {
+            chain.state.apply_block(&block).map_err(|e| {
+                ConsensusError::BlockValidation(format!(
+                    "decided block {} does not apply: {}",
+                    block.height(),
+                    e
+                ))
+            })?;
+            chain.parent = block.header.clone();
+        }
+        self.state.step = RoundStep::Committed;
+        self.state.winner_hash = Some(block.hash());
+        self.outputs.push(EngineOutput::Decided(DecidedBlock {
+            height: self.state.height,
+            round: certificate.round,
+            block,
+            certificate,
+        }));
+
+        self.state.new_height(self.state.height + 1);
+        self.detector.prune_below(self.state.height);
+        self.future.clear();
+        self.clear_round();
+        Ok(())
+    }
+
+    /// Give up on the current round
+    fn fail_round(&mut self) {
+        self.state.advance_round();
+        self.clear_round();
+    }
+
+    fn clear_round(&mut self) {
+        let (height, round) = (self.state.height, self.state.round);
+        // Earlier rounds stay pooled for late commits and locked votes
+        self.proposals.prune_before(height, 0);
+        self.proposals.set_reference(height, round, self.state.reference_efficiency);
+        self.votes = RankedVoteTally::new(height, round, &self.committee, &self.config);
+        self.replay_buffered();
+    }
+
+    /// Handle messages buffered for the current round, proposals first
+    fn replay_buffered(&mut self) {
+        let later = self.future.split_off(&(self.state.round + 1));
+        let mut messages = std::mem::replace(&mut self.future, later)
+            .remove(&self.state.round)
+            .unwrap_or_default();
+        messages.sort_by_key(|m| match m {
+            ConsensusMessage::Proposal { .. } => 0,
+            ConsensusMessage::RankedVote { .. } => 1,
+            ConsensusMessage::Commit { .. } => 2,
+        });
+        for message in messages {
+            if let Err(e) = self.handle_message(message) {
+                tracing::debug!("Dropped buffered consensus message: {}", e);
+            }
+        }
+    }
+}
+
+/// Signed proposal carried by a `Proposal` message, if the message fields
+/// match its block
+fn carried_proposal(message: &ConsensusMessage) -> ConsensusResult<BlockProposal> {
+    let ConsensusMessage::Proposal {
+        height,
+        round,
+        proposer_id,
+        block_hash,
+        efficiency_score,
+        block_data,
+        signature,
+    } = message
+    else {
+        return Err(ConsensusError::InvalidProposal("not a proposal".to_string()));
+    };
+    let block = Block::from_canonical_bytes(block_data)
+        .map_err(|e| ConsensusError::InvalidProposal(e.to_string()))?;
+    if block.hash() != *block_hash || block.header.efficiency_score != *efficiency_score {
+        return Err(ConsensusError::InvalidProposal(
+            "message fields do not match the block".to_string(),
+        ));
+    }
+    Ok(BlockProposal {
+        height: *height,
+        round: *round,
+        proposer_id: proposer_id.clone(),
+        block,
+        signature: *signature,
+    })
+}
+
+/// Signer a buffered proposal or vote counts against; commits have none
+fn signer(message: &ConsensusMessage) -> Option<&str> {
+    match message {
+        ConsensusMessage::Proposal { proposer_id, .. } => Some(proposer_id),
+        ConsensusMessage::RankedVote { validator_id, .. } => Some(validator_id),
+        ConsensusMessage::Commit { .. } => None,
+    }
+}
+
+/// `Proposal` message carrying a signed proposal
+fn proposal_message(proposal: &BlockProposal) -> ConsensusMessage {
+    ConsensusMessage::Proposal {
+        height: proposal.height,
+        round: proposal.round,
+        proposer_id: proposal.proposer_id.clone(),
+        block_hash: proposal.block.hash(),
+        efficiency_score: proposal.efficiency_score(),
+        block_data: proposal.block.to_canonical_bytes(),
+        signature: proposal.signature,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::{BlockHeader, Transaction};
+    use crate::clock::MockClock;
+    use crate::consensus::v1::efficiency::block_efficiency_score;
+    use crate::consensus::v1::test_support::{certify, committee};
+    use crate::crypto::common::traits::KeyPair;
+    use crate::crypto::Ed25519Keys;
+
+    /// Signed proposal of one transaction at `price`, claiming `claim` or
+    /// the true efficiency
+    fn proposal_claiming(
+        keys: &Ed25519Keys,
+        proposer: &str,
+        height: u64,
+        round: u64,
+        price: u64,
+        claim: Option<u64>,
+    ) -> ConsensusMessage {
+        let tx = Transaction::new(1, "c".to_string(), "s".to_string(), None, vec![], price, 1);
+        let mut header = BlockHeader::genesis("c");
+        header.height = height;
+        header.round = round;
+        header.proposer_id = proposer.to_string();
+        let mut block = Block::new(header, vec![tx]);
+        block.header.efficiency_score = claim
+            .unwrap_or_else(|| block_efficiency_score(&block, &ConsensusConfig::default()));
+        signed(keys, block)
+    }
+
+    fn signed(keys: &Ed25519Keys, block: Block) -> ConsensusMessage {
+        let (height, round) = (block.height(), block.round());
+        let proposer = block.header.proposer_id.clone();
+        let mut proposal = BlockProposal::new(height, round, proposer.clone(), block);
+        proposal.sign(keys).unwrap();
+
+        ConsensusMessage::Proposal {
+            height,
+            round,
+            proposer_id: proposer,
+            block_hash: proposal.block.hash(),
+            efficiency_score: proposal.efficiency_score(),
+            block_data: proposal.block.to_canonical_bytes(),
+            signature: proposal.signature,
+        }
+    }
+
+    fn proposal(
+        keys: &Ed25519Keys,
+        proposer: &str,
+        height: u64,
+        round: u64,
+        price: u64,
+    ) -> ConsensusMessage {
+        proposal_claiming(keys, proposer, height, round, price, None)
+    }
+
+    fn vote(
+        keys: &Ed25519Keys,
+        validator: &str,
+        round: u64,
+        p: &ConsensusMessage,
+    ) -> ConsensusMessage {
+        let ConsensusMessage::Proposal {
+            block_hash,
+            efficiency_score,
+            ..
+        } = p
+        else {
+            unreachable!()
+        };
+        let mut vote =
+            RankedVote::new(1, round, *block_hash, *efficiency_score, validator.to_string());
+        vote.sign(keys).unwrap();
+        ConsensusMessage::RankedVote {
+            height: 1,
+            round,
+            block_hash: vote.block_hash,
+            efficiency_score: vote.efficiency_score,
+            validator_id: vote.validator_id,
+            signature: vote.signature,
+        }
+    }
+
+    /// Commit of proposal `p` signed by the first `signers` validators
+    fn commit(keys: &[Ed25519Keys], signers: usize, p: &ConsensusMessage) -> ConsensusMessage {
+        let ConsensusMessage::Proposal { block_data, .. } = p else {
+            unreachable!()
+        };
+        let block = Block::from_canonical_bytes(block_data).unwrap();
+        let signers: Vec<usize> = (0..signers).collect();
+        certify(&block, keys, &signers).to_message()
+    }
+
+    fn block_hash(message: &ConsensusMessage) -> [u8; 32] {
+        match message {
+            ConsensusMessage::Proposal { block_hash, .. } => *block_hash,
+            _ => unreachable!(),
+        }
+    }
+
+    fn decided(engine: &mut ConsensusEngine) -> Vec<DecidedBlock> {
+        engine
+            .drain_outputs()
+            .into_iter()
+            .filter_map(|o| match o {
+                EngineOutput::Decided(d) => Some(d),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Empty block extending `parent` without changing `state`
+    fn empty_child(parent: &BlockHeader, state: &StateMachine, proposer: &str) -> Block {
+        let mut header = BlockHeader::genesis("c");
+        header.height = parent.height + 1;
+        header.previous_hash = parent.hash();
+        header.timestamp = parent.timestamp + 1;
+        header.state_root = state.state_root().root_hash;
+        header.proposer_id = proposer.to_string();
+        let mut block = Block::new(header, vec![]);
+        block.header.transactions_root = block.compute_transactions_root();
+        block
+    }
+
+    #[test]
+    fn test_round_decides_best_proposal() {
+        let (infos, keys) = committee(4);
+        let mut engine = ConsensusEngine::new(ConsensusConfig::default(), infos.clone(), 1);
+
+        let low = proposal(&keys[0], "v0", 1, 0, 500);
+        let high = proposal(&keys[1], "v1", 1, 0, 1000);
+        let winner = block_hash(&high);
+        engine.handle_message(low).unwrap();
+        engine.handle_message(high.clone()).unwrap();
+
+        engine.on_timeout();
+        assert_eq!(engine.round_state().step, RoundStep::Voting);
+        for (i, k) in keys.iter().enumerate().take(3) {
+            engine.handle_message(vote(k, &format!("v{}", i), 0, &high)).unwrap();
+        }
+
+        engine.on_timeout();
+        assert_eq!(engine.round_state().step, RoundStep::Finalize);
+        engine.on_timeout();
+
+        let decided = decided(&mut engine);
+        assert_eq!(decided.len(), 1);
+        assert_eq!(decided[0].block.hash(), winner);
+        assert_eq!(decided[0].certificate.signatures.len(), 3);
+        assert_eq!(CommitCertificate::from_block(&decided[0].block), decided[0].certificate);
+        decided[0].certificate.verify(&infos, engine.config()).unwrap();
+        assert_eq!(engine.round_state().height, 2);
+        assert_eq!(engine.round_state().round, 0);
+    }
+
+    #[test]
+    fn test_failures_advance_round() {
+        let (infos, keys) = committee(4);
+        let mut engine = ConsensusEngine::new(ConsensusConfig::default(), infos, 1);
+
+        // No proposals
+        engine.on_timeout();
+        assert_eq!(engine.round_state().round, 1);
+
+        // Proposal but only 2 of 4 votes (threshold 3)
+        let p = proposal(&keys[0], "v0", 1, 1, 1000);
+        engine.handle_message(p.clone()).unwrap();
+        engine.on_timeout();
+        for (i, k) in keys.iter().enumerate().take(2) {
+            engine.handle_message(vote(k, &format!("v{}", i), 1, &p)).unwrap();
+        }
+        engine.on_timeout();
+        assert_eq!(engine.round_state().round, 2);
+        assert_eq!(engine.round_state().step, RoundStep::ProposeWindow);
+        assert_eq!(engine.round_state().height, 1);
+    }
+
+    #[test]
+    fn test_rejects_bad_messages() {
+        let (infos, keys) = committee(4);
+        let outsider = Ed25519Keys::new().unwrap();
+        let mut engine = ConsensusEngine::new(ConsensusConfig::default(), infos, 1);
+
+        assert!(matches!(
+            engine.handle_message(proposal(&keys[0], "v0", 2, 0, 1000)),
+            Err(ConsensusError::WrongHeight { .. })
+        ));
+        assert!(matches!(
+            engine.handle_message(proposal(&outsider, "x", 1, 0, 1000)),
+            Err(ConsensusError::NotInCommittee(_))
+        ));
+        assert!(matches!(
+            engine.handle_message(proposal(&outsider, "v0", 1, 0, 1000)),
+            Err(ConsensusError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            engine.handle_message(proposal_claiming(&keys[0], "v0", 1, 0, 1000, Some(1))),
+            Err(ConsensusError::EfficiencyMismatch { .. })
+        ));
+
+        engine.set_reference_efficiency(u64::MAX);
+        assert!(matches!(
+            engine.handle_message(proposal(&keys[0], "v0", 1, 0, 1000)),
+            Err(ConsensusError::BelowReference { .. })
+        ));
+        engine.set_reference_efficiency(0);
+
+        let p = proposal(&keys[0], "v0", 1, 0, 1000);
+        let other = proposal(&keys[1], "v1", 1, 0, 900);
+        engine.handle_message(p.clone()).unwrap();
+        engine.handle_message(vote(&keys[1], "v1", 0, &p)).unwrap();
+        assert!(matches!(
+            engine.handle_message(vote(&keys[1], "v1", 0, &p)),
+            Err(ConsensusError::DuplicateVote(_))
+        ));
+        assert!(matches!(
+            engine.handle_message(vote(&keys[1], "v1", 0, &other)),
+            Err(ConsensusError::Equivocation { .. })
+        ));
+        let evidence: Vec<EquivocationEvidence> = engine
+            .drain_outputs()
+            .into_iter()
+            .filter_map(|o| match o {
+                EngineOutput::Evidence(e) => Some(e),
+                _ => None,
+            })
+            .collect();
+        // v0 signed two different blocks (one with a bad claim); v1 two votes
+        let offenders: Vec<&str> = evidence.iter().map(|e| e.validator_id()).collect();
+        assert_eq!(offenders, vec!["v0", "v1"]);
+    }
+
+    #[test]
+    fn test_validates_proposals_against_chain() {
+        let (infos, keys) = committee(4);
+        let config = ConsensusConfig {
+            chain_id: "c".to_string(),
+            ..ConsensusConfig::default()
+        };
+        let state = StateMachine::with_genesis("c", []);
+        let mut genesis = BlockHeader::genesis("c");
+        genesis.state_root = state.state_root().root_hash;
+        let mut engine =
+            ConsensusEngine::new(config, infos, 1).with_chain(genesis.clone(), state.clone());
+
+        let mut orphan = empty_child(&genesis, &state, "v0");
+        orphan.header.previous_hash = [9u8; 32];
+        assert!(matches!(
+            engine.handle_message(signed(&keys[0], orphan)),
+            Err(ConsensusError::BlockValidation(_))
+        ));
+        assert_eq!(engine.round_state().proposals_received, 0);
+
+        let first = empty_child(&genesis, &state, "v1");
+        let p = signed(&keys[1], first.clone());
+        engine.handle_message(p.clone()).unwrap();
+        engine.handle_message(commit(&keys, 3, &p)).unwrap();
+        assert_eq!(decided(&mut engine).len(), 1);
+
+        // The decided block is the parent of the next height
+        let sibling = empty_child(&genesis, &state, "v2");
+        assert!(matches!(
+            engine.handle_message(signed(&keys[2], empty_child(&sibling.header, &state, "v2"))),
+            Err(ConsensusError::BlockValidation(_))
+        ));
+        let second = signed(&keys[2], empty_child(&first.header, &state, "v2"));
+        engine.handle_message(second).unwrap();
+        assert_eq!(engine.round_state().proposals_received, 1);
+    }
+
+    #[test]
+    fn test_block_that_does_not_apply_is_not_decided() {
+        let (infos, keys) = committee(4);
+        let config = ConsensusConfig {
+            chain_id: "c".to_string(),
+            ..ConsensusConfig::default()
+        };
+        let state = StateMachine::with_genesis("c", []);
+        let mut genesis = BlockHeader::genesis("c");
+        genesis.state_root = state.state_root().root_hash;
+        let mut engine =
+            ConsensusEngine::new(config, infos, 1).with_chain(genesis.clone(), state.clone());
+
+        let p = signed(&keys[1], empty_child(&genesis, &state, "v1"));
+        engine.handle_message(p.clone()).unwrap();
+        // The state moves on underneath the admitted proposal
+        let sibling = empty_child(&genesis, &state, "v2");
+        engine.chain.as_mut().unwrap().state.apply_block(&sibling).unwrap();
+
+        assert!(matches!(
+            engine.handle_message(commit(&keys, 3, &p)),
+            Err(ConsensusError::BlockValidation(_))
+        ));
+        assert!(decided(&mut engine).is_empty());
+        assert_eq!(engine.chain.as_ref().unwrap().parent, genesis);
+        assert_eq!(engine.round_state().height, 1);
+        assert_ne!(engine.round_state().step, RoundStep::Committed);
+    }
+
+    #[test]
+    fn test_commits_from_any_round_decide() {
+        let (infos, keys) = committee(4);
+        let mut engine = ConsensusEngine::new(ConsensusConfig::default(), infos, 1);
+
+        // A later round's proposal waits for that round
+        let p1 = proposal(&keys[0], "v0", 1, 1, 1000);
+        engine.handle_message(p1.clone()).unwrap();
+        assert_eq!(engine.round_state().proposals_received, 0);
+        engine.on_timeout();
+        assert_eq!(engine.round_state().round, 1);
+        assert_eq!(engine.round_state().proposals_received, 1);
+        // Earlier rounds' votes are dropped, but their proposals are pooled
+        let p0 = proposal(&keys[1], "v1", 1, 0, 900);
+        assert!(matches!(
+            engine.handle_message(vote(&keys[2], "v2", 0, &p0)),
+            Err(ConsensusError::WrongRound { expected: 1, got: 0 })
+        ));
+        engine.handle_message(p0).unwrap();
+        assert_eq!(engine.round_state().proposals_received, 1);
+
+        // Round 1 fails locally, but its commit arrives late
+        engine.on_timeout();
+        engine.on_timeout();
+        assert_eq!(engine.round_state().round, 2);
+        engine.handle_message(commit(&keys, 3, &p1)).unwrap();
+        let decided_1 = decided(&mut engine);
+        assert_eq!(decided_1[0].round, 1);
+        assert_eq!(decided_1[0].block.hash(), block_hash(&p1));
+
+        // A verified commit for a later round moves the engine there
+        let p3 = proposal(&keys[1], "v1", 2, 3, 1000);
+        engine.handle_message(p3.clone()).unwrap();
+        assert!(matches!(
+            engine.handle_message(commit(&keys, 2, &p3)),
+            Err(ConsensusError::BlockValidation(_))
+        ));
+        assert_eq!(engine.round_state().round, 0);
+        engine.handle_message(commit(&keys, 3, &p3)).unwrap();
+        let decided_2 = decided(&mut engine);
+        assert_eq!((decided_2[0].height, decided_2[0].round), (2, 3));
+        assert_eq!(engine.round_state().height, 3);
+    }
+
+    #[test]
+    fn test_buffers_later_rounds_per_sender() {
+        let (infos, keys) = committee(4);
+        let mut engine = ConsensusEngine::new(ConsensusConfig::default(), infos, 1);
+        let a = proposal(&keys[1], "v1", 1, 0, 1000);
+        let b = proposal(&keys[2], "v2", 1, 0, 900);
+
+        // Too far ahead, forged, or from outside the committee
+        let far = MAX_FUTURE_ROUNDS + 1;
+        assert!(matches!(
+            engine.handle_message(vote(&keys[1], "v1", far, &a)),
+            Err(ConsensusError::WrongRound { .. })
+        ));
+        assert!(engine.handle_message(vote(&keys[2], "v1", 1, &a)).is_err());
+        let outsider = Ed25519Keys::new().unwrap();
+        assert!(matches!(
+            engine.handle_message(vote(&outsider, "x", 1, &a)),
+            Err(ConsensusError::NotInCommittee(_))
+        ));
+
+        // One validator filling its share does not crowd out the others
+        for round in 1..=MAX_FUTURE_ROUNDS {
+            engine.handle_message(vote(&keys[1], "v1", round, &a)).unwrap();
+            engine.handle_message(vote(&keys[1], "v1", round, &b)).unwrap();
+        }
+        assert!(matches!(
+            engine.handle_message(vote(&keys[1], "v1", 2, &b)),
+            Err(ConsensusError::WrongRound { .. })
+        ));
+        assert_eq!(engine.round_state().round, 0);
+        engine.handle_message(vote(&keys[2], "v2", 2, &a)).unwrap();
+        assert_eq!(engine.round_state().round, 2);
+    }
+
+    #[test]
+    fn test_locked_vote_outlives_delayed_commit() {
+        let (infos, keys) = committee(4);
+        let mut engine = ConsensusEngine::new(ConsensusConfig::default(), infos.clone(), 1)
+            .with_signer("v0".to_string(), keys[0].clone());
+
+        // Round 0: v0, v1 and v2 vote for A, but only v0's vote arrives in time
+        let a = proposal(&keys[1], "v1", 1, 0, 500);
+        engine.handle_message(a.clone()).unwrap();
+        engine.on_timeout();
+        engine.on_timeout();
+        assert_eq!(engine.round_state().round, 1);
+        engine.drain_outputs();
+
+        // Round 1: a better B arrives, but v0 stays locked on A
+        let b = proposal(&keys[2], "v2", 1, 1, 1000);
+        engine.handle_message(b).unwrap();
+        engine.on_timeout();
+        let sent: Vec<ConsensusMessage> = engine
+            .drain_outputs()
+            .into_iter()
+            .filter_map(|o| match o {
+                EngineOutput::Broadcast(m) => Some(m),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(&sent[0], ConsensusMessage::Proposal { round: 0, .. }));
+        assert!(matches!(
+            &sent[1],
+            ConsensusMessage::RankedVote { round: 1, block_hash: voted, .. }
+                if *voted == block_hash(&a)
+        ));
+
+        // The round-0 commit for A shows up only after v0 voted in round 1
+        assert!(matches!(
+            engine.handle_message(commit(&keys, 3, &a)),
+            Err(ConsensusError::WrongRound { expected: 1, got: 0 })
+        ));
+
+        // v1 and v2 are locked on A too, so A is decided in round 1
+        for (i, k) in keys.iter().enumerate().take(3).skip(1) {
+            engine.handle_message(vote(k, &format!("v{}", i), 1, &a)).unwrap();
+        }
+        engine.on_timeout();
+        assert_eq!(engine.round_state().step, RoundStep::Finalize);
+        engine.on_timeout();
+        let decided = decided(&mut engine);
+        assert_eq!(decided[0].block.hash(), block_hash(&a));
+        assert_eq!((decided[0].block.round(), decided[0].certificate.round), (0, 1));
+        assert_eq!(CommitCertificate::from_block(&decided[0].block), decided[0].certificate);
+        decided[0].certificate.verify(&infos, engine.config()).unwrap();
+    }
+
+    #[test]
+    fn test_restart_restores_lock() {
+        let path = std::env::temp_dir().join(format!(
+            "self-chain-engine-restart-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let (infos, keys) = committee(4);
+        let restart = || {
+            let store = LastSignedStore::open(&path, infos[0].public_key).unwrap();
+            ConsensusEngine::new(ConsensusConfig::default(), infos.clone(), 1)
+                .with_signer("v0".to_string(), keys[0].clone())
+                .with_last_signed(store)
+        };
+
+        // Round 0: v0 votes for A, then restarts before the round ends
+        let mut engine = restart();
+        let a = proposal(&keys[1], "v1", 1, 0, 500);
+        engine.handle_message(a).unwrap();
+        engine.on_timeout();
+        engine.on_timeout();
+        assert_eq!(engine.round_state().round, 1);
+        let locked = engine.round_state().locked_hash;
+        assert!(locked.is_some());
+
+        let mut engine = restart();
+        assert_eq!(engine.round_state().round, 0);
+        assert_eq!(engine.round_state().voted_round, Some(0));
+        assert_eq!(engine.round_state().locked_hash, locked);
+
+        // Round 1: a better B arrives, but the restarted v0 stays locked on A
+        engine.on_timeout();
+        engine.handle_message(proposal(&keys[2], "v2", 1, 1, 1000)).unwrap();
+        engine.on_timeout();
+        assert_eq!(engine.round_state().step, RoundStep::Voting);
+        assert!(!engine
+            .drain_outputs()
+            .iter()
+            .any(|o| matches!(o, EngineOutput::Broadcast(ConsensusMessage::RankedVote { .. }))));
+        assert_eq!(engine.round_state().locked_hash, locked);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_quorum_against_lock_releases_it() {
+        let (infos, keys) = committee(4);
+        let mut engine = ConsensusEngine::new(ConsensusConfig::default(), infos, 1)
+            .with_signer("v0".to_string(), keys[0].clone());
+
+        // v0 votes for the best proposal A; the others split over B and C
+        let a = proposal(&keys[1], "v1", 1, 0, 1000);
+        let b = proposal(&keys[2], "v2", 1, 0, 900);
+        let c = proposal(&keys[3], "v3", 1, 0, 800);
+        for p in [&a, &b, &c] {
+            engine.handle_message(p.clone()).unwrap();
+        }
+        engine.on_timeout();
+        assert_eq!(engine.round_state().locked_hash, Some(block_hash(&a)));
+        engine.handle_message(vote(&keys[1], "v1", 0, &b)).unwrap();
+        engine.handle_message(vote(&keys[2], "v2", 0, &b)).unwrap();
+        assert!(engine.round_state().locked_hash.is_some());
+        engine.handle_message(vote(&keys[3], "v3", 0, &c)).unwrap();
+        assert_eq!(engine.round_state().locked_hash, None);
+
+        // Without a quorum, v0 follows B, the leader, over a better newcomer
+        engine.on_timeout();
+        engine.handle_message(proposal(&keys[1], "v1", 1, 1, 2000)).unwrap();
+        engine.on_timeout();
+        assert_eq!(engine.round_state().locked_hash, Some(block_hash(&b)));
+
+        // Votes from more validators than can be faulty pull v0 to round 3,
+        // straight into voting
+        engine.handle_message(vote(&keys[1], "v1", 3, &b)).unwrap();
+        assert_eq!(engine.round_state().round, 1);
+        engine.handle_message(vote(&keys[2], "v2", 3, &b)).unwrap();
+        assert_eq!(engine.round_state().round, 3);
+        assert_eq!(engine.round_state().step, RoundStep::Voting);
+        assert_eq!(engine.round_state().voted_round, Some(3));
+    }
+
+    #[test]
+    fn test_clock_bounds_message_windows() {
+        let (infos, keys) = committee(4);
+        let config = ConsensusConfig {
+            chain_id: "c".to_string(),
+            ..ConsensusConfig::default()
+        };
+        let state = StateMachine::with_genesis("c", []);
+        let mut genesis = BlockHeader::genesis("c");
+        genesis.timestamp = 1_000;
+        genesis.state_root = state.state_root().root_hash;
+
+        // Every node derives the schedule from the parent, whenever it starts
+        let clock = MockClock::new(Duration::from_secs(1_050));
+        let engine = || {
+            ConsensusEngine::new(config.clone(), infos.clone(), 1)
+                .with_chain(genesis.clone(), state.clone())
+        };
+        assert!(engine().with_clock(clock.shared()).schedule().is_some());
+        assert!(engine().schedule().is_none());
+        let mut engine = engine().with_clock(clock.shared());
+        assert_eq!(engine.schedule().unwrap().start(), 1_060);
+
+        let mut block = empty_child(&genesis, &state, "v0");
+        block.header.timestamp = 1_060;
+        let p = signed(&keys[0], block);
+        clock.set(Duration::from_secs(1_116));
+        assert!(matches!(
+            engine.handle_message(p.clone()),
+            Err(ConsensusError::OutsideStepWindow { step: RoundStep::ProposeWindow, .. })
+        ));
+        clock.set(Duration::from_secs(1_070));
+        engine.handle_message(p.clone()).unwrap();
+
+        // A lagging validator's vote arriving after the voting window closed
+        engine.on_timeout();
+        engine.handle_message(vote(&keys[0], "v0", 0, &p)).unwrap();
+        clock.advance(Duration::from_secs(54));
+        assert!(matches!(
+            engine.handle_message(vote(&keys[1], "v1", 0, &p)),
+            Err(ConsensusError::OutsideStepWindow { step: RoundStep::Voting, .. })
+        ));
+        assert_eq!(engine.round_state().votes_received, 1);
+
+        // Later rounds follow at fixed offsets from the parent
+        engine.on_timeout();
+        assert_eq!(engine.round_state().round, 1);
+        assert_eq!(engine.schedule().unwrap().start(), 1_120);
+    }
+
+    #[tokio::test]
+    async fn test_run_on_timers() {
+        let (infos, keys) = committee(3);
+        let config = ConsensusConfig {
+            timeout_propose_window: Duration::from_millis(30),
+            timeout_voting: Duration::from_millis(30),
+            timeout_finalize: Duration::from_millis(10),
+            ..ConsensusConfig::default()
+        };
+        let mut local_keys = keys.into_iter();
+        let v0 = local_keys.next().unwrap();
+        let others: Vec<Ed25519Keys> = local_keys.collect();
+
+        let p = proposal(&v0, "v0", 1, 0, 1000);
+        let hash = block_hash(&p);
+        let engine = ConsensusEngine::new(config, infos, 1).with_signer("v0".to_string(), v0);
+
+        let (in_tx, in_rx) = mpsc::channel(16);
+        let (out_tx, mut out_rx) = mpsc::channel(16);
+        tokio::spawn(engine.run(in_rx, out_tx));
+
+        in_tx.send(vote(&others[0], "v1", 0, &p)).await.unwrap();
+        in_tx.send(vote(&others[1], "v2", 0, &p)).await.unwrap();
+        in_tx.send(p).await.unwrap();
+
+        let decided = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match out_rx.recv().await {
+                    Some(EngineOutput::Decided(d)) => return d,
+                    Some(_) => continue,
+                    None => panic!("engine stopped"),
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(decided.height, 1);
+        assert_eq!(decided.block.hash(), hash);
+        assert_eq!(decided.certificate.signatures.len(), 3);
+    }
+}
@@ -0,0 +1,510 @@
+//! PoAI v1 Equivocation Evidence
+//!
+//! Detects validators that sign two conflicting messages for the same
+//! `(height, round)` and packages both messages as `EquivocationEvidence`.
+//!
+//! ## Conflicts
+//!
+//! | Message | Conflict |
+//! |---------|----------|
+//! | `RankedVote` | Same validator, height and round; different vote hash |
+//! | `BlockProposal` | Same builder, height and round; different block hash |
+//!
+//! Proposals conflict because the protocol allows one proposal per builder
+//! per round (`constants::MAX_PROPOSALS_PER_BUILDER`), the same limit
+//! `ProposalPool` enforces.
+//!
+//! `FinalityConflict` is the chain-level counterpart: two valid
+//! `CommitCertificate`s for different blocks at the same height. When both
+//! certificates are from the same round, every validator that signed both is
+//! an offender. Across rounds signing both can be honest: a validator locked
+//! on a block is released once a quorum of a later round votes against it
+//! (see `ConsensusEngine`). Cross-round offenders are therefore named only
+//! from the ranked votes the evidence carries for the rounds between the two
+//! certificates: a validator that switched blocks with no unlock quorum in
+//! those votes broke its lock.
+//!
+//! ## Offline Verification
+//!
+//! Evidence carries both signed messages in full. `verify` needs only the
+//! offender's public key, so anyone holding the canonical bytes can check it
+//! and feed it into slashing or reputation.
+//!
+//! ## Canonical Encoding Order
+//!
+//! 1. `kind` (u8: 0 = votes, 1 = proposals)
+//! 2. `first` (canonical message)
+//! 3. `second` (canonical message)
+
+use super::certificate::CommitCertificate;
+use super::types::{constants, ConsensusConfig, ConsensusError, ConsensusResult, ValidatorInfo};
+use crate::blockchain::v1::codec::{
+    domain_hash, Canonical, CodecError, CodecResult, Decoder, Encoder,
+};
+use crate::blockchain::v1::{BlockProposal, RankedVote};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+/// Two conflicting messages signed by the same validator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EquivocationEvidence {
+    /// Ranked votes for different blocks in one round
+    ConflictingVotes {
+        first: Box<RankedVote>,
+        second: Box<RankedVote>,
+    },
+    /// Proposals of different blocks in one round
+    ConflictingProposals {
+        first: Box<BlockProposal>,
+        second: Box<BlockProposal>,
+    },
+}
+
+impl EquivocationEvidence {
+    /// Offending validator
+    pub fn validator_id(&self) -> &str {
+        match self {
+            Self::ConflictingVotes { first, .. } => &first.validator_id,
+            Self::ConflictingProposals { first, .. } => &first.proposer_id,
+        }
+    }
+
+    /// Height of the conflicting messages
+    pub fn height(&self) -> u64 {
+        match self {
+            Self::ConflictingVotes { first, .. } => first.height,
+            Self::ConflictingProposals { first, .. } => first.height,
+        }
+    }
+
+    /// Round of the conflicting messages
+    pub fn round(&self) -> u64 {
+        match self {
+            Self::ConflictingVotes { first, .. } => first.round,
+            Self::ConflictingProposals { first, .. } => first.round,
+        }
+    }
+
+    /// Evidence hash: `SHA256(DOMAIN_PREFIX_EVIDENCE || canonical bytes)`
+    pub fn hash(&self) -> [u8; 32] {
+        domain_hash(constants::DOMAIN_PREFIX_EVIDENCE, &self.to_canonical_bytes())
+    }
+
+    /// Check that both messages conflict and are signed by `public_key`
+    pub fn verify(&self, public_key: &[u8; 32]) -> ConsensusResult<()> {
+        match self {
+            Self::ConflictingVotes { first, second } => {
+                if first.validator_id != second.validator_id
+                    || first.height != second.height
+                    || first.round != second.round
+                    || first.hash() == second.hash()
+                {
+                    return Err(ConsensusError::InvalidVote(
+                        "votes do not conflict".to_string(),
+                    ));
+                }
+                first.verify_signature(public_key)?;
+                second.verify_signature(public_key)
+            }
+            Self::ConflictingProposals { first, second } => {
+                if first.proposer_id != second.proposer_id
+                    || first.height != second.height
+                    || first.round != second.round
+                    || first.block.hash() == second.block.hash()
+                {
+                    return Err(ConsensusError::InvalidProposal(
+                        "proposals do not conflict".to_string(),
+                    ));
+                }
+                first.verify_signature(public_key)?;
+                second.verify_signature(public_key)
+            }
+        }
+    }
+}
+
+impl Canonical for EquivocationEvidence {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Self::ConflictingVotes { first, second } => {
+                enc.put_u8(0);
+                first.encode(enc);
+                second.encode(enc);
+            }
+            Self::ConflictingProposals { first, second } => {
+                enc.put_u8(1);
+                first.encode(enc);
+                second.encode(enc);
+            }
+        }
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        match dec.get_u8()? {
+            0 => Ok(Self::ConflictingVotes {
+                first: Box::new(RankedVote::decode(dec)?),
+                second: Box::new(RankedVote::decode(dec)?),
+            }),
+            1 => Ok(Self::ConflictingProposals {
+                first: Box::new(BlockProposal::decode(dec)?),
+                second: Box::new(BlockProposal::decode(dec)?),
+            }),
+            tag => Err(CodecError::InvalidTag { field: "evidence kind", tag }),
+        }
+    }
+}
+
+/// Two commit certificates finalizing different blocks at one height
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalityConflict {
+    /// Certificate finalized first
+    pub first: CommitCertificate,
+
+    /// Conflicting certificate
+    pub second: CommitCertificate,
+
+    /// Ranked votes at this height from the rounds both certificates span,
+    /// as collected by the reporter
+    pub votes: Vec<RankedVote>,
+}
+
+impl FinalityConflict {
+    /// Conflict between two certificates, without intervening votes
+    pub fn new(first: CommitCertificate, second: CommitCertificate) -> Self {
+        Self {
+            first,
+            second,
+            votes: Vec::new(),
+        }
+    }
+
+    /// Height of both certificates
+    pub fn height(&self) -> u64 {
+        self.first.height
+    }
+
+    /// Validators the evidence shows breaking the voting rules, sorted by ID
+    ///
+    /// Same-round certificates name every validator that signed both. Across
+    /// rounds, a validator is named when it voted for one block and later for
+    /// another with no round in between where the other validators cast
+    /// `quorum_threshold` votes against the first block; without `votes`,
+    /// nobody is named.
+    pub fn offenders(&self, committee: &[ValidatorInfo], config: &ConsensusConfig) -> Vec<String> {
+        let mut offenders: Vec<String> = if self.first.round == self.second.round {
+            let second: HashSet<&str> = self.second.signers().collect();
+            self.first
+                .signers()
+                .filter(|id| second.contains(id))
+                .map(str::to_string)
+                .collect()
+        } else if self.votes.is_empty() {
+            Vec::new()
+        } else {
+            self.lock_breakers(config.quorum_threshold(committee.len()))
+        };
+        offenders.sort();
+        offenders.dedup();
+        offenders
+    }
+
+    /// Check that both certificates are valid and finalize different blocks,
+    /// and that every carried vote is a signed vote of the spanned rounds
+    pub fn verify(
+        &self,
+        committee: &[ValidatorInfo],
+        config: &ConsensusConfig,
+    ) -> ConsensusResult<()> {
+        if self.first.height != self.second.height
+            || self.first.block_hash == self.second.block_hash
+        {
+            return Err(ConsensusError::BlockValidation(
+                "certificates do not conflict".to_string(),
+            ));
+        }
+        self.first.verify(committee, config)?;
+        self.second.verify(committee, config)?;
+
+        let rounds = self.rounds();
+        for vote in &self.votes {
+            if vote.height != self.height() || !rounds.contains(&vote.round) {
+                return Err(ConsensusError::InvalidVote(
+                    "vote outside the conflicting rounds".to_string(),
+                ));
+            }
+            let member = committee
+                .iter()
+                .find(|v| v.validator_id == vote.validator_id)
+                .ok_or_else(|| ConsensusError::NotInCommittee(vote.validator_id.clone()))?;
+            vote.verify_signature(&member.public_key)?;
+        }
+        Ok(())
+    }
+
+    /// Rounds from the earlier certificate to the later one
+    fn rounds(&self) -> RangeInclusive<u64> {
+        let (a, b) = (self.first.round, self.second.round);
+        a.min(b)..=a.max(b)
+    }
+
+    /// Validators that voted for two blocks without an unlock quorum between
+    fn lock_breakers(&self, threshold: usize) -> Vec<String> {
+        let rounds = self.rounds();
+        let mut votes: Vec<RankedVote> = self
+            .first
+            .votes()
+            .chain(self.second.votes())
+            .chain(self.votes.iter().cloned())
+            .filter(|v| v.height == self.height() && rounds.contains(&v.round))
+            .collect();
+        votes.sort_by_key(|v| v.round);
+
+        // Other validators voting against `block_hash` in `round`
+        let against = |round: u64, block_hash: &[u8; 32], voter: &str| {
+            votes
+                .iter()
+                .filter(|v| v.round == round && &v.block_hash != block_hash && v.validator_id != voter)
+                .map(|v| v.validator_id.as_str())
+                .collect::<HashSet<_>>()
+                .len()
+        };
+
+        let mut offenders = Vec::new();
+        for (i, earlier) in votes.iter().enumerate() {
+            let broke = votes[i + 1..].iter().any(|later| {
+                later.validator_id == earlier.validator_id
+                    && later.block_hash != earlier.block_hash
+                    && (later.round == earlier.round
+                        || !(earlier.round..=later.round).any(|round| {
+                            against(round, &earlier.block_hash, &earlier.validator_id) >= threshold
+                        }))
+            });
+            if broke {
+                offenders.push(earlier.validator_id.clone());
+            }
+        }
+        offenders
+    }
+}
+
+impl Canonical for FinalityConflict {
+    fn encode(&self, enc: &mut Encoder) {
+        self.first.encode(enc);
+        self.second.encode(enc);
+        enc.put_seq(&self.votes);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            first: CommitCertificate::decode(dec)?,
+            second: CommitCertificate::decode(dec)?,
+            votes: dec.get_seq()?,
+        })
+    }
+}
+
+/// Message slot a validator may sign once per round
+type Slot = (u64, u64, String);
+
+/// Tracks signed votes and proposals and reports equivocation
+#[derive(Debug, Clone, Default)]
+pub struct EquivocationDetector {
+    validators: HashMap<String, [u8; 32]>,
+    votes: BTreeMap<Slot, RankedVote>,
+    proposals: BTreeMap<Slot, BlockProposal>,
+    reported: HashSet<(bool, Slot)>,
+    evidence: Vec<EquivocationEvidence>,
+}
+
+impl EquivocationDetector {
+    /// Create a detector for messages signed by `validators`
+    pub fn new(validators: &[ValidatorInfo]) -> Self {
+        Self {
+            validators: validators
+                .iter()
+                .map(|v| (v.validator_id.clone(), v.public_key))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Record a ranked vote, returning evidence on its first conflict
+    ///
+    /// The signature is verified first; unsigned or unknown votes are errors.
+    pub fn observe_vote(
+        &mut self,
+        vote: &RankedVote,
+    ) -> ConsensusResult<Option<EquivocationEvidence>> {
+        vote.verify_signature(&self.public_key(&vote.validator_id)?)?;
+
+        let slot = (vote.height, vote.round, vote.validator_id.clone());
+        let first = match self.votes.get(&slot) {
+            None => {
+                self.votes.insert(slot, vote.clone());
+                return Ok(None);
+            }
+            Some(first) if first.hash() == vote.hash() => return Ok(None),
+            Some(first) => Box::new(first.clone()),
+        };
+
+        Ok(self.report(false, slot, EquivocationEvidence::ConflictingVotes {
+            first,
+            second: Box::new(vote.clone()),
+        }))
+    }
+
+    /// Record a block proposal, returning evidence on its first conflict
+    ///
+    /// The signature is verified first; unsigned or unknown proposals are errors.
+    pub fn observe_proposal(
+        &mut self,
+        proposal: &BlockProposal,
+    ) -> ConsensusResult<Option<EquivocationEvidence>> {
+        proposal.verify_signature(&self.public_key(&proposal.proposer_id)?)?;
+
+        let slot = (proposal.height, proposal.round, proposal.proposer_id.clone());
+        let first = match self.proposals.get(&slot) {
+            None => {
+                self.proposals.insert(slot, proposal.clone());
+                return Ok(None);
+            }
+            Some(first) if first.block.hash() == proposal.block.hash() => return Ok(None),
+            Some(first) => Box::new(first.clone()),
+        };
+
+        Ok(self.report(true, slot, EquivocationEvidence::ConflictingProposals {
+            first,
+            second: Box::new(proposal.clone()),
+        }))
+    }
+
+    /// All evidence gathered so far
+    pub fn evidence(&self) -> &[EquivocationEvidence] {
+        &self.evidence
+    }
+
+    /// Forget messages and evidence below `height`
+    pub fn prune_below(&mut self, height: u64) {
+        let floor = (height, 0, String::new());
+        self.votes = self.votes.split_off(&floor);
+        self.proposals = self.proposals.split_off(&floor);
+        self.reported.retain(|(_, (h, _, _))| *h >= height);
+        self.evidence.retain(|e| e.height() >= height);
+    }
+
+    fn public_key(&self, validator_id: &str) -> ConsensusResult<[u8; 32]> {
+        self.validators
+            .get(validator_id)
+            .copied()
+            .ok_or_else(|| ConsensusError::NotInCommittee(validator_id.to_string()))
+    }
+
+    /// Store evidence once per offending slot
+    fn report(
+        &mut self,
+        is_proposal: bool,
+        slot: Slot,
+        evidence: EquivocationEvidence,
+    ) -> Option<EquivocationEvidence> {
+        if !self.reported.insert((is_proposal, slot)) {
+            return None;
+        }
+        tracing::warn!(
+            "Equivocation by {} at height {} round {}",
+            evidence.validator_id(),
+            evidence.height(),
+            evidence.round()
+        );
+        self.evidence.push(evidence.clone());
+        Some(evidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::{Block, BlockHeader};
+    use crate::consensus::v1::test_support::committee;
+    use crate::crypto::common::traits::KeyPair;
+    use crate::crypto::Ed25519Keys;
+
+    fn validator() -> (Vec<ValidatorInfo>, Ed25519Keys, [u8; 32]) {
+        let (validators, mut keys) = committee(1);
+        let public_key = validators[0].public_key;
+        (validators, keys.remove(0), public_key)
+    }
+
+    fn vote(keys: &Ed25519Keys, block_hash: [u8; 32]) -> RankedVote {
+        let mut vote = RankedVote::new(1, 0, block_hash, 100, "v0".to_string());
+        vote.sign(keys).unwrap();
+        vote
+    }
+
+    fn proposal(keys: &Ed25519Keys, timestamp: u64) -> BlockProposal {
+        let mut header = BlockHeader::genesis("c");
+        header.height = 1;
+        header.timestamp = timestamp;
+        header.proposer_id = "v0".to_string();
+        let mut proposal = BlockProposal::new(1, 0, "v0".to_string(), Block::new(header, vec![]));
+        proposal.sign(keys).unwrap();
+        proposal
+    }
+
+    #[test]
+    fn test_conflicting_votes_produce_evidence() {
+        let (validators, keys, public_key) = validator();
+        let mut detector = EquivocationDetector::new(&validators);
+
+        assert_eq!(detector.observe_vote(&vote(&keys, [1u8; 32])).unwrap(), None);
+        assert_eq!(detector.observe_vote(&vote(&keys, [1u8; 32])).unwrap(), None);
+
+        let evidence = detector.observe_vote(&vote(&keys, [2u8; 32])).unwrap().unwrap();
+        assert_eq!(evidence.validator_id(), "v0");
+        evidence.verify(&public_key).unwrap();
+
+        // Reported once per slot
+        assert_eq!(detector.observe_vote(&vote(&keys, [3u8; 32])).unwrap(), None);
+        assert_eq!(detector.evidence().len(), 1);
+    }
+
+    #[test]
+    fn test_conflicting_proposals_produce_evidence() {
+        let (validators, keys, public_key) = validator();
+        let mut detector = EquivocationDetector::new(&validators);
+
+        detector.observe_proposal(&proposal(&keys, 1)).unwrap();
+        let evidence = detector.observe_proposal(&proposal(&keys, 2)).unwrap().unwrap();
+        evidence.verify(&public_key).unwrap();
+
+        detector.prune_below(2);
+        assert!(detector.evidence().is_empty());
+    }
+
+    #[test]
+    fn test_evidence_round_trip_and_verification() {
+        let (validators, keys, public_key) = validator();
+        let mut detector = EquivocationDetector::new(&validators);
+        detector.observe_vote(&vote(&keys, [1u8; 32])).unwrap();
+        let evidence = detector.observe_vote(&vote(&keys, [2u8; 32])).unwrap().unwrap();
+
+        let decoded = EquivocationEvidence::from_canonical_bytes(&evidence.to_canonical_bytes())
+            .unwrap();
+        assert_eq!(decoded, evidence);
+        assert_eq!(decoded.hash(), evidence.hash());
+
+        // Wrong key or non-conflicting pair fails
+        let other = Ed25519Keys::new().unwrap();
+        assert!(evidence.verify(&other.public_key_bytes()).is_err());
+
+        let same = EquivocationEvidence::ConflictingVotes {
+            first: Box::new(vote(&keys, [1u8; 32])),
+            second: Box::new(vote(&keys, [1u8; 32])),
+        };
+        assert!(same.verify(&public_key).is_err());
+
+        // Forged signatures are rejected before recording
+        let mut forged = vote(&keys, [4u8; 32]);
+        forged.signature = [0u8; 64];
+        assert!(detector.observe_vote(&forged).is_err());
+    }
+}
@@ -0,0 +1,396 @@
+//! PoAI v1 Fork Choice and Finality
+//!
+//! Tracks the finalized chain and picks the head among competing
+//! non-finalized blocks.
+//!
+//! ## Finality
+//!
+//! `finalize` accepts a block only with a `CommitCertificate` that verifies
+//! against the height's committee. Finalized blocks must extend the finalized
+//! tip one height at a time, starting from a trusted root (genesis or a
+//! checkpoint).
+//!
+//! A second, different block with a valid certificate at an already finalized
+//! height breaks finality rule 4 ("no conflicting finalized block at same
+//! height"). It is refused with `ConsensusError::ConflictingFinality`, and
+//! both certificates are kept as `FinalityConflict` evidence.
+//!
+//! ## Fork Choice
+//!
+//! Candidates are non-finalized blocks above the finalized tip. A candidate
+//! must extend the finalized tip or another candidate, and at most
+//! `MAX_CANDIDATES_PER_HEIGHT` are tracked per height. Only candidates whose
+//! ancestry reaches the tip are considered, and the head is chosen by:
+//!
+//! 1. Highest height
+//! 2. Highest total `efficiency_score` above the finalized tip
+//! 3. Lowest block hash
+//!
+//! Without connected candidates the head is the finalized tip. Finalizing a
+//! block drops candidates at or below its height.
+
+use super::certificate::CommitCertificate;
+use super::evidence::FinalityConflict;
+use super::types::{constants, ConsensusConfig, ConsensusError, ConsensusResult, ValidatorInfo};
+use crate::blockchain::v1::{Block, BlockHeader};
+use std::collections::{BTreeMap, HashMap};
+
+/// Finalized block header and its finality proof
+#[derive(Debug, Clone)]
+pub struct FinalizedBlock {
+    pub header: BlockHeader,
+    pub certificate: CommitCertificate,
+}
+
+/// Finalized chain and competing candidate blocks
+#[derive(Debug, Clone)]
+pub struct ChainTipManager {
+    config: ConsensusConfig,
+    root: BlockHeader,
+    finalized: BTreeMap<u64, FinalizedBlock>,
+    candidates: HashMap<[u8; 32], BlockHeader>,
+    conflicts: Vec<FinalityConflict>,
+}
+
+impl ChainTipManager {
+    /// Create a manager on top of the trusted `root` header
+    pub fn new(config: ConsensusConfig, root: BlockHeader) -> Self {
+        Self {
+            config,
+            root,
+            finalized: BTreeMap::new(),
+            candidates: HashMap::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    /// Latest finalized header
+    pub fn finalized_tip(&self) -> &BlockHeader {
+        self.finalized
+            .values()
+            .next_back()
+            .map(|f| &f.header)
+            .unwrap_or(&self.root)
+    }
+
+    /// Latest finalized height
+    pub fn finalized_height(&self) -> u64 {
+        self.finalized_tip().height
+    }
+
+    /// Finalized block at `height`, if recorded
+    pub fn finalized(&self, height: u64) -> Option<&FinalizedBlock> {
+        self.finalized.get(&height)
+    }
+
+    /// Conflicting finalizations seen so far
+    pub fn conflicts(&self) -> &[FinalityConflict] {
+        &self.conflicts
+    }
+
+    /// Number of non-finalized candidates
+    pub fn candidate_count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Finalize `block` with `certificate` from `committee`
+    ///
+    /// Returns `false` if the block was already finalized.
+    pub fn finalize(
+        &mut self,
+        block: &Block,
+        certificate: CommitCertificate,
+        committee: &[ValidatorInfo],
+    ) -> ConsensusResult<bool> {
+        let header = &block.header;
+        let block_hash = block.hash();
+        if certificate.block_hash != block_hash
+            || certificate.height != header.height
+            || certificate.round < header.round
+        {
+            return Err(ConsensusError::BlockValidation(
+                "certificate does not match the block".to_string(),
+            ));
+        }
+        certificate.verify(committee, &self.config)?;
+
+        if header.height == self.root.height {
+            if block_hash == self.root.hash() {
+                return Ok(false);
+            }
+            return Err(ConsensusError::ConflictingFinality {
+                height: header.height,
+            });
+        }
+        if let Some(existing) = self.finalized.get(&header.height) {
+            if existing.certificate.block_hash == block_hash {
+                return Ok(false);
+            }
+            let conflict = FinalityConflict::new(existing.certificate.clone(), certificate);
+            tracing::error!(
+                "Conflicting finalized block at height {}, offenders {:?}",
+                conflict.height(),
+                conflict.offenders(committee, &self.config)
+            );
+            self.conflicts.push(conflict);
+            return Err(ConsensusError::ConflictingFinality {
+                height: header.height,
+            });
+        }
+
+        let tip = self.finalized_tip();
+        if header.height != tip.height + 1 {
+            return Err(ConsensusError::WrongHeight {
+                expected: tip.height + 1,
+                got: header.height,
+            });
+        }
+        if header.previous_hash != tip.hash() {
+            return Err(ConsensusError::BlockValidation(
+                "block does not extend the finalized tip".to_string(),
+            ));
+        }
+
+        let height = header.height;
+        self.finalized.insert(height, FinalizedBlock {
+            header: header.clone(),
+            certificate,
+        });
+        self.candidates.retain(|_, c| c.height > height);
+        Ok(true)
+    }
+
+    /// Track a non-finalized block for fork choice
+    ///
+    /// The block must extend the finalized tip or a tracked candidate.
+    /// Returns `false` if the block was already tracked.
+    pub fn add_candidate(&mut self, block: &Block) -> ConsensusResult<bool> {
+        let header = &block.header;
+        let tip = self.finalized_tip();
+        if header.height <= tip.height {
+            return Err(ConsensusError::WrongHeight {
+                expected: tip.height + 1,
+                got: header.height,
+            });
+        }
+        let hash = block.hash();
+        if self.candidates.contains_key(&hash) {
+            return Ok(false);
+        }
+        let linked = if header.height == tip.height + 1 {
+            header.previous_hash == tip.hash()
+        } else {
+            self.candidates
+                .get(&header.previous_hash)
+                .is_some_and(|parent| parent.height + 1 == header.height)
+        };
+        if !linked {
+            return Err(ConsensusError::BlockValidation(
+                "candidate does not extend a known block".to_string(),
+            ));
+        }
+        let at_height = self
+            .candidates
+            .values()
+            .filter(|c| c.height == header.height)
+            .count();
+        if at_height >= constants::MAX_CANDIDATES_PER_HEIGHT {
+            return Err(ConsensusError::BlockValidation(format!(
+                "too many candidates at height {}",
+                header.height
+            )));
+        }
+        self.candidates.insert(hash, header.clone());
+        Ok(true)
+    }
+
+    /// Head of the preferred chain
+    pub fn head(&self) -> &BlockHeader {
+        let mut best: Option<(&BlockHeader, u64, [u8; 32])> = None;
+        for (hash, header) in &self.candidates {
+            let Some(efficiency) = self.chain_efficiency(header) else {
+                continue;
+            };
+            let better = match best {
+                None => true,
+                Some((b, b_efficiency, b_hash)) => header
+                    .height
+                    .cmp(&b.height)
+                    .then(efficiency.cmp(&b_efficiency))
+                    .then(b_hash.cmp(hash))
+                    .is_gt(),
+            };
+            if better {
+                best = Some((header, efficiency, *hash));
+            }
+        }
+        best.map(|(header, _, _)| header)
+            .unwrap_or_else(|| self.finalized_tip())
+    }
+
+    /// Total efficiency from the finalized tip to `header`, if connected
+    fn chain_efficiency(&self, header: &BlockHeader) -> Option<u64> {
+        let tip_hash = self.finalized_tip().hash();
+        let mut total = header.efficiency_score;
+        let mut parent = header.previous_hash;
+        while parent != tip_hash {
+            let ancestor = self.candidates.get(&parent)?;
+            total = total.saturating_add(ancestor.efficiency_score);
+            parent = ancestor.previous_hash;
+        }
+        Some(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::{Canonical, RankedVote};
+    use crate::consensus::v1::test_support::{certify, committee};
+
+    fn child(parent: &BlockHeader, efficiency: u64, proposer: &str) -> Block {
+        let mut header = BlockHeader::genesis("c");
+        header.height = parent.height + 1;
+        header.previous_hash = parent.hash();
+        header.efficiency_score = efficiency;
+        header.proposer_id = proposer.to_string();
+        Block::new(header, vec![])
+    }
+
+    #[test]
+    fn test_refuses_conflicting_finalization_with_evidence() {
+        let (infos, keys) = committee(4);
+        let genesis = BlockHeader::genesis("c");
+        let mut chain = ChainTipManager::new(ConsensusConfig::default(), genesis.clone());
+
+        let a = child(&genesis, 10, "a");
+        assert!(chain.finalize(&a, certify(&a, &keys, &[0, 1, 2]), &infos).unwrap());
+        assert!(!chain.finalize(&a, certify(&a, &keys, &[1, 2, 3]), &infos).unwrap());
+        assert_eq!(chain.finalized_height(), 1);
+
+        let b = child(&genesis, 20, "b");
+        assert!(matches!(
+            chain.finalize(&b, certify(&b, &keys, &[1, 2, 3]), &infos),
+            Err(ConsensusError::ConflictingFinality { height: 1 })
+        ));
+        let config = ConsensusConfig::default();
+        let conflict = &chain.conflicts()[0];
+        assert_eq!(conflict.offenders(&infos, &config), vec!["v1", "v2"]);
+        conflict.verify(&infos, &config).unwrap();
+        assert_eq!(chain.finalized(1).unwrap().header.hash(), a.hash());
+
+        // Across rounds, signing both blocks alone names nobody
+        let mut d = child(&genesis, 40, "d");
+        d.header.round = 2;
+        let mut later = FinalityConflict::new(
+            chain.finalized(1).unwrap().certificate.clone(),
+            certify(&d, &keys, &[0, 1, 3]),
+        );
+        later.verify(&infos, &config).unwrap();
+        assert!(later.offenders(&infos, &config).is_empty());
+
+        // Round 1: v1, v2 and v3 vote for x, which releases v0's lock on a
+        // but not the locks of v1 and v2. Round 2: v2 moves on to y.
+        let vote = |round: u64, block_hash: [u8; 32], i: usize| {
+            let mut vote = RankedVote::new(1, round, block_hash, 0, format!("v{}", i));
+            vote.sign(&keys[i]).unwrap();
+            vote
+        };
+        later.votes = vec![
+            vote(1, [1u8; 32], 1),
+            vote(1, [1u8; 32], 2),
+            vote(1, [1u8; 32], 3),
+            vote(2, [2u8; 32], 2),
+        ];
+        later.verify(&infos, &config).unwrap();
+        assert_eq!(later.offenders(&infos, &config), vec!["v1", "v2"]);
+        let decoded = FinalityConflict::from_canonical_bytes(&later.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, later);
+
+        // Carried votes must be signed votes of the spanned rounds
+        later.votes.push(vote(3, [1u8; 32], 0));
+        assert!(later.verify(&infos, &config).is_err());
+
+        // Unproven blocks never reach the conflict check
+        let c = child(&genesis, 30, "c");
+        assert!(matches!(
+            chain.finalize(&c, certify(&c, &keys, &[0, 1]), &infos),
+            Err(ConsensusError::QuorumNotReached)
+        ));
+        assert_eq!(chain.conflicts().len(), 1);
+    }
+
+    #[test]
+    fn test_finalized_blocks_must_extend_the_tip() {
+        let (infos, keys) = committee(4);
+        let genesis = BlockHeader::genesis("c");
+        let mut chain = ChainTipManager::new(ConsensusConfig::default(), genesis.clone());
+
+        let a = child(&genesis, 10, "a");
+        let skip = child(&a.header, 10, "a");
+        assert!(matches!(
+            chain.finalize(&skip, certify(&skip, &keys, &[0, 1, 2]), &infos),
+            Err(ConsensusError::WrongHeight { expected: 1, got: 2 })
+        ));
+
+        let mut orphan = child(&genesis, 10, "o");
+        orphan.header.previous_hash = [9u8; 32];
+        assert!(matches!(
+            chain.finalize(&orphan, certify(&orphan, &keys, &[0, 1, 2]), &infos),
+            Err(ConsensusError::BlockValidation(_))
+        ));
+
+        let mut moved = certify(&a, &keys, &[0, 1, 2]);
+        moved.block_hash = orphan.hash();
+        assert!(chain.finalize(&a, moved, &infos).is_err());
+    }
+
+    #[test]
+    fn test_fork_choice_is_deterministic() {
+        let (infos, keys) = committee(4);
+        let genesis = BlockHeader::genesis("c");
+        let mut chain = ChainTipManager::new(ConsensusConfig::default(), genesis.clone());
+        assert_eq!(chain.head().hash(), genesis.hash());
+
+        // Two forks of equal length: higher total efficiency wins
+        let a1 = child(&genesis, 10, "a");
+        let a2 = child(&a1.header, 10, "a");
+        let b1 = child(&genesis, 30, "b");
+        let b2 = child(&b1.header, 1, "b");
+        for block in [&a1, &b1, &a2, &b2] {
+            assert!(chain.add_candidate(block).unwrap());
+        }
+        assert!(!chain.add_candidate(&a1).unwrap());
+        assert_eq!(chain.head().hash(), b2.hash());
+
+        // Equal height and efficiency: lowest hash wins
+        let c1 = child(&genesis, 30, "c");
+        let c2 = child(&c1.header, 1, "c");
+        chain.add_candidate(&c1).unwrap();
+        chain.add_candidate(&c2).unwrap();
+        assert_eq!(chain.head().hash(), b2.hash().min(c2.hash()));
+
+        // Longer chains win; unlinked blocks are refused
+        let a3 = child(&a2.header, 1, "a");
+        chain.add_candidate(&a3).unwrap();
+        let mut detached = child(&a3.header, 100, "x");
+        detached.header.previous_hash = [7u8; 32];
+        assert!(matches!(
+            chain.add_candidate(&detached),
+            Err(ConsensusError::BlockValidation(_))
+        ));
+        assert_eq!(chain.head().hash(), a3.hash());
+
+        // Candidates are capped per height (a1, b1 and c1 are at height 1)
+        for i in 3..constants::MAX_CANDIDATES_PER_HEIGHT {
+            assert!(chain.add_candidate(&child(&genesis, 0, &format!("s{}", i))).unwrap());
+        }
+        assert!(chain.add_candidate(&child(&genesis, 0, "spam")).is_err());
+
+        // Finalizing the other fork prunes stale candidates
+        chain.finalize(&b1, certify(&b1, &keys, &[0, 1, 2]), &infos).unwrap();
+        assert_eq!(chain.candidate_count(), 4);
+        assert_eq!(chain.head().hash(), b2.hash());
+        assert!(chain.add_candidate(&c1).is_err());
+    }
+}
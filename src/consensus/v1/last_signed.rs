@@ -0,0 +1,415 @@
+//! PoAI v1 Double-Sign Protection
+//!
+//! Crash-safe record of the last proposal and ranked vote a validator key
+//! signed, and of the block its votes are locked on, so a restarted
+//! validator cannot sign a conflicting message.
+//!
+//! ## Rules
+//!
+//! For each message kind, signing `(height, round, block)` is allowed if:
+//!
+//! - nothing was signed before, or
+//! - `(height, round)` is after the last signed slot, or
+//! - it is the same slot and the same block (re-signing is idempotent)
+//!
+//! Anything else, including a slot before the last one, is refused with
+//! `ConsensusError::DoubleSign`.
+//!
+//! ## Locking
+//!
+//! A signed vote also locks the key on its block at that height, in the
+//! same write. A vote for another block at the locked height is refused until
+//! `release_lock` records that the engine released the lock (see
+//! `ConsensusEngine`'s Locking section).
+//!
+//! ## Durability
+//!
+//! The new record is written to a temporary file, synced, and renamed over
+//! the old one before the message is signed. A crash at any point leaves
+//! either the old or the new record on disk, never a signature without its
+//! record.
+//!
+//! ## Canonical Encoding Order
+//!
+//! 1. `public_key` (32 bytes)
+//! 2. `proposal` (optional slot)
+//! 3. `vote` (optional slot)
+//! 4. `lock` (optional slot)
+//!
+//! A slot is `height` (u64), `round` (u64) and `hash` (32 bytes), the hash
+//! of the proposed or voted block.
+
+use super::types::{ConsensusError, ConsensusResult};
+use crate::blockchain::v1::codec::{Canonical, CodecResult, Decoder, Encoder};
+use crate::blockchain::v1::{BlockProposal, RankedVote};
+use crate::crypto::common::traits::Signer;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A signed `(height, round)` and the hash of the block it signed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignedSlot {
+    pub height: u64,
+    pub round: u64,
+    pub hash: [u8; 32],
+}
+
+impl SignedSlot {
+    /// Whether signing `next` after `self` cannot equivocate
+    fn allows(&self, next: &SignedSlot) -> bool {
+        match (next.height, next.round).cmp(&(self.height, self.round)) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Equal => next.hash == self.hash,
+            std::cmp::Ordering::Less => false,
+        }
+    }
+}
+
+impl Canonical for SignedSlot {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_u64(self.height);
+        enc.put_u64(self.round);
+        enc.put_fixed(&self.hash);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            height: dec.get_u64()?,
+            round: dec.get_u64()?,
+            hash: dec.get_fixed()?,
+        })
+    }
+}
+
+/// On-disk record for one validator key
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LastSigned {
+    public_key: [u8; 32],
+    proposal: Option<SignedSlot>,
+    vote: Option<SignedSlot>,
+    lock: Option<SignedSlot>,
+}
+
+impl Canonical for LastSigned {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_fixed(&self.public_key);
+        enc.put_option(self.proposal.as_ref());
+        enc.put_option(self.vote.as_ref());
+        enc.put_option(self.lock.as_ref());
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            public_key: dec.get_fixed()?,
+            proposal: dec.get_option()?,
+            vote: dec.get_option()?,
+            lock: dec.get_option()?,
+        })
+    }
+}
+
+/// Persistent last-signed record guarding one validator key
+#[derive(Debug)]
+pub struct LastSignedStore {
+    path: PathBuf,
+    record: LastSigned,
+}
+
+impl LastSignedStore {
+    /// Open or create the record for `public_key` at `path`
+    ///
+    /// Fails if the file is corrupt or belongs to a different key.
+    pub fn open(path: impl Into<PathBuf>, public_key: [u8; 32]) -> ConsensusResult<Self> {
+        let path = path.into();
+        let record = match fs::read(&path) {
+            Ok(bytes) => {
+                let record = LastSigned::from_canonical_bytes(&bytes).map_err(|e| {
+                    ConsensusError::Internal(format!(
+                        "corrupt last-signed record {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                if record.public_key != public_key {
+                    return Err(ConsensusError::Internal(format!(
+                        "last-signed record {} belongs to another key",
+                        path.display()
+                    )));
+                }
+                record
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => LastSigned {
+                public_key,
+                proposal: None,
+                vote: None,
+                lock: None,
+            },
+            Err(e) => return Err(io_error(&path, e)),
+        };
+        Ok(Self { path, record })
+    }
+
+    /// Last signed proposal slot
+    pub fn last_proposal(&self) -> Option<SignedSlot> {
+        self.record.proposal
+    }
+
+    /// Last signed ranked vote slot
+    pub fn last_vote(&self) -> Option<SignedSlot> {
+        self.record.vote
+    }
+
+    /// Block the key's votes are locked on: its height, the round of the
+    /// last vote for it, and its hash
+    pub fn locked(&self) -> Option<SignedSlot> {
+        self.record.lock
+    }
+
+    /// Record and sign a ranked vote, locking on its block and refusing
+    /// conflicting ones
+    pub fn sign_vote<S: Signer + ?Sized>(
+        &mut self,
+        vote: &mut RankedVote,
+        signer: &S,
+    ) -> ConsensusResult<()> {
+        let slot = SignedSlot {
+            height: vote.height,
+            round: vote.round,
+            hash: vote.block_hash,
+        };
+        if let Some(last) = self.record.vote.filter(|last| !last.allows(&slot)) {
+            return Err(refused("ranked vote", &last, &slot));
+        }
+        if let Some(lock) = self
+            .record
+            .lock
+            .filter(|lock| lock.height == slot.height && lock.hash != slot.hash)
+        {
+            return Err(ConsensusError::DoubleSign(format!(
+                "ranked vote at height {} round {} leaves the block locked in round {}",
+                slot.height, slot.round, lock.round
+            )));
+        }
+        if self.record.vote != Some(slot) || self.record.lock != Some(slot) {
+            let record = LastSigned {
+                vote: Some(slot),
+                lock: Some(slot),
+                ..self.record.clone()
+            };
+            self.persist(record)?;
+        }
+        vote.sign(signer)
+            .map_err(|e| ConsensusError::Internal(format!("signing failed: {}", e)))
+    }
+
+    /// Record that the lock was released, so votes may move to another block
+    pub fn release_lock(&mut self) -> ConsensusResult<()> {
+        if self.record.lock.is_none() {
+            return Ok(());
+        }
+        let record = LastSigned {
+            lock: None,
+            ..self.record.clone()
+        };
+        self.persist(record)
+    }
+
+    /// Record and sign a block proposal, refusing conflicting ones
+    pub fn sign_proposal<S: Signer + ?Sized>(
+        &mut self,
+        proposal: &mut BlockProposal,
+        signer: &S,
+    ) -> ConsensusResult<()> {
+        let slot = SignedSlot {
+            height: proposal.height,
+            round: proposal.round,
+            hash: proposal.block.hash(),
+        };
+        if let Some(last) = self.record.proposal.filter(|last| !last.allows(&slot)) {
+            return Err(refused("proposal", &last, &slot));
+        }
+        if self.record.proposal != Some(slot) {
+            let record = LastSigned {
+                proposal: Some(slot),
+                ..self.record.clone()
+            };
+            self.persist(record)?;
+        }
+        proposal
+            .sign(signer)
+            .map_err(|e| ConsensusError::Internal(format!("signing failed: {}", e)))
+    }
+
+    /// Atomically replace the on-disk record, then adopt it
+    fn persist(&mut self, record: LastSigned) -> ConsensusResult<()> {
+        let tmp = self.path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut file = File::create(&tmp)?;
+            file.write_all(&record.to_canonical_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp, &self.path)?;
+            sync_dir(&self.path)
+        };
+        write().map_err(|e| io_error(&self.path, e))?;
+        self.record = record;
+        Ok(())
+    }
+}
+
+/// Sync the directory entry so the rename survives a crash
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(dir) => File::open(dir)?.sync_all(),
+        None => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> ConsensusError {
+    ConsensusError::Internal(format!("last-signed record {}: {}", path.display(), e))
+}
+
+fn refused(kind: &str, last: &SignedSlot, next: &SignedSlot) -> ConsensusError {
+    ConsensusError::DoubleSign(format!(
+        "{} at height {} round {} conflicts with last signed height {} round {}",
+        kind, next.height, next.round, last.height, last.round
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::{Block, BlockHeader};
+    use crate::consensus::v1::test_support::committee;
+    use crate::crypto::Ed25519Keys;
+
+    fn record_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "self-chain-last-signed-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn keys() -> (Ed25519Keys, [u8; 32]) {
+        let (validators, mut keys) = committee(1);
+        (keys.remove(0), validators[0].public_key)
+    }
+
+    fn vote(height: u64, round: u64, block: u8) -> RankedVote {
+        RankedVote::new(height, round, [block; 32], 100, "v0".to_string())
+    }
+
+    #[test]
+    fn test_refuses_conflicting_votes_across_restarts() {
+        let path = record_path("votes");
+        let (keys, public_key) = keys();
+
+        let mut store = LastSignedStore::open(&path, public_key).unwrap();
+        let mut first = vote(5, 1, 1);
+        store.sign_vote(&mut first, &keys).unwrap();
+        first.verify_signature(&public_key).unwrap();
+        drop(store);
+
+        // Simulated restart
+        let mut store = LastSignedStore::open(&path, public_key).unwrap();
+        assert_eq!(store.last_vote().unwrap().hash, first.block_hash);
+        assert!(matches!(
+            store.sign_vote(&mut vote(5, 1, 2), &keys),
+            Err(ConsensusError::DoubleSign(_))
+        ));
+        assert!(matches!(
+            store.sign_vote(&mut vote(5, 0, 1), &keys),
+            Err(ConsensusError::DoubleSign(_))
+        ));
+
+        // Same vote again, and later rounds, are allowed
+        let mut again = vote(5, 1, 1);
+        store.sign_vote(&mut again, &keys).unwrap();
+        assert_eq!(again.signature, first.signature);
+        store.sign_vote(&mut vote(5, 2, 1), &keys).unwrap();
+        store.sign_vote(&mut vote(6, 0, 3), &keys).unwrap();
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lock_survives_restarts_until_released() {
+        let path = record_path("lock");
+        let (keys, public_key) = keys();
+
+        let mut store = LastSignedStore::open(&path, public_key).unwrap();
+        store.sign_vote(&mut vote(5, 1, 1), &keys).unwrap();
+        drop(store);
+
+        // Simulated restart: a later round may not move to another block
+        let mut store = LastSignedStore::open(&path, public_key).unwrap();
+        let lock = store.locked().unwrap();
+        assert_eq!((lock.height, lock.round, lock.hash), (5, 1, [1u8; 32]));
+        assert!(matches!(
+            store.sign_vote(&mut vote(5, 2, 2), &keys),
+            Err(ConsensusError::DoubleSign(_))
+        ));
+        store.sign_vote(&mut vote(5, 2, 1), &keys).unwrap();
+        assert_eq!(store.locked().unwrap().round, 2);
+
+        store.release_lock().unwrap();
+        let mut store = LastSignedStore::open(&path, public_key).unwrap();
+        assert_eq!(store.locked(), None);
+        store.sign_vote(&mut vote(5, 3, 2), &keys).unwrap();
+        assert_eq!(store.locked().unwrap().hash, [2u8; 32]);
+
+        // A lock never carries over to the next height
+        store.sign_vote(&mut vote(6, 0, 3), &keys).unwrap();
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_proposals_are_tracked_separately() {
+        let path = record_path("proposals");
+        let (keys, public_key) = keys();
+        let mut store = LastSignedStore::open(&path, public_key).unwrap();
+
+        let proposal = |timestamp| {
+            let mut header = BlockHeader::genesis("c");
+            header.height = 5;
+            header.timestamp = timestamp;
+            BlockProposal::new(5, 0, "v0".to_string(), Block::new(header, vec![]))
+        };
+        store.sign_proposal(&mut proposal(1), &keys).unwrap();
+        store.sign_vote(&mut vote(5, 0, 1), &keys).unwrap();
+        assert!(matches!(
+            store.sign_proposal(&mut proposal(2), &keys),
+            Err(ConsensusError::DoubleSign(_))
+        ));
+
+        let store = LastSignedStore::open(&path, public_key).unwrap();
+        assert_eq!(store.last_proposal().map(|s| s.height), Some(5));
+        assert_eq!(store.last_vote().map(|s| s.height), Some(5));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_corrupt_records() {
+        let path = record_path("foreign");
+        let (keys, public_key) = keys();
+        let mut store = LastSignedStore::open(&path, public_key).unwrap();
+        store.sign_vote(&mut vote(1, 0, 1), &keys).unwrap();
+
+        assert!(LastSignedStore::open(&path, [9u8; 32]).is_err());
+        fs::write(&path, b"garbage").unwrap();
+        assert!(LastSignedStore::open(&path, public_key).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
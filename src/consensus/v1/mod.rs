@@ -13,6 +13,52 @@
 //! | `COMMITTEE_SIZE_MIN` | 10 | Minimum committee members |
 //! | `COMMITTEE_SIZE_MAX` | 100 | Maximum committee members |
 //!
+//! ## Block Validation
+//!
+//! `BlockValidator` checks a block against its parent and the local state and
+//! returns a `ValidationResult` listing every failed rule.
+//! `SelectionComplianceChecker` audits a block's 20/20/50/10 selection against
+//! a mempool snapshot.
+//!
+//! `RoundSchedule` bounds proposal and ranked vote timing by the round's step
+//! windows and `clock_drift_tolerance`.
+//!
+//! ## Reference Block
+//!
+//! `generate_reference_block` deterministically derives the round's reference
+//! block from a mempool snapshot and the parent header, so every node agrees
+//! on `reference_efficiency`.
+//!
+//! ## Committee Selection
+//!
+//! `CommitteeSelector` samples each height's committee from the eligible
+//! validators, seeded by the previous block hash and the height, optionally
+//! weighted by stake or reputation.
+//!
+//! ## Consensus Engine
+//!
+//! `ConsensusEngine` drives `RoundState` through the round steps on the
+//! configured timeouts and outputs decided blocks. `RankedVoteTally` counts
+//! the round's ranked votes and reports a `RankedQuorumResult`; `ProposalPool`
+//! validates and ranks the builders' proposals. `EquivocationDetector` turns
+//! conflicting signed messages into verifiable `EquivocationEvidence`, and
+//! every decided block carries a `CommitCertificate` as its finality proof.
+//! `LastSignedStore` persists each validator key's last signed vote and
+//! proposal so restarts cannot produce a double sign.
+//!
+//! ## Fork Choice
+//!
+//! `ChainTipManager` records finalized blocks with their commit certificates,
+//! refuses conflicting finalizations with `FinalityConflict` evidence, and
+//! deterministically picks the head among non-finalized candidates.
+//!
+//! ## Simulation
+//!
+//! `Simulation` runs validators, builders and a coordinator in one process on
+//! virtual time with seeded scheduling and injected `Fault`s. The coordinator
+//! ratifies finalized blocks through `VotingSystem`, and the `SimReport`
+//! checks the safety invariants.
+//!
 //! ## Round Steps
 //!
 //! ```text
@@ -23,10 +69,45 @@
 //! └───────────────┘  └───────────────┘  └───────────────┘
 //! ```
 
+pub mod certificate;
+pub mod committee;
+pub mod efficiency;
+pub mod engine;
+pub mod evidence;
+pub mod fork_choice;
+pub mod last_signed;
+pub mod pool;
+pub mod reference;
+pub mod selection;
+pub mod simulation;
+pub mod tally;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod timing;
 pub mod types;
+pub mod validation;
 
 pub use types::{
     ConsensusConfig, RoundStep, RoundState, ValidatorInfo,
-    ConsensusMessage, ConsensusError, ConsensusResult,
+    ConsensusMessage, CommitSignatureMsg, ConsensusError, ConsensusResult,
     constants,
 };
+pub use certificate::CommitCertificate;
+pub use committee::CommitteeSelector;
+pub use engine::{ConsensusEngine, DecidedBlock, EngineOutput};
+pub use last_signed::{LastSignedStore, SignedSlot};
+pub use pool::ProposalPool;
+pub use evidence::{EquivocationDetector, EquivocationEvidence, FinalityConflict};
+pub use fork_choice::{ChainTipManager, FinalizedBlock};
+pub use efficiency::{block_efficiency_score, compute_efficiency_score};
+pub use reference::{generate_reference_block, select_reference_transactions};
+pub use simulation::{Fault, SimConfig, SimReport, Simulation};
+pub use tally::{rank_proposals, RankedQuorumResult, RankedVoteTally};
+pub use timing::RoundSchedule;
+pub use selection::{
+    CategoryDeviation, SelectionCategory, SelectionComplianceChecker, SelectionReport,
+};
+pub use validation::{
+    BlockValidator, BlockValidationError, SelectionCompliance, ValidationContext,
+    ValidationResult,
+};
@@ -0,0 +1,298 @@
+//! PoAI v1 Proposal Pool
+//!
+//! Collects builder proposals during the propose window, keyed by
+//! `(height, round)`.
+//!
+//! ## Admission
+//!
+//! A proposal is accepted only if:
+//!
+//! 1. Its height and round match the block header, and `proposer_id` matches
+//!    the header's proposer
+//! 2. The proposer is a known builder and the signature verifies
+//! 3. The builder has no other proposal in the round
+//!    (`constants::MAX_PROPOSALS_PER_BUILDER`)
+//! 4. The recomputed efficiency equals the claimed `efficiency_score`
+//!    (`EfficiencyMismatch` otherwise)
+//! 5. The efficiency reaches the round's reference (`BelowReference`
+//!    otherwise); rounds without a reference use 0
+//!
+//! Resubmitting an already pooled block is a no-op, so gossip duplicates are
+//! harmless.
+//!
+//! ## Ranking
+//!
+//! `ranked` orders a round's proposals with `rank_proposals`: highest
+//! verified efficiency first, lowest proposer ID on ties.
+
+use super::efficiency::block_efficiency_score;
+use super::tally::rank_proposals;
+use super::types::{constants, ConsensusConfig, ConsensusError, ConsensusResult, ValidatorInfo};
+use crate::blockchain::v1::{BlockProposal, ValidatedProposal};
+use std::collections::{BTreeMap, HashMap};
+
+/// Proposals and reference for a single round
+#[derive(Debug, Clone, Default)]
+struct RoundProposals {
+    reference_efficiency: u64,
+    proposals: HashMap<[u8; 32], ValidatedProposal>,
+    /// Admissions per builder, including proposals dropped since
+    per_builder: HashMap<String, usize>,
+}
+
+/// Validated builder proposals keyed by `(height, round)`
+#[derive(Debug, Clone)]
+pub struct ProposalPool {
+    config: ConsensusConfig,
+    builders: HashMap<String, [u8; 32]>,
+    rounds: BTreeMap<(u64, u64), RoundProposals>,
+}
+
+impl ProposalPool {
+    /// Create a pool accepting proposals from `builders`
+    pub fn new(config: ConsensusConfig, builders: &[ValidatorInfo]) -> Self {
+        Self {
+            config,
+            builders: builders
+                .iter()
+                .map(|b| (b.validator_id.clone(), b.public_key))
+                .collect(),
+            rounds: BTreeMap::new(),
+        }
+    }
+
+    /// Set the reference efficiency proposals must reach in a round
+    ///
+    /// Already pooled proposals that fall below the new reference are dropped,
+    /// but still count toward their builder's `MAX_PROPOSALS_PER_BUILDER`.
+    pub fn set_reference(&mut self, height: u64, round: u64, reference_efficiency: u64) {
+        let entry = self.rounds.entry((height, round)).or_default();
+        entry.reference_efficiency = reference_efficiency;
+        entry.proposals.retain(|_, p| {
+            p.beats_reference = p.verified_efficiency >= reference_efficiency;
+            p.efficiency_delta = p.verified_efficiency as i64 - reference_efficiency as i64;
+            p.beats_reference
+        });
+    }
+
+    /// Reference efficiency of a round
+    pub fn reference(&self, height: u64, round: u64) -> u64 {
+        self.rounds
+            .get(&(height, round))
+            .map(|r| r.reference_efficiency)
+            .unwrap_or(0)
+    }
+
+    /// Check that `proposal` is signed by a known builder
+    pub fn verify_proposer(&self, proposal: &BlockProposal) -> ConsensusResult<()> {
+        let public_key = self
+            .builders
+            .get(&proposal.proposer_id)
+            .ok_or_else(|| ConsensusError::NotInCommittee(proposal.proposer_id.clone()))?;
+        proposal.verify_signature(public_key)
+    }
+
+    /// Validate and pool a proposal
+    ///
+    /// Returns `false` if the block was already pooled.
+    pub fn add(&mut self, proposal: BlockProposal) -> ConsensusResult<bool> {
+        let header = &proposal.block.header;
+        if header.height != proposal.height
+            || header.round != proposal.round
+            || header.proposer_id != proposal.proposer_id
+        {
+            return Err(ConsensusError::InvalidProposal(
+                "proposal fields do not match the block header".to_string(),
+            ));
+        }
+        self.verify_proposer(&proposal)?;
+
+        let block_hash = proposal.block.hash();
+        let round = self.rounds.entry((proposal.height, proposal.round)).or_default();
+        if round.proposals.contains_key(&block_hash) {
+            return Ok(false);
+        }
+        let submitted = round.per_builder.get(&proposal.proposer_id).copied().unwrap_or(0);
+        if submitted >= constants::MAX_PROPOSALS_PER_BUILDER {
+            return Err(ConsensusError::InvalidProposal(format!(
+                "builder {} exceeded {} proposals per round",
+                proposal.proposer_id,
+                constants::MAX_PROPOSALS_PER_BUILDER
+            )));
+        }
+
+        let verified = block_efficiency_score(&proposal.block, &self.config);
+        if verified != proposal.efficiency_score() {
+            return Err(ConsensusError::EfficiencyMismatch {
+                claimed: proposal.efficiency_score(),
+                actual: verified,
+            });
+        }
+        if verified < round.reference_efficiency {
+            return Err(ConsensusError::BelowReference {
+                proposal: verified,
+                reference: round.reference_efficiency,
+            });
+        }
+
+        *round.per_builder.entry(proposal.proposer_id.clone()).or_default() += 1;
+        let validated = ValidatedProposal::new(proposal, verified, round.reference_efficiency);
+        round.proposals.insert(block_hash, validated);
+        Ok(true)
+    }
+
+    /// Pooled proposal for `block_hash`
+    pub fn get(
+        &self,
+        height: u64,
+        round: u64,
+        block_hash: &[u8; 32],
+    ) -> Option<&ValidatedProposal> {
+        self.rounds.get(&(height, round))?.proposals.get(block_hash)
+    }
+
+    /// Pooled proposal for `block_hash` from any round of `height`
+    pub fn find(&self, height: u64, block_hash: &[u8; 32]) -> Option<&ValidatedProposal> {
+        self.rounds
+            .range((height, 0)..=(height, u64::MAX))
+            .find_map(|(_, r)| r.proposals.get(block_hash))
+    }
+
+    /// Number of proposals pooled for a round
+    pub fn len(&self, height: u64, round: u64) -> usize {
+        self.rounds
+            .get(&(height, round))
+            .map(|r| r.proposals.len())
+            .unwrap_or(0)
+    }
+
+    /// Whether a round has no proposals
+    pub fn is_empty(&self, height: u64, round: u64) -> bool {
+        self.len(height, round) == 0
+    }
+
+    /// A round's proposals in no particular order
+    pub fn proposals(&self, height: u64, round: u64) -> impl Iterator<Item = &ValidatedProposal> {
+        self.rounds
+            .get(&(height, round))
+            .into_iter()
+            .flat_map(|r| r.proposals.values())
+    }
+
+    /// Proposals of every round of `height` up to and including `round`
+    pub fn proposals_through(
+        &self,
+        height: u64,
+        round: u64,
+    ) -> impl Iterator<Item = &ValidatedProposal> {
+        self.rounds
+            .range((height, 0)..=(height, round))
+            .flat_map(|(_, r)| r.proposals.values())
+    }
+
+    /// A round's proposals, best first
+    pub fn ranked(&self, height: u64, round: u64) -> Vec<&ValidatedProposal> {
+        match self.rounds.get(&(height, round)) {
+            Some(r) => rank_proposals(r.proposals.values()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Best proposal of a round
+    pub fn best(&self, height: u64, round: u64) -> Option<&ValidatedProposal> {
+        self.ranked(height, round).into_iter().next()
+    }
+
+    /// Drop every round before `(height, round)`
+    pub fn prune_before(&mut self, height: u64, round: u64) {
+        self.rounds = self.rounds.split_off(&(height, round));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::{Block, BlockHeader, Transaction};
+    use crate::consensus::v1::test_support::committee;
+    use crate::crypto::Ed25519Keys;
+
+    /// Signed proposal of one transaction at `price`, with the true efficiency
+    fn proposal(keys: &Ed25519Keys, builder: usize, round: u64, price: u64) -> BlockProposal {
+        let tx = Transaction::new(1, "c".to_string(), "s".to_string(), None, vec![], price, 1);
+        let mut header = BlockHeader::genesis("c");
+        header.height = 1;
+        header.round = round;
+        header.proposer_id = format!("v{}", builder);
+        let mut block = Block::new(header, vec![tx]);
+        block.header.efficiency_score = block_efficiency_score(&block, &ConsensusConfig::default());
+
+        let mut proposal = BlockProposal::new(1, round, format!("v{}", builder), block);
+        proposal.sign(keys).unwrap();
+        proposal
+    }
+
+    #[test]
+    fn test_ranks_by_efficiency_then_proposer() {
+        let (infos, keys) = committee(3);
+        let mut pool = ProposalPool::new(ConsensusConfig::default(), &infos);
+
+        assert!(pool.add(proposal(&keys[2], 2, 0, 1000)).unwrap());
+        assert!(pool.add(proposal(&keys[1], 1, 0, 600)).unwrap());
+        assert!(pool.add(proposal(&keys[0], 0, 0, 1000)).unwrap());
+        assert!(!pool.add(proposal(&keys[0], 0, 0, 1000)).unwrap());
+
+        let order: Vec<&str> = pool.ranked(1, 0).iter().map(|p| p.proposer_id()).collect();
+        assert_eq!(order, vec!["v0", "v2", "v1"]);
+        assert_eq!(pool.best(1, 0).unwrap().proposer_id(), "v0");
+        assert!(pool.is_empty(1, 1));
+    }
+
+    #[test]
+    fn test_rejects_invalid_proposals() {
+        let (infos, keys) = committee(2);
+        let mut pool = ProposalPool::new(ConsensusConfig::default(), &infos);
+
+        let mut forged = proposal(&keys[1], 0, 0, 1000);
+        assert!(matches!(pool.add(forged.clone()), Err(ConsensusError::InvalidSignature(_))));
+        forged.proposer_id = "stranger".to_string();
+        assert!(matches!(pool.add(forged), Err(ConsensusError::InvalidProposal(_))));
+
+        let mut inflated = proposal(&keys[0], 0, 0, 1000);
+        inflated.block.header.efficiency_score += 1;
+        inflated.sign(&keys[0]).unwrap();
+        assert!(matches!(pool.add(inflated), Err(ConsensusError::EfficiencyMismatch { .. })));
+
+        let weak = proposal(&keys[0], 0, 0, 200);
+        pool.set_reference(1, 0, weak.efficiency_score() + 1);
+        assert!(matches!(pool.add(weak), Err(ConsensusError::BelowReference { .. })));
+    }
+
+    #[test]
+    fn test_caps_proposals_per_builder() {
+        let (infos, keys) = committee(1);
+        let mut pool = ProposalPool::new(ConsensusConfig::default(), &infos);
+
+        let first = proposal(&keys[0], 0, 0, 1000);
+        assert!(pool.add(first.clone()).unwrap());
+        assert!(!pool.add(first).unwrap());
+        assert!(matches!(
+            pool.add(proposal(&keys[0], 0, 0, 900)),
+            Err(ConsensusError::InvalidProposal(_))
+        ));
+
+        // A proposal dropped by a raised reference still used up the slot
+        pool.set_reference(1, 0, u64::MAX);
+        assert_eq!(pool.len(1, 0), 0);
+        pool.set_reference(1, 0, 0);
+        assert!(matches!(
+            pool.add(proposal(&keys[0], 0, 0, 900)),
+            Err(ConsensusError::InvalidProposal(_))
+        ));
+
+        // The cap is per round
+        pool.add(proposal(&keys[0], 0, 1, 800)).unwrap();
+        pool.prune_before(1, 1);
+        assert_eq!(pool.len(1, 0), 0);
+        assert_eq!(pool.len(1, 1), 1);
+    }
+}
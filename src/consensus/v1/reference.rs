@@ -0,0 +1,204 @@
+//! PoAI v1 Reference Block
+//!
+//! Deterministic reference block generation. The coordinator and every
+//! validator derive the same block, and therefore the same
+//! `reference_efficiency`, from the same mempool snapshot.
+//!
+//! ## Determinism
+//!
+//! The generator is a pure function of its inputs:
+//!
+//! - duplicate transactions (same hash) are collapsed
+//! - every sort is a total order, with the transaction hash as the final key
+//! - the header timestamp is `parent.timestamp + block_time`, never the wall
+//!   clock, and the proposer ID is empty
+//!
+//! ## Selection
+//!
+//! `select_reference_transactions` runs the builders' `TransactionSelector`
+//! on the deduplicated mempool, so the reference and every honest proposal
+//! follow the same 20/20/50/10 rule. For `n = min(mempool,
+//! max_tx_per_block)` the counts come from
+//! `TransactionSelector::category_counts`, filled in this order
+//! without overlap:
+//!
+//! 1. **HighPrice** - highest `point_price`
+//! 2. **LowPrice** - lowest `point_price`
+//! 3. **AveragePrice** - closest to the mempool's average `point_price`,
+//!    lower price first
+//! 4. **Oldest** - lowest `timestamp`
+//!
+//! Remaining ties are broken by transaction hash. The selected transactions
+//! are ordered by `(sender, nonce, hash)` so each sender's transactions
+//! appear in nonce order.
+//!
+//! The reference block is a benchmark for scoring proposals and is never
+//! applied, so its `state_root` is left zeroed.
+
+use super::efficiency::compute_efficiency_score;
+use super::types::ConsensusConfig;
+use crate::blockchain::merkle;
+use crate::blockchain::v1::{Block, BlockHeader, Transaction};
+use crate::consensus::transaction_selector::{
+    SelectableTransaction, SelectedTransactions, TransactionSelector, TransactionSelectorConfig,
+};
+use std::collections::HashSet;
+
+/// Generate the reference block for `height` on top of `parent`
+pub fn generate_reference_block(
+    mempool_snapshot: &[Transaction],
+    height: u64,
+    parent: &BlockHeader,
+    config: &ConsensusConfig,
+) -> Block {
+    let selector = TransactionSelector::new(TransactionSelectorConfig {
+        max_transactions_per_block: config.max_tx_per_block,
+        ..TransactionSelectorConfig::default()
+    });
+    let mut transactions =
+        select_reference_transactions(&selector, mempool_snapshot).into_transactions();
+    transactions.sort_by_cached_key(|tx| (tx.sender.clone(), tx.nonce, tx.hash()));
+
+    let point_price = if transactions.is_empty() {
+        0
+    } else {
+        let total: u128 = transactions.iter().map(|tx| tx.point_price as u128).sum();
+        (total / transactions.len() as u128) as u64
+    };
+
+    let header = BlockHeader {
+        height,
+        previous_hash: parent.hash(),
+        timestamp: parent.timestamp.saturating_add(config.block_time.as_secs()),
+        state_root: [0u8; 32],
+        transactions_root: merkle::transactions_root(&transactions),
+        proposer_id: String::new(),
+        round: 0,
+        chain_id: config.chain_id.clone(),
+        efficiency_score: compute_efficiency_score(
+            &transactions,
+            config.target_point_price,
+            config.max_tx_per_block,
+        ),
+        point_price,
+        commit_signatures: vec![],
+        commit_round: 0,
+    };
+
+    Block::new(header, transactions)
+}
+
+/// Reference selection from `mempool`: `selector`'s 20/20/50/10 choice
+/// after collapsing duplicate transactions
+pub fn select_reference_transactions<T: SelectableTransaction>(
+    selector: &TransactionSelector,
+    mempool: &[T],
+) -> SelectedTransactions<T> {
+    let mut seen = HashSet::with_capacity(mempool.len());
+    let unique: Vec<T> = mempool.iter().filter(|tx| seen.insert(tx.id())).cloned().collect();
+    selector.select(unique)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::v1::selection::SelectionComplianceChecker;
+    use crate::consensus::v1::validation::SelectionCompliance;
+
+    /// Transaction `i` has PointPrice `900 + i`; transaction 50 is the oldest
+    /// and the others share timestamps in threes
+    fn mempool(m: u64) -> Vec<Transaction> {
+        (0..m)
+            .map(|i| {
+                let sender = format!("s{:03}", i);
+                let timestamp = if i == 50 { 1 } else { 100 + i / 3 };
+                Transaction::new(1, "c".to_string(), sender, None, vec![], 900 + i, timestamp)
+            })
+            .collect()
+    }
+
+    fn parent() -> BlockHeader {
+        let mut parent = BlockHeader::genesis("c");
+        parent.timestamp = 1_000;
+        parent
+    }
+
+    fn config(max_tx: usize) -> ConsensusConfig {
+        ConsensusConfig {
+            chain_id: "c".to_string(),
+            max_tx_per_block: max_tx,
+            ..ConsensusConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_independent_of_mempool_order() {
+        let pool = mempool(50);
+        let mut shuffled: Vec<Transaction> = pool.iter().rev().cloned().collect();
+        shuffled.rotate_left(17);
+        // Duplicates collapse to a single transaction
+        shuffled.extend_from_slice(&pool[..5]);
+
+        let a = generate_reference_block(&pool, 1, &parent(), &config(20));
+        let b = generate_reference_block(&shuffled, 1, &parent(), &config(20));
+        assert_eq!(a, b);
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.tx_count(), 20);
+    }
+
+    #[test]
+    fn test_header_derived_from_parent() {
+        let parent = parent();
+        let block = generate_reference_block(&mempool(10), 1, &parent, &config(100));
+
+        assert_eq!(block.header.previous_hash, parent.hash());
+        assert_eq!(block.header.timestamp, 1_060);
+        assert_eq!(block.header.transactions_root, block.compute_transactions_root());
+        assert_eq!(
+            block.header.efficiency_score,
+            compute_efficiency_score(&block.transactions, 1000, 100)
+        );
+        assert!(block.header.proposer_id.is_empty());
+    }
+
+    #[test]
+    fn test_selection_is_compliant() {
+        let pool = mempool(100);
+        let block = generate_reference_block(&pool, 1, &parent(), &config(10));
+
+        let report = SelectionComplianceChecker::new().check(&block.transactions, &pool);
+        assert_eq!(report.compliance, SelectionCompliance::Valid);
+    }
+
+    #[test]
+    fn test_matches_builder_selection() {
+        let pool = mempool(100);
+        let block = generate_reference_block(&pool, 1, &parent(), &config(20));
+
+        let selector = TransactionSelector::new(TransactionSelectorConfig {
+            max_transactions_per_block: 20,
+            ..TransactionSelectorConfig::default()
+        });
+        let selected = selector.select_transactions(pool).unwrap();
+        let mut expected: Vec<[u8; 32]> =
+            selected.into_transactions().iter().map(|tx| tx.hash()).collect();
+        let mut actual: Vec<[u8; 32]> = block.transactions.iter().map(|tx| tx.hash()).collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ties_broken_by_hash() {
+        // Identical prices and timestamps: only the hash orders them
+        let pool: Vec<Transaction> = (0..10)
+            .map(|i| Transaction::new(i, "c".to_string(), format!("s{}", i), None, vec![], 1000, 1))
+            .collect();
+        let mut reversed = pool.clone();
+        reversed.reverse();
+
+        let a = generate_reference_block(&pool, 1, &parent(), &config(5));
+        let b = generate_reference_block(&reversed, 1, &parent(), &config(5));
+        assert_eq!(a.transactions, b.transactions);
+    }
+}
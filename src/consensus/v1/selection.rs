@@ -0,0 +1,319 @@
+//! PoAI v1 Selection Compliance
+//!
+//! Audits a block someone else built against the 20/20/50/10 transaction
+//! selection rule, using this node's mempool snapshot.
+//!
+//! ## Category Membership
+//!
+//! For a block of `n` transactions the expected category sizes are the ones
+//! `TransactionSelector::category_counts` fills:
+//!
+//! ```text
+//! high   = n * 20 / 100
+//! low    = n * 20 / 100
+//! oldest = n * 10 / 100
+//! avg    = n - high - low - oldest
+//! ```
+//!
+//! Each block transaction is placed in the first category it qualifies for:
+//!
+//! 1. **HighPrice** - among the `high` highest PointPrices in the mempool
+//! 2. **LowPrice** - among the `low` lowest PointPrices in the mempool
+//! 3. **Oldest** - among the `oldest` oldest timestamps of the mempool
+//!    transactions that are neither HighPrice nor LowPrice, as the selector
+//!    fills it from transactions it has not taken yet
+//! 4. **AveragePrice** - any other mempool transaction
+//!
+//! Transactions missing from the snapshot are counted separately. Ties are
+//! broken by transaction hash so every node ranks the mempool identically.
+//!
+//! ## Deviation
+//!
+//! Each category's deviation is `|actual - expected| * 10000 / n` basis
+//! points (missing transactions deviate from an expected count of zero). The
+//! block is `Valid` with no deviation at all, `WithinTolerance` when the
+//! largest deviation is at most `SELECTION_TOLERANCE_BPS` (15%), and
+//! `Invalid` otherwise.
+
+use super::types::constants;
+use super::validation::SelectionCompliance;
+use crate::blockchain::v1::Transaction;
+use crate::consensus::transaction_selector::TransactionSelector;
+use std::collections::HashMap;
+
+/// Category a selected transaction counts towards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionCategory {
+    /// Highest PointPrice (20%)
+    HighPrice,
+    /// Lowest PointPrice (20%)
+    LowPrice,
+    /// Average PointPrice (50%)
+    AveragePrice,
+    /// Oldest by timestamp (10%)
+    Oldest,
+}
+
+impl SelectionCategory {
+    /// All categories in 20/20/50/10 order
+    pub const ALL: [SelectionCategory; 4] = [
+        SelectionCategory::HighPrice,
+        SelectionCategory::LowPrice,
+        SelectionCategory::AveragePrice,
+        SelectionCategory::Oldest,
+    ];
+}
+
+/// Expected and actual size of one category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CategoryDeviation {
+    /// Category measured
+    pub category: SelectionCategory,
+
+    /// Count the 20/20/50/10 rule calls for
+    pub expected: usize,
+
+    /// Count found in the block
+    pub actual: usize,
+
+    /// `|actual - expected|` in basis points of the block size
+    pub deviation_bps: u64,
+}
+
+/// Result of auditing a block's transaction selection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionReport {
+    /// Overall classification
+    pub compliance: SelectionCompliance,
+
+    /// Per-category breakdown in 20/20/50/10 order
+    pub categories: Vec<CategoryDeviation>,
+
+    /// Block indices of transactions not in the mempool snapshot
+    pub not_in_mempool: Vec<usize>,
+
+    /// Largest deviation across categories and missing transactions
+    pub max_deviation_bps: u64,
+}
+
+impl SelectionReport {
+    /// Breakdown for a single category
+    pub fn category(&self, category: SelectionCategory) -> &CategoryDeviation {
+        self.categories
+            .iter()
+            .find(|c| c.category == category)
+            .expect("report covers every category")
+    }
+}
+
+/// Checks blocks against the 20/20/50/10 selection rule
+#[derive(Debug, Clone)]
+pub struct SelectionComplianceChecker {
+    tolerance_bps: u64,
+}
+
+impl Default for SelectionComplianceChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SelectionComplianceChecker {
+    /// Create a checker with the spec's 15% tolerance
+    pub fn new() -> Self {
+        Self::with_tolerance(constants::SELECTION_TOLERANCE_BPS)
+    }
+
+    /// Create a checker with a custom tolerance in basis points
+    pub fn with_tolerance(tolerance_bps: u64) -> Self {
+        Self { tolerance_bps }
+    }
+
+    /// Expected `(high, low, avg, oldest)` counts for a block of `n`
+    /// transactions, the sizes `TransactionSelector` fills
+    pub fn expected_counts(n: usize) -> [usize; 4] {
+        TransactionSelector::category_counts(n)
+    }
+
+    /// Audit `block_txs` against the mempool snapshot they were selected from
+    pub fn check(&self, block_txs: &[Transaction], mempool: &[Transaction]) -> SelectionReport {
+        let n = block_txs.len();
+        let expected = Self::expected_counts(n);
+        let membership = Self::classify_mempool(mempool, &expected);
+
+        let mut actual: HashMap<SelectionCategory, usize> = HashMap::new();
+        let mut not_in_mempool = Vec::new();
+        for (index, tx) in block_txs.iter().enumerate() {
+            match membership.get(&tx.hash()) {
+                Some(category) => *actual.entry(*category).or_default() += 1,
+                None => not_in_mempool.push(index),
+            }
+        }
+
+        let categories: Vec<CategoryDeviation> = SelectionCategory::ALL
+            .iter()
+            .zip(expected)
+            .map(|(&category, expected)| {
+                let actual = actual.get(&category).copied().unwrap_or(0);
+                CategoryDeviation {
+                    category,
+                    expected,
+                    actual,
+                    deviation_bps: deviation_bps(actual.abs_diff(expected), n),
+                }
+            })
+            .collect();
+
+        let max_deviation_bps = categories
+            .iter()
+            .map(|c| c.deviation_bps)
+            .chain([deviation_bps(not_in_mempool.len(), n)])
+            .max()
+            .unwrap_or(0);
+
+        let compliance = if max_deviation_bps == 0 {
+            SelectionCompliance::Valid
+        } else if max_deviation_bps <= self.tolerance_bps {
+            SelectionCompliance::WithinTolerance
+        } else {
+            SelectionCompliance::Invalid
+        };
+
+        SelectionReport {
+            compliance,
+            categories,
+            not_in_mempool,
+            max_deviation_bps,
+        }
+    }
+
+    /// Assign every mempool transaction to its category
+    fn classify_mempool(
+        mempool: &[Transaction],
+        expected: &[usize; 4],
+    ) -> HashMap<[u8; 32], SelectionCategory> {
+        let [high, low, _, oldest] = *expected;
+        let hashed: Vec<([u8; 32], &Transaction)> =
+            mempool.iter().map(|tx| (tx.hash(), tx)).collect();
+
+        let mut by_price: Vec<&([u8; 32], &Transaction)> = hashed.iter().collect();
+        by_price.sort_by_key(|(hash, tx)| (tx.point_price, *hash));
+        let mut by_age: Vec<&([u8; 32], &Transaction)> = hashed.iter().collect();
+        by_age.sort_by_key(|(hash, tx)| (tx.timestamp, *hash));
+
+        let mut membership = HashMap::new();
+        for (hash, _) in by_price.iter().rev().take(high) {
+            membership.entry(*hash).or_insert(SelectionCategory::HighPrice);
+        }
+        for (hash, _) in by_price.iter().take(low) {
+            membership.entry(*hash).or_insert(SelectionCategory::LowPrice);
+        }
+        let unclassified: Vec<[u8; 32]> = by_age
+            .iter()
+            .map(|(hash, _)| *hash)
+            .filter(|hash| !membership.contains_key(hash))
+            .take(oldest)
+            .collect();
+        for hash in unclassified {
+            membership.insert(hash, SelectionCategory::Oldest);
+        }
+        for (hash, _) in &hashed {
+            membership.entry(*hash).or_insert(SelectionCategory::AveragePrice);
+        }
+        membership
+    }
+}
+
+/// `count` as basis points of `n`
+fn deviation_bps(count: usize, n: usize) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    (count as u64 * 10_000) / n as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::transaction_selector::TransactionSelectorConfig;
+
+    /// Transaction `i` has PointPrice `i + 1`; transaction 50 is the oldest
+    fn mempool(m: u64) -> Vec<Transaction> {
+        (0..m)
+            .map(|i| {
+                let timestamp = if i == 50 { 1 } else { 2000 + i };
+                let sender = format!("s{}", i);
+                Transaction::new(i, "c".to_string(), sender, None, vec![], i + 1, timestamp)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expected_counts() {
+        assert_eq!(SelectionComplianceChecker::expected_counts(10), [2, 2, 5, 1]);
+        assert_eq!(SelectionComplianceChecker::expected_counts(7), [1, 1, 5, 0]);
+        assert_eq!(SelectionComplianceChecker::expected_counts(0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_exact_and_tolerated_selection() {
+        let pool = mempool(100);
+        // 10 tx block: 2 highest, 2 lowest, 1 oldest, 5 from the middle
+        let pick = |indices: &[usize]| -> Vec<Transaction> {
+            indices.iter().map(|&i| pool[i].clone()).collect()
+        };
+        let exact = pick(&[99, 98, 0, 1, 50, 40, 41, 42, 43, 44]);
+
+        let report = SelectionComplianceChecker::new().check(&exact, &pool);
+        assert_eq!(report.compliance, SelectionCompliance::Valid);
+        assert_eq!(report.max_deviation_bps, 0);
+
+        // Skipping the oldest transaction shifts one slot to average (10%)
+        let skewed = pick(&[99, 98, 0, 1, 45, 40, 41, 42, 43, 44]);
+        let report = SelectionComplianceChecker::new().check(&skewed, &pool);
+        assert_eq!(report.category(SelectionCategory::Oldest).actual, 0);
+        assert_eq!(report.category(SelectionCategory::AveragePrice).actual, 6);
+        assert_eq!(report.max_deviation_bps, 1000);
+        assert_eq!(report.compliance, SelectionCompliance::WithinTolerance);
+    }
+
+    #[test]
+    fn test_old_priced_transactions_leave_oldest_slots_open() {
+        let mut pool = mempool(100);
+        // The most expensive transaction is also the oldest one
+        pool[99].timestamp = 0;
+
+        let selector = TransactionSelector::new(TransactionSelectorConfig {
+            max_transactions_per_block: 10,
+            ..TransactionSelectorConfig::default()
+        });
+        let block = selector.select(pool.clone()).into_transactions();
+
+        let report = SelectionComplianceChecker::new().check(&block, &pool);
+        assert_eq!(report.category(SelectionCategory::Oldest).actual, 1);
+        assert_eq!(report.compliance, SelectionCompliance::Valid);
+    }
+
+    #[test]
+    fn test_gamed_block_is_invalid() {
+        let pool = mempool(100);
+        // Builder takes only the most expensive transactions
+        let block: Vec<Transaction> = pool[90..].to_vec();
+
+        let report = SelectionComplianceChecker::new().check(&block, &pool);
+        assert_eq!(report.category(SelectionCategory::HighPrice).actual, 2);
+        assert_eq!(report.category(SelectionCategory::LowPrice).actual, 0);
+        assert_eq!(report.compliance, SelectionCompliance::Invalid);
+    }
+
+    #[test]
+    fn test_unknown_transactions_counted() {
+        let pool = mempool(20);
+        let mut block: Vec<Transaction> = pool[5..15].to_vec();
+        block[0].nonce = 999;
+
+        let report = SelectionComplianceChecker::with_tolerance(10_000).check(&block, &pool);
+        assert_eq!(report.not_in_mempool, vec![0]);
+        assert!(report.max_deviation_bps >= 1000);
+    }
+}
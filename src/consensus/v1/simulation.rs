@@ -0,0 +1,1127 @@
+//! PoAI v1 Consensus Simulator
+//!
+//! Runs validators, builders and a coordinator in one process on virtual
+//! time, so liveness and safety can be tested without a network.
+//!
+//! ## Model
+//!
+//! - **Validators** are `ConsensusEngine`s driven through the synchronous
+//!   `handle_message` / `on_timeout` API; the engine buffers messages for
+//!   later rounds itself. A validator that receives a message for a later
+//!   height asks the sender for the blocks it decided and adopts them with
+//!   `ConsensusEngine::adopt_decided`, which verifies each certificate (block
+//!   sync). Sync requests and responses cross the same faulty network.
+//!   Each validator signs its votes through a `LastSignedStore` kept in a
+//!   directory the run removes when it ends.
+//! - **Builders** propose once per round, signing through their own
+//!   `LastSignedStore`. Each sees a seeded subset of the height's mempool and
+//!   selects with `TransactionSelector`; a builder that cannot reach the
+//!   reference proposes the reference block instead.
+//! - **The coordinator** derives each height's reference efficiency with
+//!   `generate_reference_block` and follows finality with a
+//!   `ChainTipManager`. It ratifies each finalized block in a `VotingSystem`
+//!   round on the virtual clock: every validator that decides the block
+//!   sends a score, its recomputed efficiency as a percentage of the
+//!   reference, across the same faulty network, and votes that miss the
+//!   window are refused.
+//!
+//! Every random choice (keys, mempools, latencies, drops) comes from
+//! `SimConfig::seed`, and events at the same instant run in scheduling order,
+//! so a seed always replays the same run.
+//!
+//! ## Faults
+//!
+//! | Fault | Effect |
+//! |-------|--------|
+//! | `Drop` | Messages are lost with the given probability |
+//! | `Delay` | Extra latency on every message |
+//! | `Partition` | No messages between different groups |
+//! | `Crash` | Node ignores everything; a recovered validator restarts from persisted state |
+//! | `Equivocate` | Validator sends a second vote for another block and scores every block 0 |
+//! | `FalseEfficiency` | Builder claims more efficiency than its block has |
+//!
+//! ## Invariants
+//!
+//! `SimReport::check_invariants` fails if two blocks were finalized at one
+//! height, a validator decided something other than the finalized block, an
+//! honest node was accused of equivocation, or a ratification rejected its
+//! block or named a block other than the finalized one.
+
+use super::certificate::CommitCertificate;
+use super::efficiency::block_efficiency_score;
+use super::engine::{ConsensusEngine, EngineOutput};
+use super::evidence::{EquivocationEvidence, FinalityConflict};
+use super::fork_choice::ChainTipManager;
+use super::last_signed::LastSignedStore;
+use super::reference::generate_reference_block;
+use super::types::{ConsensusConfig, ConsensusError, ConsensusMessage, RoundStep, ValidatorInfo};
+use crate::blockchain::v1::codec::{domain_hash, Canonical};
+use crate::blockchain::v1::{Block, BlockHeader, BlockProposal, RankedVote, Transaction};
+use crate::clock::MockClock;
+use crate::consensus::metrics::ConsensusMetrics;
+use crate::consensus::transaction_selector::{TransactionSelector, TransactionSelectorConfig};
+use crate::consensus::vote::VotingResult;
+use crate::consensus::voting::{VotingConfig, VotingSystem};
+use crate::crypto::common::traits::KeyPair;
+use crate::crypto::Ed25519Keys;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Decided blocks returned per sync request
+const SYNC_BATCH: usize = 8;
+
+/// Time a validator waits for a sync response before asking again
+const SYNC_RETRY: Duration = Duration::from_secs(5);
+
+/// Time the coordinator collects ratification votes for a finalized block
+const RATIFY_WINDOW: Duration = Duration::from_secs(10);
+
+/// Node ID of the coordinator on the simulated network
+const COORDINATOR: &str = "coordinator";
+
+/// Simulation parameters
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    /// Number of validators (`v0`, `v1`, ...)
+    pub validators: usize,
+
+    /// Number of builders (`b0`, `b1`, ...)
+    pub builders: usize,
+
+    /// Seed for keys, mempools, latencies and drops
+    pub seed: u64,
+
+    /// Stop once every live validator has decided this many heights
+    pub heights: u64,
+
+    /// Stop at this virtual time even without progress
+    pub max_time: Duration,
+
+    /// Minimum message latency
+    pub min_latency: Duration,
+
+    /// Maximum message latency
+    pub max_latency: Duration,
+
+    /// Transactions in each height's mempool
+    pub mempool_size: usize,
+
+    /// Consensus parameters shared by every node
+    pub consensus: ConsensusConfig,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            validators: 4,
+            builders: 2,
+            seed: 0,
+            heights: 3,
+            max_time: Duration::from_secs(3600),
+            min_latency: Duration::from_millis(50),
+            max_latency: Duration::from_millis(500),
+            mempool_size: 50,
+            consensus: ConsensusConfig::default(),
+        }
+    }
+}
+
+/// Injected fault
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Lose messages sent in `[from, until)` with probability `rate`
+    Drop { rate: f64, from: Duration, until: Duration },
+
+    /// Add `extra` latency to messages sent in `[from, until)`
+    Delay { extra: Duration, from: Duration, until: Duration },
+
+    /// Cut links between groups in `[from, until)`; unlisted nodes reach all
+    Partition { groups: Vec<Vec<String>>, from: Duration, until: Duration },
+
+    /// Stop `node` at `at`, optionally restarting it at `recover_at`
+    ///
+    /// A restarted validator keeps only its decided blocks and its
+    /// `LastSignedStore` record.
+    Crash { node: String, at: Duration, recover_at: Option<Duration> },
+
+    /// Validator sends a conflicting ranked vote after each honest one
+    Equivocate { validator: String },
+
+    /// Builder inflates its claimed efficiency score
+    FalseEfficiency { builder: String },
+}
+
+/// Outcome of a simulation run
+#[derive(Debug, Clone, Default)]
+pub struct SimReport {
+    /// Finalized block hash per height
+    pub finalized: BTreeMap<u64, [u8; 32]>,
+
+    /// Block hash each validator decided per height
+    pub decided: BTreeMap<String, BTreeMap<u64, [u8; 32]>>,
+
+    /// Distinct equivocation evidence reported by validators
+    pub evidence: Vec<EquivocationEvidence>,
+
+    /// Conflicting finalizations seen by the coordinator
+    pub conflicts: Vec<FinalityConflict>,
+
+    /// Coordinator ratification of each finalized block, by height
+    pub ratifications: BTreeMap<u64, VotingResult>,
+
+    /// Safety problems found during the run
+    pub violations: Vec<String>,
+
+    /// Nodes configured as Byzantine
+    pub byzantine: BTreeSet<String>,
+
+    /// Messages sent
+    pub messages_sent: u64,
+
+    /// Messages lost to drops, partitions or crashes
+    pub messages_dropped: u64,
+
+    /// Messages a validator or the coordinator refused
+    pub messages_rejected: u64,
+
+    /// Blocks validators adopted through block sync
+    pub synced_blocks: u64,
+
+    /// Virtual time at the end of the run
+    pub elapsed: Duration,
+}
+
+impl SimReport {
+    /// Highest finalized height
+    pub fn finalized_height(&self) -> u64 {
+        self.finalized.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Check the safety invariants
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if let Some(violation) = self.violations.first() {
+            return Err(violation.clone());
+        }
+        if let Some(conflict) = self.conflicts.first() {
+            return Err(format!("two blocks finalized at height {}", conflict.height()));
+        }
+        for (validator, decided) in &self.decided {
+            for (height, hash) in decided {
+                if self.finalized.get(height) != Some(hash) {
+                    return Err(format!(
+                        "{} decided a non-finalized block at height {}",
+                        validator, height
+                    ));
+                }
+            }
+        }
+        for evidence in &self.evidence {
+            if !self.byzantine.contains(evidence.validator_id()) {
+                return Err(format!("honest {} accused", evidence.validator_id()));
+            }
+        }
+        for (height, ratification) in &self.ratifications {
+            let finalized = self.finalized.get(height).map(hex::encode);
+            if finalized.as_ref() != Some(&ratification.block_hash) {
+                return Err(format!("ratified a non-finalized block at height {}", height));
+            }
+            if !ratification.approved {
+                return Err(format!("ratification rejected at height {}", height));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Block hash and claimed efficiency of a proposal
+type ProposalSummary = ([u8; 32], u64);
+
+/// Scheduled event
+#[derive(Debug, Clone)]
+enum Event {
+    Deliver { from: String, to: usize, message: ConsensusMessage },
+    SyncRequest { from: usize, to: usize, height: u64 },
+    SyncResponse { to: usize, blocks: Vec<(Block, CommitCertificate)> },
+    Timeout { validator: usize, epoch: u64, position: (u64, u64, RoundStep) },
+    StartRound { height: u64, round: u64 },
+    Ratify { validator: String, height: u64, block_hash: [u8; 32], score: u64 },
+    EndRatification,
+    Crash { node: String },
+    Recover { node: String },
+}
+
+struct SimValidator {
+    id: String,
+    keys: Ed25519Keys,
+    engine: ConsensusEngine,
+    epoch: u64,
+    position: Option<(u64, u64, RoundStep)>,
+    blocks: BTreeMap<u64, (Block, CommitCertificate)>,
+    sync_after: Duration,
+    crashed: bool,
+}
+
+struct SimBuilder {
+    id: String,
+    keys: Ed25519Keys,
+    signed: LastSignedStore,
+    crashed: bool,
+}
+
+/// Deterministic in-process PoAI network
+pub struct Simulation {
+    config: SimConfig,
+    dir: PathBuf,
+    faults: Vec<Fault>,
+    rng: StdRng,
+    now: Duration,
+    next_seq: u64,
+    queue: BTreeMap<(Duration, u64), Event>,
+    committee: Vec<ValidatorInfo>,
+    builder_infos: Vec<ValidatorInfo>,
+    validators: Vec<SimValidator>,
+    builders: Vec<SimBuilder>,
+    chain: ChainTipManager,
+    selector: TransactionSelector,
+    mempools: BTreeMap<u64, Vec<Transaction>>,
+    started_rounds: BTreeSet<(u64, u64)>,
+    round_proposals: BTreeMap<(u64, u64), Vec<ProposalSummary>>,
+    evidence_seen: BTreeSet<[u8; 32]>,
+    clock: MockClock,
+    voting: VotingSystem,
+    ratifying: Option<u64>,
+    to_ratify: BTreeSet<u64>,
+    early_ratify_votes: BTreeMap<u64, Vec<(String, [u8; 32], u64)>>,
+    report: SimReport,
+}
+
+impl Simulation {
+    /// Create a simulation of honest nodes on a reliable network
+    pub fn new(config: SimConfig) -> Self {
+        let key = |id: &str| {
+            let mut seed = config.seed.to_le_bytes().to_vec();
+            seed.extend_from_slice(id.as_bytes());
+            Ed25519Keys::from_private_key(&domain_hash(b"self-chain-sim-key", &seed))
+                .expect("32-byte seed")
+        };
+        let info = |id: &str, keys: &Ed25519Keys| {
+            ValidatorInfo::new(id.to_string(), keys.public_key_bytes(), "sim".to_string())
+        };
+
+        static RUNS: AtomicU64 = AtomicU64::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "self-chain-sim-{}-{}",
+            std::process::id(),
+            RUNS.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("simulation state directory");
+
+        let validator_keys: Vec<(String, Ed25519Keys)> = (0..config.validators)
+            .map(|i| format!("v{}", i))
+            .map(|id| (id.clone(), key(&id)))
+            .collect();
+        let builders: Vec<SimBuilder> = (0..config.builders)
+            .map(|i| format!("b{}", i))
+            .map(|id| {
+                let keys = key(&id);
+                let signed = LastSignedStore::open(record_path(&dir, &id), keys.public_key_bytes())
+                    .expect("simulation last-signed record");
+                SimBuilder {
+                    keys,
+                    id,
+                    signed,
+                    crashed: false,
+                }
+            })
+            .collect();
+        let committee: Vec<ValidatorInfo> =
+            validator_keys.iter().map(|(id, keys)| info(id, keys)).collect();
+        let builder_infos: Vec<ValidatorInfo> =
+            builders.iter().map(|b| info(&b.id, &b.keys)).collect();
+
+        let clock = MockClock::default();
+        let metrics = ConsensusMetrics::new(&prometheus::Registry::new()).expect("fresh registry");
+        let voting = VotingSystem::with_clock(
+            VotingConfig {
+                voting_window: RATIFY_WINDOW.as_secs(),
+                min_voters: config.consensus.quorum_threshold(config.validators) as u64,
+                ..VotingConfig::default()
+            },
+            Arc::new(metrics),
+            clock.shared(),
+        );
+
+        let mut sim = Self {
+            dir,
+            rng: StdRng::seed_from_u64(config.seed),
+            selector: TransactionSelector::new(TransactionSelectorConfig {
+                max_transactions_per_block: config.consensus.max_tx_per_block,
+                ..TransactionSelectorConfig::default()
+            }),
+            chain: ChainTipManager::new(config.consensus.clone(), Self::genesis(&config)),
+            faults: Vec::new(),
+            now: Duration::ZERO,
+            next_seq: 0,
+            queue: BTreeMap::new(),
+            validators: Vec::new(),
+            builders,
+            committee,
+            builder_infos,
+            mempools: BTreeMap::new(),
+            started_rounds: BTreeSet::new(),
+            round_proposals: BTreeMap::new(),
+            evidence_seen: BTreeSet::new(),
+            clock,
+            voting,
+            ratifying: None,
+            to_ratify: BTreeSet::new(),
+            early_ratify_votes: BTreeMap::new(),
+            report: SimReport::default(),
+            config,
+        };
+        sim.validators = validator_keys
+            .into_iter()
+            .map(|(id, keys)| SimValidator {
+                engine: sim.engine(&id, &keys, 1),
+                id,
+                keys,
+                epoch: 0,
+                position: None,
+                blocks: BTreeMap::new(),
+                sync_after: Duration::ZERO,
+                crashed: false,
+            })
+            .collect();
+        sim
+    }
+
+    /// Inject a fault
+    pub fn with_fault(mut self, fault: Fault) -> Self {
+        match &fault {
+            Fault::Equivocate { validator: node } | Fault::FalseEfficiency { builder: node } => {
+                self.report.byzantine.insert(node.clone());
+            }
+            _ => {}
+        }
+        self.faults.push(fault);
+        self
+    }
+
+    /// Current virtual time
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Run until every live validator decides `heights` and the coordinator
+    /// has ratified every finalized block, or `max_time` passes
+    pub async fn run(mut self) -> SimReport {
+        self.start();
+        while !self.done() && self.step().await {}
+        self.finish()
+    }
+
+    /// Schedule the crash faults and arm every validator's first timer
+    fn start(&mut self) {
+        for fault in self.faults.clone() {
+            if let Fault::Crash { node, at, recover_at } = fault {
+                self.schedule(at, Event::Crash { node: node.clone() });
+                if let Some(recover_at) = recover_at {
+                    self.schedule(recover_at, Event::Recover { node });
+                }
+            }
+        }
+        for i in 0..self.validators.len() {
+            self.sync_validator(i);
+        }
+    }
+
+    /// Handle the next event, or return false once none is left before
+    /// `max_time`
+    async fn step(&mut self) -> bool {
+        let Some(((at, _), event)) = self.queue.pop_first() else { return false };
+        if at > self.config.max_time {
+            return false;
+        }
+        self.now = at;
+        self.clock.set(at);
+        self.handle(event).await;
+        self.start_ratification().await;
+        true
+    }
+
+    fn finish(&mut self) -> SimReport {
+        self.report.elapsed = self.now;
+        self.report.conflicts = self.chain.conflicts().to_vec();
+        std::mem::take(&mut self.report)
+    }
+
+    /// Every live validator has decided the target height and no
+    /// ratification is pending
+    fn done(&self) -> bool {
+        self.ratifying.is_none()
+            && self.to_ratify.is_empty()
+            && self
+                .validators
+                .iter()
+                .filter(|v| !v.crashed)
+                .all(|v| v.engine.round_state().height > self.config.heights)
+    }
+
+    fn genesis(config: &SimConfig) -> BlockHeader {
+        BlockHeader::genesis(&config.consensus.chain_id)
+    }
+
+    /// Engine for validator `id` at `height`, signing through the
+    /// `LastSignedStore` record it left in the run directory
+    fn engine(&self, id: &str, keys: &Ed25519Keys, height: u64) -> ConsensusEngine {
+        let store = LastSignedStore::open(record_path(&self.dir, id), keys.public_key_bytes())
+            .expect("simulation last-signed record");
+        ConsensusEngine::new(self.config.consensus.clone(), self.committee.clone(), height)
+            .with_builders(&self.builder_infos)
+            .with_signer(id.to_string(), keys.clone())
+            .with_last_signed(store)
+    }
+
+    fn schedule(&mut self, at: Duration, event: Event) {
+        self.queue.insert((at, self.next_seq), event);
+        self.next_seq += 1;
+    }
+
+    async fn handle(&mut self, event: Event) {
+        match event {
+            Event::Deliver { from, to, message } => self.deliver(&from, to, message),
+            Event::Timeout { validator, epoch, position } => {
+                let v = &mut self.validators[validator];
+                if v.crashed || v.epoch != epoch || v.position != Some(position) {
+                    return;
+                }
+                v.engine.on_timeout();
+                self.process_outputs(validator);
+                self.sync_validator(validator);
+            }
+            Event::StartRound { height, round } => self.propose(height, round),
+            Event::Crash { node } => self.set_crashed(&node, true),
+            Event::Recover { node } => {
+                self.set_crashed(&node, false);
+                if let Some(i) = self.validators.iter().position(|v| v.id == node) {
+                    self.restart(i);
+                }
+            }
+            Event::SyncRequest { from, to, height } => self.serve_sync(from, to, height),
+            Event::SyncResponse { to, blocks } => self.apply_sync(to, blocks),
+            Event::Ratify { validator, height, block_hash, score } => {
+                self.ratify_vote(validator, height, block_hash, score).await;
+            }
+            Event::EndRatification => self.end_ratification().await,
+        }
+    }
+
+    /// Rebuild validator `i` from what survives a crash: its decided blocks
+    /// and its `LastSignedStore` record
+    fn restart(&mut self, i: usize) {
+        let (id, keys) = (self.validators[i].id.clone(), self.validators[i].keys.clone());
+        let height = self.validators[i].blocks.keys().next_back().map_or(1, |h| h + 1);
+        let engine = self.engine(&id, &keys, height);
+
+        // Timers armed before the crash belong to the old engine
+        let v = &mut self.validators[i];
+        v.engine = engine;
+        v.epoch += 1;
+        v.position = None;
+        v.sync_after = Duration::ZERO;
+        self.sync_validator(i);
+    }
+
+    fn set_crashed(&mut self, node: &str, crashed: bool) {
+        for v in self.validators.iter_mut().filter(|v| v.id == node) {
+            v.crashed = crashed;
+        }
+        for b in self.builders.iter_mut().filter(|b| b.id == node) {
+            b.crashed = crashed;
+        }
+    }
+
+    fn deliver(&mut self, from: &str, i: usize, message: ConsensusMessage) {
+        if self.validators[i].crashed {
+            self.report.messages_dropped += 1;
+            return;
+        }
+        let v = &mut self.validators[i];
+        match v.engine.handle_message(message) {
+            Ok(()) => {}
+            Err(ConsensusError::WrongHeight { expected, got }) if got > expected => {
+                self.report.messages_rejected += 1;
+                self.request_sync(i, from, expected);
+            }
+            Err(e) => {
+                tracing::debug!("{} rejected message: {}", v.id, e);
+                self.report.messages_rejected += 1;
+            }
+        }
+        self.process_outputs(i);
+        self.sync_validator(i);
+    }
+
+    /// Ask validator `peer` for the blocks decided from `height` on
+    fn request_sync(&mut self, i: usize, peer: &str, height: u64) {
+        let Some(to) = self.validators.iter().position(|v| v.id == peer) else {
+            return;
+        };
+        if self.now < self.validators[i].sync_after {
+            return;
+        }
+        self.validators[i].sync_after = self.now + SYNC_RETRY;
+        let from_id = self.validators[i].id.clone();
+        if let Some(latency) = self.link(&from_id, peer) {
+            self.schedule(self.now + latency, Event::SyncRequest { from: i, to, height });
+        }
+    }
+
+    /// Answer a sync request from the peer's own decided blocks
+    fn serve_sync(&mut self, from: usize, to: usize, height: u64) {
+        if self.validators[to].crashed {
+            self.report.messages_dropped += 1;
+            return;
+        }
+        let blocks: Vec<(Block, CommitCertificate)> = self.validators[to]
+            .blocks
+            .range(height..)
+            .take(SYNC_BATCH)
+            .map(|(_, decided)| decided.clone())
+            .collect();
+        let (peer, requester) = (self.validators[to].id.clone(), self.validators[from].id.clone());
+        if let Some(latency) = self.link(&peer, &requester) {
+            self.schedule(self.now + latency, Event::SyncResponse { to: from, blocks });
+        }
+    }
+
+    /// Adopt synced blocks through the engine, which checks their certificates
+    fn apply_sync(&mut self, i: usize, blocks: Vec<(Block, CommitCertificate)>) {
+        if self.validators[i].crashed {
+            self.report.messages_dropped += 1;
+            return;
+        }
+        self.validators[i].sync_after = Duration::ZERO;
+        for (block, certificate) in blocks {
+            let v = &mut self.validators[i];
+            if block.height() < v.engine.round_state().height {
+                continue;
+            }
+            if let Err(e) = v.engine.adopt_decided(block, certificate) {
+                tracing::debug!("{} rejected synced block: {}", v.id, e);
+                break;
+            }
+            self.report.synced_blocks += 1;
+            self.process_outputs(i);
+        }
+        self.sync_validator(i);
+    }
+
+    /// React to a validator entering a new step, round or height
+    fn sync_validator(&mut self, i: usize) {
+        loop {
+            let v = &self.validators[i];
+            let state = v.engine.round_state();
+            let position = (state.height, state.round, state.step);
+            if v.position == Some(position) {
+                return;
+            }
+            let new_round = v.position.map(|(h, r, _)| (h, r)) != Some((position.0, position.1));
+
+            self.validators[i].position = Some(position);
+            let timeout = self.now + self.validators[i].engine.step_timeout();
+            let epoch = self.validators[i].epoch;
+            self.schedule(timeout, Event::Timeout { validator: i, epoch, position });
+
+            if new_round {
+                let reference = self.reference_block(position.0).header.efficiency_score;
+                self.validators[i].engine.set_reference_efficiency(reference);
+                if self.started_rounds.insert((position.0, position.1)) {
+                    self.schedule(self.now, Event::StartRound {
+                        height: position.0,
+                        round: position.1,
+                    });
+                }
+            }
+        }
+    }
+
+    fn process_outputs(&mut self, i: usize) {
+        let outputs = self.validators[i].engine.drain_outputs();
+        let id = self.validators[i].id.clone();
+        for output in outputs {
+            match output {
+                EngineOutput::Broadcast(message) => {
+                    let conflicting = self.conflicting_vote(i, &message);
+                    self.broadcast(&id, message);
+                    if let Some(conflicting) = conflicting {
+                        self.broadcast(&id, conflicting);
+                    }
+                }
+                EngineOutput::Decided(decided) => {
+                    self.send_ratification(i, &decided.block);
+                    self.report
+                        .decided
+                        .entry(id.clone())
+                        .or_default()
+                        .insert(decided.height, decided.block.hash());
+                    self.validators[i].blocks.insert(
+                        decided.height,
+                        (decided.block.clone(), decided.certificate.clone()),
+                    );
+                    self.finalize(&decided.block, decided.certificate);
+                }
+                EngineOutput::Evidence(evidence) => {
+                    if self.evidence_seen.insert(evidence.hash()) {
+                        self.report.evidence.push(evidence);
+                    }
+                }
+            }
+        }
+    }
+
+    fn finalize(&mut self, block: &Block, certificate: CommitCertificate) {
+        match self.chain.finalize(block, certificate, &self.committee) {
+            Ok(newly_finalized) => {
+                self.report.finalized.insert(block.height(), block.hash());
+                if newly_finalized {
+                    self.to_ratify.insert(block.height());
+                }
+            }
+            Err(e) => self
+                .report
+                .violations
+                .push(format!("height {} not finalized: {}", block.height(), e)),
+        }
+    }
+
+    /// Validator `i` scores its decided block to the coordinator
+    ///
+    /// The score is the block's recomputed efficiency as a percentage of the
+    /// height's reference, capped at 100; a Byzantine validator scores 0.
+    fn send_ratification(&mut self, i: usize, block: &Block) {
+        let validator = self.validators[i].id.clone();
+        let reference = self.reference_block(block.height()).header.efficiency_score;
+        let efficiency = block_efficiency_score(block, &self.config.consensus);
+        let score = if self.report.byzantine.contains(&validator) {
+            0
+        } else {
+            efficiency.saturating_mul(100).checked_div(reference).unwrap_or(100).min(100)
+        };
+        if let Some(latency) = self.link(&validator, COORDINATOR) {
+            self.schedule(self.now + latency, Event::Ratify {
+                validator,
+                height: block.height(),
+                block_hash: block.hash(),
+                score,
+            });
+        }
+    }
+
+    /// Open a `VotingSystem` round for the next finalized block, if idle
+    async fn start_ratification(&mut self) {
+        if self.ratifying.is_some() {
+            return;
+        }
+        let Some(height) = self.to_ratify.pop_first() else { return };
+        let block = crate::blockchain::Block {
+            header: crate::blockchain::BlockHeader {
+                index: height,
+                ..Default::default()
+            },
+            hash: hex::encode(self.report.finalized[&height]),
+            ..Default::default()
+        };
+        if let Err(e) = self.voting.start_voting_round(&block).await {
+            self.report.violations.push(format!("ratification of {} not started: {}", height, e));
+            return;
+        }
+        self.ratifying = Some(height);
+        self.schedule(self.now + RATIFY_WINDOW, Event::EndRatification);
+
+        let early = self.early_ratify_votes.remove(&height).unwrap_or_default();
+        for (validator, block_hash, score) in early {
+            self.ratify_vote(validator, height, block_hash, score).await;
+        }
+    }
+
+    /// Cast a validator's score in the coordinator's `VotingSystem`
+    async fn ratify_vote(
+        &mut self,
+        validator: String,
+        height: u64,
+        block_hash: [u8; 32],
+        score: u64,
+    ) {
+        if self.ratifying != Some(height) {
+            if self.to_ratify.contains(&height) {
+                self.early_ratify_votes
+                    .entry(height)
+                    .or_default()
+                    .push((validator, block_hash, score));
+            } else {
+                tracing::debug!("Late ratification vote from {} at height {}", validator, height);
+                self.report.messages_rejected += 1;
+            }
+            return;
+        }
+        let block_hash = hex::encode(block_hash);
+        if let Err(e) = self.voting.cast_vote(&validator, &block_hash, score).await {
+            tracing::debug!("Coordinator rejected ratification vote from {}: {}", validator, e);
+            self.report.messages_rejected += 1;
+        }
+    }
+
+    /// Close the open ratification round and record its result
+    async fn end_ratification(&mut self) {
+        let Some(height) = self.ratifying.take() else { return };
+        match self.voting.end_voting_round().await {
+            Ok(result) => {
+                self.report.ratifications.insert(height, result);
+            }
+            Err(e) => tracing::debug!("Height {} not ratified: {}", height, e),
+        }
+    }
+
+    /// Second vote from an equivocating validator, for another proposal
+    fn conflicting_vote(&self, i: usize, message: &ConsensusMessage) -> Option<ConsensusMessage> {
+        let v = &self.validators[i];
+        let equivocates = self.faults.iter().any(|f| {
+            matches!(f, Fault::Equivocate { validator } if *validator == v.id)
+        });
+        let ConsensusMessage::RankedVote { height, round, block_hash, .. } = message else {
+            return None;
+        };
+        if !equivocates {
+            return None;
+        }
+
+        let (other, efficiency) = self
+            .round_proposals
+            .get(&(*height, *round))?
+            .iter()
+            .find(|(hash, _)| hash != block_hash)?;
+        let mut vote = RankedVote::new(*height, *round, *other, *efficiency, v.id.clone());
+        vote.sign(&v.keys).ok()?;
+        Some(ConsensusMessage::RankedVote {
+            height: vote.height,
+            round: vote.round,
+            block_hash: vote.block_hash,
+            efficiency_score: vote.efficiency_score,
+            validator_id: vote.validator_id,
+            signature: vote.signature,
+        })
+    }
+
+    /// Every live builder proposes for `(height, round)`
+    fn propose(&mut self, height: u64, round: u64) {
+        if self.chain.finalized_height() + 1 != height {
+            return;
+        }
+        let reference = self.reference_block(height);
+
+        for b in 0..self.builders.len() {
+            if self.builders[b].crashed {
+                continue;
+            }
+            let view: Vec<Transaction> = self.mempools[&height]
+                .clone()
+                .into_iter()
+                .filter(|_| self.rng.random_bool(0.8))
+                .collect();
+            let selected = match self.selector.select_transactions(view) {
+                Ok(selected) => selected.into_transactions(),
+                Err(_) => Vec::new(),
+            };
+
+            let builder = &self.builders[b];
+            let mut block = Self::with_transactions(&reference, selected, &self.config.consensus);
+            if block.header.efficiency_score < reference.header.efficiency_score {
+                block = reference.clone();
+            }
+            block.header.round = round;
+            block.header.proposer_id = builder.id.clone();
+            if self.faults.iter().any(|f| {
+                matches!(f, Fault::FalseEfficiency { builder: id } if *id == builder.id)
+            }) {
+                block.header.efficiency_score += 1;
+            }
+
+            let builder = &mut self.builders[b];
+            let mut proposal = BlockProposal::new(height, round, builder.id.clone(), block);
+            if let Err(e) = builder.signed.sign_proposal(&mut proposal, &builder.keys) {
+                tracing::debug!("{} did not propose: {}", builder.id, e);
+                continue;
+            }
+            let block_hash = proposal.block.hash();
+            self.round_proposals
+                .entry((height, round))
+                .or_default()
+                .push((block_hash, proposal.efficiency_score()));
+
+            let id = builder.id.clone();
+            self.broadcast(&id, ConsensusMessage::Proposal {
+                height,
+                round,
+                proposer_id: proposal.proposer_id.clone(),
+                block_hash,
+                efficiency_score: proposal.efficiency_score(),
+                block_data: proposal.block.to_canonical_bytes(),
+                signature: proposal.signature,
+            });
+        }
+    }
+
+    /// `template` with its transactions replaced
+    fn with_transactions(
+        template: &Block,
+        transactions: Vec<Transaction>,
+        config: &ConsensusConfig,
+    ) -> Block {
+        let mut block = Block::new(template.header.clone(), transactions);
+        block.header.transactions_root = block.compute_transactions_root();
+        block.header.point_price = if block.transactions.is_empty() {
+            0
+        } else {
+            let total: u128 = block.transactions.iter().map(|tx| tx.point_price as u128).sum();
+            (total / block.transactions.len() as u128) as u64
+        };
+        block.header.efficiency_score = block_efficiency_score(&block, config);
+        block
+    }
+
+    /// Coordinator's reference block on top of the finalized tip
+    fn reference_block(&mut self, height: u64) -> Block {
+        let config = &self.config;
+        let mempool = self.mempools.entry(height).or_insert_with(|| {
+            let mut rng = StdRng::seed_from_u64(config.seed ^ height.rotate_left(32));
+            let target = config.consensus.target_point_price;
+            (0..config.mempool_size)
+                .map(|i| {
+                    Transaction::new(
+                        height,
+                        config.consensus.chain_id.clone(),
+                        format!("sender-{}", i),
+                        Some(format!("recipient-{}", rng.random_range(0..10))),
+                        vec![0u8; rng.random_range(0..64)],
+                        rng.random_range(target / 2..=target * 3 / 2),
+                        rng.random_range(0..1_000),
+                    )
+                })
+                .collect()
+        });
+        generate_reference_block(mempool, height, self.chain.finalized_tip(), &config.consensus)
+    }
+
+    /// Send `message` from `from` to every other validator
+    fn broadcast(&mut self, from: &str, message: ConsensusMessage) {
+        for to in 0..self.validators.len() {
+            if self.validators[to].id == from {
+                continue;
+            }
+            let to_id = self.validators[to].id.clone();
+            if let Some(latency) = self.link(from, &to_id) {
+                self.schedule(self.now + latency, Event::Deliver {
+                    from: from.to_string(),
+                    to,
+                    message: message.clone(),
+                });
+            }
+        }
+    }
+
+    /// Send one message from `from` to `to`: its latency, or `None` if lost
+    fn link(&mut self, from: &str, to: &str) -> Option<Duration> {
+        self.report.messages_sent += 1;
+        if self.partitioned(from, to) || self.dropped() {
+            self.report.messages_dropped += 1;
+            return None;
+        }
+        Some(self.latency())
+    }
+
+    fn active(&self, from: Duration, until: Duration) -> bool {
+        from <= self.now && self.now < until
+    }
+
+    fn partitioned(&self, a: &str, b: &str) -> bool {
+        self.faults.iter().any(|f| match f {
+            Fault::Partition { groups, from, until } if self.active(*from, *until) => {
+                let group = |id: &str| groups.iter().position(|g| g.iter().any(|n| n == id));
+                matches!((group(a), group(b)), (Some(x), Some(y)) if x != y)
+            }
+            _ => false,
+        })
+    }
+
+    fn dropped(&mut self) -> bool {
+        let rates: Vec<f64> = self
+            .faults
+            .iter()
+            .filter_map(|f| match f {
+                Fault::Drop { rate, from, until } if self.active(*from, *until) => Some(*rate),
+                _ => None,
+            })
+            .collect();
+        rates.into_iter().any(|rate| self.rng.random_bool(rate.clamp(0.0, 1.0)))
+    }
+
+    fn latency(&mut self) -> Duration {
+        let extra: Duration = self
+            .faults
+            .iter()
+            .filter_map(|f| match f {
+                Fault::Delay { extra, from, until } if self.active(*from, *until) => Some(*extra),
+                _ => None,
+            })
+            .sum();
+        let min = self.config.min_latency.as_millis() as u64;
+        let max = (self.config.max_latency.as_millis() as u64).max(min);
+        let millis = self.rng.random_range(min..=max);
+        Duration::from_millis(millis) + extra
+    }
+}
+
+/// `LastSignedStore` record of node `id` in the run directory `dir`
+fn record_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.signed", id))
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[tokio::test]
+    async fn test_honest_run_is_deterministic() {
+        let config = SimConfig {
+            seed: 7,
+            ..SimConfig::default()
+        };
+        let first = Simulation::new(config.clone()).run().await;
+        let second = Simulation::new(config.clone()).run().await;
+
+        first.check_invariants().unwrap();
+        assert!(first.finalized_height() >= 3);
+        assert_eq!(first.finalized, second.finalized);
+        assert_eq!(first.messages_sent, second.messages_sent);
+        assert_eq!(first.elapsed, second.elapsed);
+        assert_eq!(first.ratifications.len(), first.finalized.len());
+
+        let other = Simulation::new(SimConfig { seed: 8, ..config }).run().await;
+        assert_ne!(other.finalized, first.finalized);
+    }
+
+    #[tokio::test]
+    async fn test_byzantine_nodes_are_contained() {
+        let report = Simulation::new(SimConfig::default())
+            .with_fault(Fault::Equivocate { validator: "v3".to_string() })
+            .with_fault(Fault::FalseEfficiency { builder: "b1".to_string() })
+            .with_fault(Fault::Drop { rate: 0.05, from: secs(0), until: secs(3600) })
+            .run()
+            .await;
+
+        report.check_invariants().unwrap();
+        assert!(report.finalized_height() >= 3);
+        assert!(report.evidence.iter().all(|e| e.validator_id() == "v3"));
+        assert!(!report.evidence.is_empty());
+        assert!(report.messages_rejected > 0);
+        // The Byzantine validator's 0 score is outvoted
+        let dissent: Vec<u64> = report
+            .ratifications
+            .values()
+            .filter_map(|r| r.votes.get("v3").map(|vote| vote.score))
+            .collect();
+        assert!(!dissent.is_empty());
+        assert!(dissent.iter().all(|&score| score == 0));
+    }
+
+    #[tokio::test]
+    async fn test_recovers_from_partition_and_crash() {
+        let split = vec![
+            vec!["v0".to_string(), "v1".to_string()],
+            vec!["v2".to_string(), "v3".to_string()],
+        ];
+        let report = Simulation::new(SimConfig::default())
+            .with_fault(Fault::Partition { groups: split, from: secs(0), until: secs(200) })
+            .with_fault(Fault::Delay { extra: secs(1), from: secs(0), until: secs(600) })
+            .with_fault(Fault::Crash {
+                node: "v3".to_string(),
+                at: secs(250),
+                recover_at: Some(secs(300)),
+            })
+            .run()
+            .await;
+
+        report.check_invariants().unwrap();
+        assert!(report.finalized_height() >= 3);
+        assert!(report.elapsed > secs(200));
+        assert!(report.messages_dropped > 0);
+        assert!(report.synced_blocks > 0);
+        assert!(!report.ratifications.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restarted_validator_keeps_its_vote() {
+        let mut sim = Simulation::new(SimConfig::default());
+        sim.start();
+
+        // Run until v3 votes, keeping the proposals it received
+        let mut proposals = Vec::new();
+        while sim.validators[3].engine.round_state().voted_round.is_none() {
+            if let Some((_, Event::Deliver { from, to: 3, message })) = sim.queue.first_key_value()
+            {
+                if matches!(message, ConsensusMessage::Proposal { .. }) {
+                    proposals.push((from.clone(), message.clone()));
+                }
+            }
+            assert!(sim.step().await);
+        }
+        let state = sim.validators[3].engine.round_state();
+        let (round, voted) = (state.round, state.locked_hash.unwrap());
+        assert_eq!(state.height, 1);
+
+        // The restarted engine comes back locked from its record alone
+        sim.handle(Event::Crash { node: "v3".to_string() }).await;
+        sim.handle(Event::Recover { node: "v3".to_string() }).await;
+        let state = sim.validators[3].engine.round_state();
+        assert_eq!((state.height, state.round, state.step), (1, round, RoundStep::ProposeWindow));
+        assert_eq!(state.locked_hash, Some(voted));
+
+        // Shown only a proposal it did not vote for, v3 does not vote again
+        let (from, other) = proposals
+            .into_iter()
+            .find(|(_, m)| {
+                matches!(m, ConsensusMessage::Proposal { round: r, block_hash, .. }
+                    if *r == round && *block_hash != voted)
+            })
+            .unwrap();
+        sim.deliver(&from, 3, other);
+        sim.validators[3].engine.on_timeout();
+        assert!(!sim.validators[3].engine.drain_outputs().iter().any(|o| matches!(
+            o,
+            EngineOutput::Broadcast(ConsensusMessage::RankedVote { .. })
+        )));
+        sim.sync_validator(3);
+
+        while !sim.done() && sim.step().await {}
+        let report = sim.finish();
+        report.check_invariants().unwrap();
+        assert!(report.evidence.is_empty());
+        assert!(report.decided["v3"].contains_key(&3));
+    }
+}
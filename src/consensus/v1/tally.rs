@@ -0,0 +1,369 @@
+//! PoAI v1 Ranked-Vote Tally
+//!
+//! Counts `RankedVote`s for a single height and round and decides whether one
+//! proposal holds a 2/3+ quorum of the committee.
+//!
+//! ## Vote Admission
+//!
+//! A vote is counted only if it is for the tally's height and round, comes
+//! from a committee member, and carries a valid signature. Each validator
+//! counts once: resubmitting the same vote is a `DuplicateVote`, and voting
+//! for two different blocks is an `Equivocation` that removes the validator's
+//! vote entirely, so the outcome does not depend on arrival order.
+//!
+//! ## Result
+//!
+//! Only proposals whose efficiency claim was verified and that reach the
+//! reference efficiency are eligible, and a vote only counts for a proposal if
+//! its `efficiency_score` matches the verified efficiency. Proposals from
+//! earlier rounds of the height stay eligible, since validators locked on a
+//! block keep voting for it. The leader is the
+//! proposal with the most votes; ties go to the lowest proposer ID, then the
+//! lowest block hash. The leader wins if its votes reach
+//! `ConsensusConfig::quorum_threshold`.
+//!
+//! `rank_proposals` applies the same eligibility rule and orders proposals by
+//! efficiency with the same proposer ID tiebreak; validators vote for the
+//! first entry.
+
+use super::types::{ConsensusConfig, ConsensusError, ConsensusResult, ValidatorInfo};
+use crate::blockchain::v1::{RankedVote, ValidatedProposal};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Outcome of a ranked vote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RankedQuorumResult {
+    /// A proposal reached 2/3+ of the committee
+    Winner {
+        block_hash: [u8; 32],
+        efficiency_score: u64,
+        vote_count: usize,
+        proposer_id: String,
+    },
+    /// Votes were cast but no proposal reached the threshold
+    NoQuorum {
+        leader_votes: usize,
+        threshold: usize,
+    },
+    /// No proposal was eligible to win
+    NoValidProposals,
+}
+
+/// Eligible proposals, best first
+///
+/// Drops proposals whose efficiency claim did not verify or that fall below
+/// the reference, then orders by verified efficiency (highest first), proposer
+/// ID (lowest first) and block hash.
+pub fn rank_proposals<'a>(
+    proposals: impl IntoIterator<Item = &'a ValidatedProposal>,
+) -> Vec<&'a ValidatedProposal> {
+    let mut ranked: Vec<(&ValidatedProposal, [u8; 32])> = proposals
+        .into_iter()
+        .filter(|p| p.efficiency_matches_claim() && p.beats_reference)
+        .map(|p| (p, p.proposal.block.hash()))
+        .collect();
+    ranked.sort_by(|(a, ha), (b, hb)| {
+        b.verified_efficiency
+            .cmp(&a.verified_efficiency)
+            .then_with(|| a.proposer_id().cmp(b.proposer_id()))
+            .then_with(|| ha.cmp(hb))
+    });
+    ranked.into_iter().map(|(p, _)| p).collect()
+}
+
+/// Ranked votes collected for one height and round
+#[derive(Debug, Clone)]
+pub struct RankedVoteTally {
+    height: u64,
+    round: u64,
+    committee: HashMap<String, [u8; 32]>,
+    threshold: usize,
+    votes: BTreeMap<String, RankedVote>,
+    equivocators: BTreeSet<String>,
+}
+
+impl RankedVoteTally {
+    /// Create an empty tally for `committee` at `height` / `round`
+    pub fn new(
+        height: u64,
+        round: u64,
+        committee: &[ValidatorInfo],
+        config: &ConsensusConfig,
+    ) -> Self {
+        Self {
+            height,
+            round,
+            committee: committee
+                .iter()
+                .map(|v| (v.validator_id.clone(), v.public_key))
+                .collect(),
+            threshold: config.quorum_threshold(committee.len()),
+            votes: BTreeMap::new(),
+            equivocators: BTreeSet::new(),
+        }
+    }
+
+    /// Votes needed for a quorum
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Number of counted votes
+    pub fn vote_count(&self) -> usize {
+        self.votes.len()
+    }
+
+    /// Validators caught voting for two different blocks
+    pub fn equivocators(&self) -> impl Iterator<Item = &str> {
+        self.equivocators.iter().map(String::as_str)
+    }
+
+    /// Counted votes for `block_hash`, ordered by validator ID
+    pub fn votes_for<'a>(
+        &'a self,
+        block_hash: &'a [u8; 32],
+    ) -> impl Iterator<Item = &'a RankedVote> {
+        self.votes.values().filter(move |vote| &vote.block_hash == block_hash)
+    }
+
+    /// Number of counted votes for blocks other than `block_hash`
+    pub fn votes_against(&self, block_hash: &[u8; 32]) -> usize {
+        self.votes.values().filter(|vote| &vote.block_hash != block_hash).count()
+    }
+
+    /// Verify and count a vote
+    pub fn add_vote(&mut self, vote: RankedVote) -> ConsensusResult<()> {
+        if vote.height != self.height {
+            return Err(ConsensusError::WrongHeight {
+                expected: self.height,
+                got: vote.height,
+            });
+        }
+        if vote.round != self.round {
+            return Err(ConsensusError::WrongRound {
+                expected: self.round,
+                got: vote.round,
+            });
+        }
+        let public_key = self
+            .committee
+            .get(&vote.validator_id)
+            .ok_or_else(|| ConsensusError::NotInCommittee(vote.validator_id.clone()))?;
+        vote.verify_signature(public_key)?;
+
+        if self.equivocators.contains(&vote.validator_id) {
+            return Err(ConsensusError::Equivocation {
+                validator_id: vote.validator_id,
+            });
+        }
+        if let Some(existing) = self.votes.get(&vote.validator_id) {
+            if existing.block_hash == vote.block_hash
+                && existing.efficiency_score == vote.efficiency_score
+            {
+                return Err(ConsensusError::DuplicateVote(vote.validator_id));
+            }
+            self.votes.remove(&vote.validator_id);
+            self.equivocators.insert(vote.validator_id.clone());
+            return Err(ConsensusError::Equivocation {
+                validator_id: vote.validator_id,
+            });
+        }
+
+        self.votes.insert(vote.validator_id.clone(), vote);
+        Ok(())
+    }
+
+    /// Eligible proposal with the most counted votes, and its vote count
+    ///
+    /// `None` if no counted vote is for an eligible proposal.
+    pub fn leader<'a>(
+        &self,
+        proposals: impl IntoIterator<Item = &'a ValidatedProposal>,
+    ) -> Option<(&'a ValidatedProposal, usize)> {
+        let eligible = self.eligible(proposals);
+        self.count_leader(&eligible)
+            .map(|(block_hash, vote_count)| (eligible[&block_hash], vote_count))
+    }
+
+    /// Decide the round from the counted votes
+    pub fn result<'a>(
+        &self,
+        proposals: impl IntoIterator<Item = &'a ValidatedProposal>,
+    ) -> RankedQuorumResult {
+        let eligible = self.eligible(proposals);
+        if eligible.is_empty() {
+            return RankedQuorumResult::NoValidProposals;
+        }
+
+        match self.count_leader(&eligible) {
+            Some((block_hash, vote_count)) if vote_count >= self.threshold => {
+                let proposal = eligible[&block_hash];
+                RankedQuorumResult::Winner {
+                    block_hash,
+                    efficiency_score: proposal.verified_efficiency,
+                    vote_count,
+                    proposer_id: proposal.proposer_id().to_string(),
+                }
+            }
+            leader => RankedQuorumResult::NoQuorum {
+                leader_votes: leader.map(|(_, count)| count).unwrap_or(0),
+                threshold: self.threshold,
+            },
+        }
+    }
+
+    /// Proposals of this height, up to this round, that can win
+    fn eligible<'a>(
+        &self,
+        proposals: impl IntoIterator<Item = &'a ValidatedProposal>,
+    ) -> HashMap<[u8; 32], &'a ValidatedProposal> {
+        rank_proposals(proposals)
+            .into_iter()
+            .filter(|p| p.proposal.height == self.height && p.proposal.round <= self.round)
+            .map(|p| (p.proposal.block.hash(), p))
+            .collect()
+    }
+
+    /// Most voted eligible block; ties go to the lowest proposer ID, then the
+    /// lowest block hash
+    fn count_leader(
+        &self,
+        eligible: &HashMap<[u8; 32], &ValidatedProposal>,
+    ) -> Option<([u8; 32], usize)> {
+        let mut counts: HashMap<[u8; 32], usize> = HashMap::new();
+        for vote in self.votes.values() {
+            if let Some(p) = eligible.get(&vote.block_hash) {
+                if vote.efficiency_score == p.verified_efficiency {
+                    *counts.entry(vote.block_hash).or_default() += 1;
+                }
+            }
+        }
+
+        counts.into_iter().min_by(|(ha, a), (hb, b)| {
+            b.cmp(a)
+                .then_with(|| eligible[ha].proposer_id().cmp(eligible[hb].proposer_id()))
+                .then_with(|| ha.cmp(hb))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::{Block, BlockHeader, BlockProposal};
+    use crate::consensus::v1::test_support::committee;
+    use crate::crypto::common::traits::KeyPair;
+    use crate::crypto::Ed25519Keys;
+
+    fn proposal(proposer: &str, efficiency: u64) -> ValidatedProposal {
+        let mut header = BlockHeader::genesis("c");
+        header.height = 1;
+        header.proposer_id = proposer.to_string();
+        header.efficiency_score = efficiency;
+        let block = Block::new(header, vec![]);
+        ValidatedProposal::new(BlockProposal::new(1, 0, proposer.to_string(), block), efficiency, 0)
+    }
+
+    fn vote(keys: &Ed25519Keys, validator: usize, p: &ValidatedProposal) -> RankedVote {
+        let mut vote = RankedVote::new(
+            1,
+            0,
+            p.proposal.block.hash(),
+            p.verified_efficiency,
+            format!("v{}", validator),
+        );
+        vote.sign(keys).unwrap();
+        vote
+    }
+
+    #[test]
+    fn test_quorum_winner() {
+        let (infos, keys) = committee(4);
+        let mut tally = RankedVoteTally::new(1, 0, &infos, &ConsensusConfig::default());
+        let a = proposal("alice", 100);
+        let b = proposal("bob", 90);
+
+        for (i, k) in keys.iter().enumerate().take(3) {
+            tally.add_vote(vote(k, i, &a)).unwrap();
+        }
+        tally.add_vote(vote(&keys[3], 3, &b)).unwrap();
+
+        assert_eq!(
+            tally.result([&a, &b]),
+            RankedQuorumResult::Winner {
+                block_hash: a.proposal.block.hash(),
+                efficiency_score: 100,
+                vote_count: 3,
+                proposer_id: "alice".to_string(),
+            }
+        );
+        assert_eq!(tally.votes_for(&a.proposal.block.hash()).count(), 3);
+    }
+
+    #[test]
+    fn test_no_quorum_and_no_valid_proposals() {
+        let (infos, keys) = committee(4);
+        let mut tally = RankedVoteTally::new(1, 0, &infos, &ConsensusConfig::default());
+        let a = proposal("alice", 100);
+        let b = proposal("bob", 100);
+
+        tally.add_vote(vote(&keys[0], 0, &a)).unwrap();
+        tally.add_vote(vote(&keys[1], 1, &a)).unwrap();
+        tally.add_vote(vote(&keys[2], 2, &b)).unwrap();
+        assert_eq!(
+            tally.result([&a, &b]),
+            RankedQuorumResult::NoQuorum { leader_votes: 2, threshold: 3 }
+        );
+        assert_eq!(tally.votes_against(&a.proposal.block.hash()), 1);
+
+        // A proposal whose claim does not verify is never eligible
+        let mut lying = proposal("carol", 100);
+        lying.verified_efficiency = 50;
+        assert_eq!(tally.result([&lying]), RankedQuorumResult::NoValidProposals);
+    }
+
+    #[test]
+    fn test_rejects_invalid_votes() {
+        let (infos, keys) = committee(4);
+        let outsider = Ed25519Keys::new().unwrap();
+        let mut tally = RankedVoteTally::new(1, 0, &infos, &ConsensusConfig::default());
+        let a = proposal("alice", 100);
+        let b = proposal("bob", 100);
+
+        assert!(matches!(
+            tally.add_vote(vote(&outsider, 0, &a)),
+            Err(ConsensusError::InvalidSignature(_))
+        ));
+        let mut stranger = vote(&outsider, 0, &a);
+        stranger.validator_id = "x".to_string();
+        assert!(matches!(
+            tally.add_vote(stranger),
+            Err(ConsensusError::NotInCommittee(_))
+        ));
+
+        tally.add_vote(vote(&keys[0], 0, &a)).unwrap();
+        assert!(matches!(
+            tally.add_vote(vote(&keys[0], 0, &a)),
+            Err(ConsensusError::DuplicateVote(_))
+        ));
+        assert!(matches!(
+            tally.add_vote(vote(&keys[0], 0, &b)),
+            Err(ConsensusError::Equivocation { .. })
+        ));
+        assert_eq!(tally.vote_count(), 0);
+        assert_eq!(tally.equivocators().collect::<Vec<_>>(), vec!["v0"]);
+    }
+
+    #[test]
+    fn test_ranking_ties_go_to_lowest_proposer_id() {
+        let zed = proposal("zed", 100);
+        let amy = proposal("amy", 100);
+        let low = proposal("aaron", 90);
+        let mut lying = proposal("abe", 200);
+        lying.verified_efficiency = 10;
+
+        let ranked = rank_proposals([&zed, &low, &lying, &amy]);
+        let order: Vec<&str> = ranked.iter().map(|p| p.proposer_id()).collect();
+        assert_eq!(order, vec!["amy", "zed", "aaron"]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Test Support
+//!
+//! Fixtures shared by the consensus and network unit tests.
+
+use super::certificate::CommitCertificate;
+use super::types::ValidatorInfo;
+use crate::blockchain::v1::{Block, BlockHeader, RankedVote};
+use crate::crypto::common::traits::KeyPair;
+use crate::crypto::Ed25519Keys;
+
+/// Committee of `n` fresh validators `v0..vn` and their keys
+pub(crate) fn committee(n: usize) -> (Vec<ValidatorInfo>, Vec<Ed25519Keys>) {
+    let keys: Vec<Ed25519Keys> = (0..n).map(|_| Ed25519Keys::new().unwrap()).collect();
+    let infos = keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| ValidatorInfo::new(format!("v{}", i), k.public_key_bytes(), "c".to_string()))
+        .collect();
+    (infos, keys)
+}
+
+/// `len` linked empty blocks on top of the `c` genesis, block `h` scoring
+/// `100 + h`
+pub(crate) fn chain(len: u64) -> Vec<Block> {
+    let mut parent = BlockHeader::genesis("c");
+    (1..=len)
+        .map(|height| {
+            let mut header = BlockHeader::genesis("c");
+            header.height = height;
+            header.previous_hash = parent.hash();
+            header.efficiency_score = 100 + height;
+            let mut block = Block::new(header, vec![]);
+            block.header.transactions_root = block.compute_transactions_root();
+            parent = block.header.clone();
+            block
+        })
+        .collect()
+}
+
+/// Certificate for `block` signed by the validators at `signers`
+pub(crate) fn certify(block: &Block, keys: &[Ed25519Keys], signers: &[usize]) -> CommitCertificate {
+    let votes: Vec<RankedVote> = signers
+        .iter()
+        .map(|&i| {
+            let mut vote = RankedVote::new(
+                block.height(),
+                block.round(),
+                block.hash(),
+                block.header.efficiency_score,
+                format!("v{}", i),
+            );
+            vote.sign(&keys[i]).unwrap();
+            vote
+        })
+        .collect();
+    CommitCertificate::from_votes(
+        block.height(),
+        block.round(),
+        block.hash(),
+        block.header.efficiency_score,
+        &votes,
+    )
+}
@@ -0,0 +1,186 @@
+//! PoAI v1 Timestamp Validation
+//!
+//! Bounds proposals and ranked votes by local time, the round's step windows
+//! and the configured `clock_drift_tolerance`.
+//!
+//! ## Round Schedule
+//!
+//! Schedules come from chain data, so every node computes the same windows.
+//! Round `r` on top of `parent` starts at
+//!
+//! ```text
+//! start = parent.timestamp + (r + 1) * round_duration
+//! ```
+//!
+//! Builders timestamp blocks at the start of their round, so the parent's
+//! round fills the first `round_duration` after its timestamp and round 0 of
+//! the next height begins where it ended. A round starting at `start` (Unix
+//! seconds) has the windows:
+//!
+//! ```text
+//! ProposeWindow  [start,          start + P]
+//! Voting         [start + P,      start + P + V]
+//! Finalize       [start + P + V,  start + P + V + F]
+//! ```
+//!
+//! where `P`, `V` and `F` are the configured step timeouts. Every window is
+//! widened by the drift tolerance `D` on both sides.
+//!
+//! ## Rules
+//!
+//! - **Proposal**: the block timestamp is at most `now + D` and inside the
+//!   proposal window, and the proposal arrives (`now`) inside it.
+//! - **Ranked vote**: the vote arrives between the start of the proposal
+//!   window and the end of the voting window. Ranked votes are not
+//!   timestamped, so the receiver's clock decides: a validator whose clock
+//!   lags cannot vote into a round that has already closed.
+//!
+//! Block timestamps against the parent are checked by `BlockValidator`.
+
+use super::types::{ConsensusConfig, ConsensusError, ConsensusResult, RoundStep};
+use crate::blockchain::v1::{BlockHeader, BlockProposal};
+
+/// Step windows of one round, in Unix seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundSchedule {
+    start: u64,
+    propose: u64,
+    voting: u64,
+    finalize: u64,
+    drift: u64,
+}
+
+impl RoundSchedule {
+    /// Schedule of `round` on top of `parent`
+    pub fn for_round(config: &ConsensusConfig, parent: &BlockHeader, round: u64) -> Self {
+        let offset = round.saturating_add(1).saturating_mul(config.round_duration().as_secs());
+        Self::new(config, parent.timestamp.saturating_add(offset))
+    }
+
+    /// Schedule of a round that starts at `start`
+    pub fn new(config: &ConsensusConfig, start: u64) -> Self {
+        Self {
+            start,
+            propose: config.timeout_propose_window.as_secs(),
+            voting: config.timeout_voting.as_secs(),
+            finalize: config.timeout_finalize.as_secs(),
+            drift: config.clock_drift_tolerance.as_secs(),
+        }
+    }
+
+    /// Time the round starts
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Window of `step`, before widening by the drift tolerance
+    pub fn window(&self, step: RoundStep) -> (u64, u64) {
+        let voting_start = self.start + self.propose;
+        let finalize_start = voting_start + self.voting;
+        match step {
+            RoundStep::ProposeWindow => (self.start, voting_start),
+            RoundStep::Voting => (voting_start, finalize_start),
+            RoundStep::Finalize | RoundStep::Committed => {
+                (finalize_start, finalize_start + self.finalize)
+            }
+        }
+    }
+
+    /// Check a proposal received at local time `now`
+    pub fn check_proposal(&self, proposal: &BlockProposal, now: u64) -> ConsensusResult<()> {
+        let timestamp = proposal.block.header.timestamp;
+        let max = now.saturating_add(self.drift);
+        if timestamp > max {
+            return Err(ConsensusError::TimestampTooFarAhead { timestamp, max });
+        }
+        let (start, end) = self.window(RoundStep::ProposeWindow);
+        self.check_within(RoundStep::ProposeWindow, timestamp, start, end)?;
+        self.check_within(RoundStep::ProposeWindow, now, start, end)
+    }
+
+    /// Check a ranked vote received at local time `now`
+    pub fn check_vote(&self, now: u64) -> ConsensusResult<()> {
+        let (start, _) = self.window(RoundStep::ProposeWindow);
+        let (_, end) = self.window(RoundStep::Voting);
+        self.check_within(RoundStep::Voting, now, start, end)
+    }
+
+    fn check_within(
+        &self,
+        step: RoundStep,
+        time: u64,
+        start: u64,
+        end: u64,
+    ) -> ConsensusResult<()> {
+        let (start, end) = (start.saturating_sub(self.drift), end.saturating_add(self.drift));
+        if time < start || time > end {
+            return Err(ConsensusError::OutsideStepWindow { step, time, start, end });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::{Block, BlockHeader};
+
+    const START: u64 = 1_704_067_200;
+
+    fn proposal(timestamp: u64) -> BlockProposal {
+        let mut header = BlockHeader::genesis("c");
+        header.height = 1;
+        header.timestamp = timestamp;
+        BlockProposal::new(1, 0, "b0".to_string(), Block::new(header, vec![]))
+    }
+
+    #[test]
+    fn test_windows_follow_config() {
+        let schedule = RoundSchedule::new(&ConsensusConfig::default(), START);
+        assert_eq!(schedule.window(RoundStep::ProposeWindow), (START, START + 50));
+        assert_eq!(schedule.window(RoundStep::Voting), (START + 50, START + 58));
+        assert_eq!(schedule.window(RoundStep::Finalize), (START + 58, START + 60));
+    }
+
+    #[test]
+    fn test_schedule_follows_parent_timestamp() {
+        let config = ConsensusConfig::default();
+        let mut parent = BlockHeader::genesis("c");
+        parent.timestamp = START;
+        assert_eq!(RoundSchedule::for_round(&config, &parent, 0).start(), START + 60);
+        assert_eq!(RoundSchedule::for_round(&config, &parent, 2).start(), START + 180);
+    }
+
+    #[test]
+    fn test_proposal_bounds() {
+        let schedule = RoundSchedule::new(&ConsensusConfig::default(), START);
+        schedule.check_proposal(&proposal(START + 10), START + 12).unwrap();
+
+        // Timestamped beyond local time plus drift
+        assert!(matches!(
+            schedule.check_proposal(&proposal(START + 20), START + 12),
+            Err(ConsensusError::TimestampTooFarAhead { max, .. }) if max == START + 17
+        ));
+        // Timestamped before the round began
+        assert!(matches!(
+            schedule.check_proposal(&proposal(START - 6), START + 12),
+            Err(ConsensusError::OutsideStepWindow { step: RoundStep::ProposeWindow, .. })
+        ));
+        // Arrived after the proposal window closed
+        assert!(schedule.check_proposal(&proposal(START + 49), START + 56).is_err());
+        schedule.check_proposal(&proposal(START + 49), START + 55).unwrap();
+    }
+
+    #[test]
+    fn test_votes_after_close_are_rejected() {
+        let schedule = RoundSchedule::new(&ConsensusConfig::default(), START);
+        schedule.check_vote(START + 30).unwrap();
+        schedule.check_vote(START + 63).unwrap();
+        assert!(matches!(
+            schedule.check_vote(START + 64),
+            Err(ConsensusError::OutsideStepWindow { step: RoundStep::Voting, end, .. })
+                if end == START + 63
+        ));
+        assert!(schedule.check_vote(START - 6).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Common types used throughout the decentralized consensus implementation.
 
+use crate::blockchain::v1::codec::{Canonical, CodecError, CodecResult, Decoder, Encoder};
 use std::time::Duration;
 use thiserror::Error;
 
@@ -29,6 +30,26 @@ pub mod constants {
     /// Maximum block size (1 MB)
     pub const MAX_BLOCK_SIZE: usize = 1_000_000;
     
+    /// Maximum transaction size in canonical bytes
+    pub const MAX_TX_SIZE: usize = 10_000;
+    
+    /// Target PointPrice for efficiency scoring
+    pub const TARGET_POINT_PRICE: u64 = 1000;
+    
+    /// Fixed-point scale of `efficiency_score` (10000 = 1.0)
+    pub const EFFICIENCY_SCALE: u64 = 10_000;
+    
+    /// Allowed 20/20/50/10 deviation per category (basis points, 15%)
+    pub const SELECTION_TOLERANCE_BPS: u64 = 1_500;
+    
+    /// Distinct proposals accepted from one builder per round
+    ///
+    /// Fixed by the protocol: a second proposal is equivocation evidence.
+    pub const MAX_PROPOSALS_PER_BUILDER: usize = 1;
+    
+    /// Non-finalized fork-choice candidates tracked per height
+    pub const MAX_CANDIDATES_PER_HEIGHT: usize = 16;
+    
     /// PoAI Competition Model timeout values
     pub const TIMEOUT_PROPOSE_WINDOW: Duration = Duration::from_secs(50);
     pub const TIMEOUT_VOTING: Duration = Duration::from_secs(8);
@@ -44,6 +65,8 @@ pub mod constants {
     pub const DOMAIN_PREFIX_PREVOTE: &[u8] = b"self-chain-vote-prevote-v1";
     pub const DOMAIN_PREFIX_PRECOMMIT: &[u8] = b"self-chain-vote-precommit-v1";
     pub const DOMAIN_PREFIX_RANKED_VOTE: &[u8] = b"self-chain-ranked-vote-v1";
+    pub const DOMAIN_PREFIX_EVIDENCE: &[u8] = b"self-chain-equivocation-evidence-v1";
+    pub const DOMAIN_PREFIX_COMMITTEE: &[u8] = b"self-chain-committee-seed-v1";
 }
 
 /// Configuration for the consensus engine
@@ -64,6 +87,9 @@ pub struct ConsensusConfig {
     /// Finalization timeout
     pub timeout_finalize: Duration,
     
+    /// Maximum disagreement tolerated between local time and message timestamps
+    pub clock_drift_tolerance: Duration,
+    
     /// Minimum committee size
     pub committee_size_min: usize,
     
@@ -75,6 +101,9 @@ pub struct ConsensusConfig {
     
     /// Maximum block size in bytes
     pub max_block_size: usize,
+    
+    /// Target PointPrice used for efficiency scoring
+    pub target_point_price: u64,
 }
 
 impl Default for ConsensusConfig {
@@ -85,10 +114,12 @@ impl Default for ConsensusConfig {
             timeout_propose_window: constants::TIMEOUT_PROPOSE_WINDOW,
             timeout_voting: constants::TIMEOUT_VOTING,
             timeout_finalize: constants::TIMEOUT_FINALIZE,
+            clock_drift_tolerance: constants::CLOCK_DRIFT_TOLERANCE,
             committee_size_min: constants::COMMITTEE_SIZE_MIN,
             committee_size_max: constants::COMMITTEE_SIZE_MAX,
             max_tx_per_block: constants::MAX_TX_PER_BLOCK,
             max_block_size: constants::MAX_BLOCK_SIZE,
+            target_point_price: constants::TARGET_POINT_PRICE,
         }
     }
 }
@@ -153,6 +184,15 @@ pub struct RoundState {
     
     /// Winning block hash (if determined)
     pub winner_hash: Option<[u8; 32]>,
+    
+    /// Round of the local validator's latest vote at this height
+    pub voted_round: Option<u64>,
+    
+    /// Block the local validator must vote for again in later rounds
+    pub locked_hash: Option<[u8; 32]>,
+    
+    /// Most voted block of the latest round without a quorum at this height
+    pub leader_hash: Option<[u8; 32]>,
 }
 
 impl RoundState {
@@ -166,10 +206,15 @@ impl RoundState {
             proposals_received: 0,
             votes_received: 0,
             winner_hash: None,
+            voted_round: None,
+            locked_hash: None,
+            leader_hash: None,
         }
     }
     
     /// Advance to the next round (on timeout/failure)
+    ///
+    /// The vote lock and the leading block carry over to the new round.
     pub fn advance_round(&mut self) {
         self.round += 1;
         self.step = RoundStep::ProposeWindow;
@@ -187,6 +232,9 @@ impl RoundState {
         self.proposals_received = 0;
         self.votes_received = 0;
         self.winner_hash = None;
+        self.voted_round = None;
+        self.locked_hash = None;
+        self.leader_hash = None;
     }
 }
 
@@ -223,7 +271,12 @@ impl ValidatorInfo {
 }
 
 /// Messages exchanged during consensus
-#[derive(Debug, Clone)]
+///
+/// ## Canonical Encoding Order
+///
+/// A `u8` tag (0 = `Proposal`, 1 = `RankedVote`, 2 = `Commit`), then the
+/// variant's fields in declaration order.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConsensusMessage {
     /// Block proposal from a builder
     Proposal {
@@ -261,7 +314,7 @@ pub enum ConsensusMessage {
 }
 
 /// Signature included in commit proof
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CommitSignatureMsg {
     pub validator_id: String,
     pub signature: [u8; 64],
@@ -287,6 +340,105 @@ impl ConsensusMessage {
     }
 }
 
+impl Canonical for CommitSignatureMsg {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_str(&self.validator_id);
+        enc.put_fixed(&self.signature);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            validator_id: dec.get_string()?,
+            signature: dec.get_fixed()?,
+        })
+    }
+}
+
+impl Canonical for ConsensusMessage {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            ConsensusMessage::Proposal {
+                height,
+                round,
+                proposer_id,
+                block_hash,
+                efficiency_score,
+                block_data,
+                signature,
+            } => {
+                enc.put_u8(0);
+                enc.put_u64(*height);
+                enc.put_u64(*round);
+                enc.put_str(proposer_id);
+                enc.put_fixed(block_hash);
+                enc.put_u64(*efficiency_score);
+                enc.put_bytes(block_data);
+                enc.put_fixed(signature);
+            }
+            ConsensusMessage::RankedVote {
+                height,
+                round,
+                block_hash,
+                efficiency_score,
+                validator_id,
+                signature,
+            } => {
+                enc.put_u8(1);
+                enc.put_u64(*height);
+                enc.put_u64(*round);
+                enc.put_fixed(block_hash);
+                enc.put_u64(*efficiency_score);
+                enc.put_str(validator_id);
+                enc.put_fixed(signature);
+            }
+            ConsensusMessage::Commit {
+                height,
+                round,
+                block_hash,
+                signatures,
+            } => {
+                enc.put_u8(2);
+                enc.put_u64(*height);
+                enc.put_u64(*round);
+                enc.put_fixed(block_hash);
+                enc.put_seq(signatures);
+            }
+        }
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        match dec.get_u8()? {
+            0 => Ok(ConsensusMessage::Proposal {
+                height: dec.get_u64()?,
+                round: dec.get_u64()?,
+                proposer_id: dec.get_string()?,
+                block_hash: dec.get_fixed()?,
+                efficiency_score: dec.get_u64()?,
+                block_data: dec.get_bytes()?,
+                signature: dec.get_fixed()?,
+            }),
+            1 => Ok(ConsensusMessage::RankedVote {
+                height: dec.get_u64()?,
+                round: dec.get_u64()?,
+                block_hash: dec.get_fixed()?,
+                efficiency_score: dec.get_u64()?,
+                validator_id: dec.get_string()?,
+                signature: dec.get_fixed()?,
+            }),
+            2 => Ok(ConsensusMessage::Commit {
+                height: dec.get_u64()?,
+                round: dec.get_u64()?,
+                block_hash: dec.get_fixed()?,
+                signatures: dec.get_seq()?,
+            }),
+            tag => Err(CodecError::InvalidTag {
+                field: "consensus message",
+                tag,
+            }),
+        }
+    }
+}
+
 /// Errors that can occur during consensus
 #[derive(Debug, Error)]
 pub enum ConsensusError {
@@ -314,6 +466,12 @@ pub enum ConsensusError {
     #[error("Timeout in step {step}")]
     Timeout { step: RoundStep },
     
+    #[error("Timestamp {timestamp} is too far ahead of local time (max {max})")]
+    TimestampTooFarAhead { timestamp: u64, max: u64 },
+    
+    #[error("Time {time} is outside the {step} window [{start}, {end}]")]
+    OutsideStepWindow { step: RoundStep, time: u64, start: u64, end: u64 },
+    
     #[error("Duplicate vote from validator: {0}")]
     DuplicateVote(String),
     
@@ -329,6 +487,18 @@ pub enum ConsensusError {
     #[error("Below reference efficiency: {proposal} < {reference}")]
     BelowReference { proposal: u64, reference: u64 },
     
+    #[error("Conflicting finalized block at height {height}")]
+    ConflictingFinality { height: u64 },
+    
+    #[error("Refusing to sign: {0}")]
+    DoubleSign(String),
+    
+    #[error("Conflicting entries for validator: {0}")]
+    DuplicateValidator(String),
+    
+    #[error("Not enough eligible validators: {eligible} < {required}")]
+    InsufficientValidators { eligible: usize, required: usize },
+    
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -401,4 +571,45 @@ mod tests {
         assert_eq!(validator.validator_id, "validator-1");
         assert!(validator.is_eligible);
     }
+
+    #[test]
+    fn test_consensus_message_canonical_roundtrip() {
+        let messages = vec![
+            ConsensusMessage::Proposal {
+                height: 3,
+                round: 1,
+                proposer_id: "builder-1".to_string(),
+                block_hash: [1u8; 32],
+                efficiency_score: 4200,
+                block_data: vec![9, 8, 7],
+                signature: [2u8; 64],
+            },
+            ConsensusMessage::RankedVote {
+                height: 3,
+                round: 1,
+                block_hash: [1u8; 32],
+                efficiency_score: 4200,
+                validator_id: "validator-1".to_string(),
+                signature: [3u8; 64],
+            },
+            ConsensusMessage::Commit {
+                height: 3,
+                round: 1,
+                block_hash: [1u8; 32],
+                signatures: vec![CommitSignatureMsg {
+                    validator_id: "validator-1".to_string(),
+                    signature: [3u8; 64],
+                }],
+            },
+        ];
+        for message in messages {
+            let bytes = message.to_canonical_bytes();
+            assert_eq!(ConsensusMessage::from_canonical_bytes(&bytes).unwrap(), message);
+        }
+
+        assert!(matches!(
+            ConsensusMessage::from_canonical_bytes(&[7]),
+            Err(CodecError::InvalidTag { tag: 7, .. })
+        ));
+    }
 }
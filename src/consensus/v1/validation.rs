@@ -0,0 +1,492 @@
+//! PoAI v1 Block Validation
+//!
+//! Checks a block against its parent header and the local account state,
+//! following the nine "Block Validity" rules in `docs/POAI_SPECIFICATION.md`:
+//!
+//! 1. Height is `parent.height + 1`
+//! 2. `previous_hash` is the parent's hash
+//! 3. Timestamp is after the parent's and at most `clock_drift_tolerance`
+//!    ahead of local time
+//! 4. `chain_id` matches
+//! 5. Within `MAX_BLOCK_SIZE` and `MAX_TX_PER_BLOCK`; the size leaves out
+//!    the commit signatures, like the block hash, so a block keeps passing
+//!    once its certificate is attached
+//! 6. Every transaction is valid (and none is duplicated)
+//! 7. `state_root` matches the state after applying the block
+//! 8. `transactions_root` matches the Merkle root
+//! 9. `efficiency_score` matches `compute_efficiency_score`
+//!
+//! If the context carries a mempool snapshot, the block's transaction
+//! selection is also audited (see `selection`) and an `Invalid` result is
+//! reported as a failure.
+//!
+//! Every rule is evaluated; `ValidationResult.errors` lists all failures so
+//! builders can see everything wrong with a rejected proposal at once.
+//!
+//! ```rust,ignore
+//! use self_chain_core::consensus::v1::{BlockValidator, ValidationContext};
+//!
+//! let validator = BlockValidator::new(config);
+//! let result = validator.validate(&block, &ValidationContext::new(&parent, &state, now));
+//! for error in &result.errors {
+//!     println!("rejected: {}", error);
+//! }
+//! ```
+
+use super::efficiency::block_efficiency_score;
+use super::selection::SelectionComplianceChecker;
+use super::types::{ConsensusConfig, ConsensusError, ConsensusResult};
+use crate::blockchain::merkle::transactions_root;
+use crate::blockchain::v1::codec::Encoder;
+use crate::blockchain::v1::{Block, BlockHeader, Transaction};
+use crate::state::{StateError, StateMachine};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// A single failed block validity rule
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BlockValidationError {
+    #[error("Wrong height: expected {expected}, got {actual}")]
+    WrongHeight { expected: u64, actual: u64 },
+
+    #[error("Previous hash mismatch: expected {}, got {}", hex::encode(.expected), hex::encode(.actual))]
+    PreviousHashMismatch { expected: [u8; 32], actual: [u8; 32] },
+
+    #[error("Timestamp {timestamp} is not after parent timestamp {parent}")]
+    TimestampNotAfterParent { timestamp: u64, parent: u64 },
+
+    #[error("Timestamp {timestamp} is more than the drift tolerance ahead of local time (max {max})")]
+    TimestampTooFarAhead { timestamp: u64, max: u64 },
+
+    #[error("Chain ID mismatch: expected {expected}, got {actual}")]
+    ChainIdMismatch { expected: String, actual: String },
+
+    #[error("Too many transactions: {count} (max {max})")]
+    TooManyTransactions { count: usize, max: usize },
+
+    #[error("Block too large: {size} bytes (max {max})")]
+    BlockTooLarge { size: usize, max: usize },
+
+    #[error("Duplicate transaction at index {index} (first seen at {first})")]
+    DuplicateTransaction { index: usize, first: usize },
+
+    #[error("Invalid transaction at index {index}: {error}")]
+    InvalidTransaction { index: usize, error: StateError },
+
+    #[error("State at height {state_height} cannot validate a child of height {parent_height}")]
+    StateHeightMismatch { state_height: u64, parent_height: u64 },
+
+    #[error("State root mismatch: header {}, computed {}", hex::encode(.expected), hex::encode(.computed))]
+    StateRootMismatch { expected: [u8; 32], computed: [u8; 32] },
+
+    #[error("Transactions root mismatch: header {}, computed {}", hex::encode(.expected), hex::encode(.computed))]
+    TransactionsRootMismatch { expected: [u8; 32], computed: [u8; 32] },
+
+    #[error("Efficiency mismatch: claimed {claimed}, actual {actual}")]
+    EfficiencyMismatch { claimed: u64, actual: u64 },
+
+    #[error("Transaction selection deviates {max_deviation_bps} bps from 20/20/50/10")]
+    SelectionNonCompliant { max_deviation_bps: u64 },
+}
+
+/// How closely a block follows the 20/20/50/10 selection rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionCompliance {
+    /// Follows 20/20/50/10 exactly
+    Valid,
+    /// Minor deviation (within 15%)
+    WithinTolerance,
+    /// Gross violation
+    Invalid,
+}
+
+/// Outcome of validating a block
+///
+/// Mirrors the spec's `ValidationResult`, with `errors` holding typed
+/// failures instead of strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationResult {
+    /// True if no rule failed
+    pub is_valid: bool,
+
+    /// Efficiency computed by this node
+    pub actual_efficiency: u64,
+
+    /// Efficiency claimed in the header
+    pub claimed_efficiency: u64,
+
+    /// Whether the claimed efficiency matches
+    pub efficiency_matches: bool,
+
+    /// 20/20/50/10 compliance (`None` if not checked)
+    pub selection_compliance: Option<SelectionCompliance>,
+
+    /// Every failed rule, in rule order
+    pub errors: Vec<BlockValidationError>,
+}
+
+impl ValidationResult {
+    /// Convert to a `ConsensusResult`, joining all failures into one message
+    pub fn into_result(self) -> ConsensusResult<()> {
+        if self.is_valid {
+            return Ok(());
+        }
+
+        let messages: Vec<String> = self.errors.iter().map(ToString::to_string).collect();
+        Err(ConsensusError::BlockValidation(messages.join("; ")))
+    }
+}
+
+/// Chain context a block is validated against
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationContext<'a> {
+    /// Header of the block being extended
+    pub parent: &'a BlockHeader,
+
+    /// Account state after applying the parent
+    pub state: &'a StateMachine,
+
+    /// Local time (Unix seconds)
+    pub now: u64,
+
+    /// Mempool snapshot for the 20/20/50/10 audit (`None` = skip it)
+    pub mempool: Option<&'a [Transaction]>,
+}
+
+impl<'a> ValidationContext<'a> {
+    /// Create a context for validating a child of `parent`
+    pub fn new(parent: &'a BlockHeader, state: &'a StateMachine, now: u64) -> Self {
+        Self {
+            parent,
+            state,
+            now,
+            mempool: None,
+        }
+    }
+
+    /// Also audit transaction selection against a mempool snapshot
+    pub fn with_mempool(mut self, mempool: &'a [Transaction]) -> Self {
+        self.mempool = Some(mempool);
+        self
+    }
+}
+
+/// Stateless checker for the v1 block validity rules
+#[derive(Debug, Clone)]
+pub struct BlockValidator {
+    config: ConsensusConfig,
+}
+
+impl BlockValidator {
+    /// Create a validator for the given consensus configuration
+    pub fn new(config: ConsensusConfig) -> Self {
+        Self { config }
+    }
+
+    /// Consensus configuration
+    pub fn config(&self) -> &ConsensusConfig {
+        &self.config
+    }
+
+    /// Validate `block` as the child of `ctx.parent`, reporting every failure
+    pub fn validate(&self, block: &Block, ctx: &ValidationContext<'_>) -> ValidationResult {
+        let header = &block.header;
+        let mut errors = Vec::new();
+
+        // 1. Height
+        let expected_height = ctx.parent.height + 1;
+        if header.height != expected_height {
+            errors.push(BlockValidationError::WrongHeight {
+                expected: expected_height,
+                actual: header.height,
+            });
+        }
+
+        // 2. Previous hash
+        let parent_hash = ctx.parent.hash();
+        if header.previous_hash != parent_hash {
+            errors.push(BlockValidationError::PreviousHashMismatch {
+                expected: parent_hash,
+                actual: header.previous_hash,
+            });
+        }
+
+        // 3. Timestamp bounds
+        if header.timestamp <= ctx.parent.timestamp {
+            errors.push(BlockValidationError::TimestampNotAfterParent {
+                timestamp: header.timestamp,
+                parent: ctx.parent.timestamp,
+            });
+        }
+        let max_timestamp = ctx.now.saturating_add(self.config.clock_drift_tolerance.as_secs());
+        if header.timestamp > max_timestamp {
+            errors.push(BlockValidationError::TimestampTooFarAhead {
+                timestamp: header.timestamp,
+                max: max_timestamp,
+            });
+        }
+
+        // 4. Chain ID
+        if header.chain_id != self.config.chain_id {
+            errors.push(BlockValidationError::ChainIdMismatch {
+                expected: self.config.chain_id.clone(),
+                actual: header.chain_id.clone(),
+            });
+        }
+
+        // 5. Size limits
+        if block.transactions.len() > self.config.max_tx_per_block {
+            errors.push(BlockValidationError::TooManyTransactions {
+                count: block.transactions.len(),
+                max: self.config.max_tx_per_block,
+            });
+        }
+        let size = unsigned_size(block);
+        if size > self.config.max_block_size {
+            errors.push(BlockValidationError::BlockTooLarge {
+                size,
+                max: self.config.max_block_size,
+            });
+        }
+
+        // 6 & 7. Transactions and state root
+        let mut first_seen = HashMap::new();
+        for (index, tx) in block.transactions.iter().enumerate() {
+            if let Some(&first) = first_seen.get(&tx.hash()) {
+                errors.push(BlockValidationError::DuplicateTransaction { index, first });
+            } else {
+                first_seen.insert(tx.hash(), index);
+            }
+        }
+
+        if ctx.state.height() != ctx.parent.height {
+            errors.push(BlockValidationError::StateHeightMismatch {
+                state_height: ctx.state.height(),
+                parent_height: ctx.parent.height,
+            });
+        } else {
+            let run = ctx.state.dry_run(&block.transactions);
+            let all_applied = run.is_success();
+            for (index, error) in run.failures {
+                errors.push(BlockValidationError::InvalidTransaction { index, error });
+            }
+            // The root is only meaningful if every transaction applied
+            if all_applied && run.state_root != header.state_root {
+                errors.push(BlockValidationError::StateRootMismatch {
+                    expected: header.state_root,
+                    computed: run.state_root,
+                });
+            }
+        }
+
+        // 8. Transactions root
+        let computed_root = transactions_root(&block.transactions);
+        if header.transactions_root != computed_root {
+            errors.push(BlockValidationError::TransactionsRootMismatch {
+                expected: header.transactions_root,
+                computed: computed_root,
+            });
+        }
+
+        // 9. Efficiency
+        let claimed_efficiency = header.efficiency_score;
+        let actual_efficiency = block_efficiency_score(block, &self.config);
+        let efficiency_matches = actual_efficiency == claimed_efficiency;
+        if !efficiency_matches {
+            errors.push(BlockValidationError::EfficiencyMismatch {
+                claimed: claimed_efficiency,
+                actual: actual_efficiency,
+            });
+        }
+
+        // Selection compliance (only with a mempool snapshot)
+        let selection_compliance = ctx.mempool.map(|mempool| {
+            let report = SelectionComplianceChecker::new().check(&block.transactions, mempool);
+            if report.compliance == SelectionCompliance::Invalid {
+                errors.push(BlockValidationError::SelectionNonCompliant {
+                    max_deviation_bps: report.max_deviation_bps,
+                });
+            }
+            report.compliance
+        });
+
+        ValidationResult {
+            is_valid: errors.is_empty(),
+            actual_efficiency,
+            claimed_efficiency,
+            efficiency_matches,
+            selection_compliance,
+            errors,
+        }
+    }
+}
+
+/// Canonical size of `block` without its finality proof, as hashed
+fn unsigned_size(block: &Block) -> usize {
+    let mut enc = Encoder::new();
+    block.header.encode_unsigned(&mut enc);
+    enc.put_seq(&block.transactions);
+    enc.into_bytes().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::codec::Canonical;
+    use crate::blockchain::v1::CommitSignature;
+    use crate::crypto::common::traits::KeyPair;
+    use crate::crypto::{derive_address, Ed25519Keys};
+    use crate::state::{Account, TransferPayload};
+
+    const CHAIN: &str = "test-chain";
+
+    fn setup() -> (BlockValidator, BlockHeader, StateMachine, Ed25519Keys) {
+        let config = ConsensusConfig {
+            chain_id: CHAIN.to_string(),
+            ..Default::default()
+        };
+        let keys = Ed25519Keys::new().unwrap();
+        let mut alice = Account::new(derive_address(keys.public_key()), "self-app".to_string());
+        alice.native_coin_balance = Some(10_000);
+        let state = StateMachine::with_genesis(CHAIN, [alice]);
+
+        let mut parent = BlockHeader::genesis(CHAIN);
+        parent.timestamp = 1_000;
+        parent.state_root = state.state_root().root_hash;
+
+        (BlockValidator::new(config), parent, state, keys)
+    }
+
+    fn transfer(keys: &Ed25519Keys, nonce: u64, amount: u64) -> Transaction {
+        let mut tx = Transaction::new(
+            nonce,
+            CHAIN.to_string(),
+            derive_address(keys.public_key()),
+            Some("bob".to_string()),
+            TransferPayload::native(amount).to_canonical_bytes(),
+            1_000,
+            1_000,
+        );
+        tx.sign(keys).unwrap();
+        tx
+    }
+
+    fn valid_child(parent: &BlockHeader, state: &StateMachine, txs: Vec<Transaction>) -> Block {
+        let mut header = BlockHeader::genesis(CHAIN);
+        header.height = parent.height + 1;
+        header.previous_hash = parent.hash();
+        header.timestamp = parent.timestamp + 60;
+        header.state_root = state.compute_state_root(&txs).unwrap();
+        header.transactions_root = transactions_root(&txs);
+        let mut block = Block::new(header, txs);
+        block.header.efficiency_score = block_efficiency_score(&block, &ConsensusConfig::default());
+        block
+    }
+
+    #[test]
+    fn test_valid_block() {
+        let (validator, parent, state, keys) = setup();
+        let txs = vec![transfer(&keys, 1, 10), transfer(&keys, 2, 10)];
+        let block = valid_child(&parent, &state, txs);
+
+        let result = validator.validate(&block, &ValidationContext::new(&parent, &state, 1_060));
+
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert!(result.efficiency_matches);
+        assert!(result.actual_efficiency > 0);
+        assert_eq!(result.selection_compliance, None);
+        assert!(result.into_result().is_ok());
+
+        // Both transactions are in the mempool; a 2-tx block is all "average"
+        let mempool = block.transactions.clone();
+        let ctx = ValidationContext::new(&parent, &state, 1_060).with_mempool(&mempool);
+        let result = validator.validate(&block, &ctx);
+        assert_eq!(result.selection_compliance, Some(SelectionCompliance::Valid));
+
+        let ctx = ValidationContext::new(&parent, &state, 1_060).with_mempool(&[]);
+        let result = validator.validate(&block, &ctx);
+        assert_eq!(result.selection_compliance, Some(SelectionCompliance::Invalid));
+        assert!(matches!(
+            result.errors[..],
+            [BlockValidationError::SelectionNonCompliant { max_deviation_bps: 10_000 }]
+        ));
+    }
+
+    #[test]
+    fn test_reports_every_failure() {
+        let (validator, parent, state, keys) = setup();
+        let tx = transfer(&keys, 1, 10);
+        let mut block = valid_child(&parent, &state, vec![tx.clone()]);
+        block.transactions.push(tx);
+        block.header.height = 5;
+        block.header.timestamp = 2_000;
+        block.header.chain_id = "other".to_string();
+        block.header.efficiency_score += 1;
+
+        let result = validator.validate(&block, &ValidationContext::new(&parent, &state, 1_060));
+
+        assert!(!result.is_valid);
+        assert!(!result.efficiency_matches);
+        use BlockValidationError::*;
+        let has = |f: fn(&BlockValidationError) -> bool| result.errors.iter().any(f);
+        assert!(matches!(result.errors[0], WrongHeight { expected: 1, actual: 5 }));
+        assert!(has(|e| matches!(e, TimestampTooFarAhead { .. })));
+        assert!(has(|e| matches!(e, ChainIdMismatch { .. })));
+        assert!(has(|e| matches!(e, DuplicateTransaction { index: 1, first: 0 })));
+        assert!(has(|e| matches!(e, InvalidTransaction { index: 1, .. })));
+        assert!(has(|e| matches!(e, TransactionsRootMismatch { .. })));
+        assert!(has(|e| matches!(e, EfficiencyMismatch { .. })));
+        assert!(matches!(
+            result.into_result(),
+            Err(ConsensusError::BlockValidation(_))
+        ));
+    }
+
+    #[test]
+    fn test_size_excludes_commit_signatures() {
+        let (_, parent, state, keys) = setup();
+        let mut block = valid_child(&parent, &state, vec![transfer(&keys, 1, 10)]);
+        let validator = BlockValidator::new(ConsensusConfig {
+            chain_id: CHAIN.to_string(),
+            max_block_size: unsigned_size(&block),
+            ..Default::default()
+        });
+        let ctx = ValidationContext::new(&parent, &state, 1_060);
+        assert!(validator.validate(&block, &ctx).is_valid);
+
+        // Attaching a certificate does not push the block over the limit
+        block.header.commit_signatures = (0..4)
+            .map(|i| CommitSignature {
+                validator_id: format!("v{}", i),
+                signature: [i; 64],
+            })
+            .collect();
+        block.header.commit_round = 1;
+        assert!(block.to_canonical_bytes().len() > unsigned_size(&block));
+        let result = validator.validate(&block, &ctx);
+        assert!(result.is_valid, "{:?}", result.errors);
+
+        block.transactions.push(transfer(&keys, 2, 10));
+        let result = validator.validate(&block, &ctx);
+        assert!(result
+            .errors
+            .iter()
+            .any(|e| matches!(e, BlockValidationError::BlockTooLarge { .. })));
+    }
+
+    #[test]
+    fn test_parent_linkage_and_state_root() {
+        let (validator, parent, state, keys) = setup();
+        let mut block = valid_child(&parent, &state, vec![transfer(&keys, 1, 10)]);
+        block.header.previous_hash = [1u8; 32];
+        block.header.timestamp = parent.timestamp;
+        block.header.state_root = [2u8; 32];
+
+        let result = validator.validate(&block, &ValidationContext::new(&parent, &state, 1_060));
+
+        assert_eq!(result.errors.len(), 3);
+        assert!(matches!(result.errors[0], BlockValidationError::PreviousHashMismatch { .. }));
+        assert!(matches!(result.errors[1], BlockValidationError::TimestampNotAfterParent { .. }));
+        assert!(matches!(result.errors[2], BlockValidationError::StateRootMismatch { .. }));
+    }
+}
@@ -27,6 +27,7 @@
 //! ```
 
 use crate::blockchain::{Block, Transaction};
+use crate::clock::{system_clock, SharedClock};
 use crate::consensus::cache::ValidationCache;
 use crate::consensus::error::ConsensusError;
 use crate::consensus::metrics::ConsensusMetrics;
@@ -34,7 +35,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::Instant;
 
 /// Configuration for the validator
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,17 +76,13 @@ pub struct Validator {
     wallet_colors: Arc<tokio::sync::RwLock<HashMap<String, WalletColor>>>,
     metrics: Arc<ConsensusMetrics>,
     cache: Arc<ValidationCache>,
+    clock: SharedClock,
 }
 
 impl Validator {
     /// Create a new validator with default configuration
     pub fn new(metrics: Arc<ConsensusMetrics>, cache: Arc<ValidationCache>) -> Self {
-        Self {
-            config: ValidatorConfig::default(),
-            wallet_colors: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
-            metrics,
-            cache,
-        }
+        Self::with_config(ValidatorConfig::default(), metrics, cache)
     }
 
     /// Create a new validator with custom configuration
@@ -93,12 +90,23 @@ impl Validator {
         config: ValidatorConfig,
         metrics: Arc<ConsensusMetrics>,
         cache: Arc<ValidationCache>,
+    ) -> Self {
+        Self::with_clock(config, metrics, cache, system_clock())
+    }
+
+    /// Create a new validator that timestamps wallet colors with `clock`
+    pub fn with_clock(
+        config: ValidatorConfig,
+        metrics: Arc<ConsensusMetrics>,
+        cache: Arc<ValidationCache>,
+        clock: SharedClock,
     ) -> Self {
         Self {
             config,
             wallet_colors: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             metrics,
             cache,
+            clock,
         }
     }
 
@@ -150,7 +158,7 @@ impl Validator {
             }
         }
 
-        let start_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs_f64();
+        let start_time = Instant::now();
 
         // Calculate block efficiency
         let efficiency = self.calculate_block_efficiency(block).await?;
@@ -161,7 +169,7 @@ impl Validator {
             self.validate_transaction(tx).await?;
         }
 
-        let duration = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs_f64() - start_time;
+        let duration = start_time.elapsed().as_secs_f64();
         self.metrics.observe_block_validation(duration);
         self.metrics.increment_blocks_validated();
 
@@ -192,7 +200,7 @@ impl Validator {
             WalletColor {
                 address: address.to_string(),
                 color: color.to_string(),
-                last_update: Self::current_timestamp(),
+                last_update: self.clock.unix_secs(),
             },
         );
         Ok(())
@@ -205,9 +213,14 @@ impl Validator {
     /// 2. For each part: recursively sum hex digits until single digit remains
     /// 3. Combine the 6 single digits into a 6-character HEX string
     pub fn calculate_hex_transaction(&self, tx: &Transaction) -> Result<String> {
-        let hash = tx.hash();
-        let hash_hex = hash;
+        Self::hex_transaction_from_hash(&tx.hash())
+    }
 
+    /// Calculate the HEX transaction from a hex-encoded transaction hash
+    ///
+    /// Shared by the production validator and `state::StateMachine`, which
+    /// passes `hex::encode(v1_tx.hash())`.
+    pub fn hex_transaction_from_hash(hash_hex: &str) -> Result<String> {
         // Ensure we have enough data
         if hash_hex.len() < 6 {
             return Err(anyhow::anyhow!("Transaction hash too short for color calculation"));
@@ -255,10 +268,15 @@ impl Validator {
     ///
     /// new_color = (current_color + hex_tx) mod 0x1000000
     pub fn calculate_new_color(&self, current_color: &str, hex_tx: &str) -> Result<String> {
-        if !self.is_valid_hex(current_color) {
+        Self::next_color(current_color, hex_tx)
+    }
+
+    /// Add a HEX transaction to a wallet color (see `calculate_new_color`)
+    pub fn next_color(current_color: &str, hex_tx: &str) -> Result<String> {
+        if !Self::is_hex_color(current_color) {
             return Err(anyhow::anyhow!("Invalid current color format: {}", current_color));
         }
-        if !self.is_valid_hex(hex_tx) {
+        if !Self::is_hex_color(hex_tx) {
             return Err(anyhow::anyhow!("Invalid hex transaction format: {}", hex_tx));
         }
 
@@ -278,6 +296,10 @@ impl Validator {
 
     /// Check if a string is a valid 6-character hex color
     fn is_valid_hex(&self, color: &str) -> bool {
+        Self::is_hex_color(color)
+    }
+
+    fn is_hex_color(color: &str) -> bool {
         color.len() == 6 && color.chars().all(|c| c.is_ascii_hexdigit())
     }
 
@@ -297,13 +319,6 @@ impl Validator {
         let actual = block.transactions.len() as f64;
         Ok((actual / target).min(1.0) * 100.0)
     }
-
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,7 @@
 
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::clock::{Clock, SystemClock};
 
 /// Represents a vote cast by a validator for a block
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,16 +25,18 @@ pub struct Vote {
 }
 
 impl Vote {
-    /// Create a new vote for a block
+    /// Create a new vote for a block, timestamped by the system clock
     pub fn new(block_hash: String, validator_id: String, score: u64) -> Self {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)
-            .expect("Time went backwards").as_secs();
-            
+        Self::new_at(block_hash, validator_id, score, SystemClock.unix_secs())
+    }
+
+    /// Create a new vote for a block cast at `timestamp` (seconds since epoch)
+    pub fn new_at(block_hash: String, validator_id: String, score: u64, timestamp: u64) -> Self {
         Self {
             block_hash,
             validator_id,
             score,
-            timestamp: now,
+            timestamp,
             signature: None,
         }
     }
@@ -78,8 +80,18 @@ pub struct VotingResult {
 }
 
 impl VotingResult {
-    /// Create a new voting result
+    /// Create a new voting result, timestamped by the system clock
     pub fn new(block_hash: String, votes: HashMap<String, Vote>, approved: bool) -> Self {
+        Self::new_at(block_hash, votes, approved, SystemClock.unix_secs())
+    }
+
+    /// Create a new voting result completed at `timestamp` (seconds since epoch)
+    pub fn new_at(
+        block_hash: String,
+        votes: HashMap<String, Vote>,
+        approved: bool,
+        timestamp: u64,
+    ) -> Self {
         let total_votes = votes.len() as u64;
         let participants = votes.len() as u64;
         
@@ -90,9 +102,6 @@ impl VotingResult {
             0.0
         };
         
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)
-            .expect("Time went backwards").as_secs();
-            
         Self {
             block_hash,
             total_votes,
@@ -100,7 +109,7 @@ impl VotingResult {
             average_score,
             approved,
             votes,
-            timestamp: now,
+            timestamp,
         }
     }
 }
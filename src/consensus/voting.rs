@@ -16,6 +16,7 @@
 //! - `min_participation`: Minimum participation rate (0.0 - 1.0)
 
 use crate::blockchain::Block;
+use crate::clock::{system_clock, SharedClock};
 use crate::consensus::error::ConsensusError;
 use crate::consensus::metrics::ConsensusMetrics;
 use crate::consensus::vote::{Vote, VotingResult};
@@ -23,7 +24,6 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
 /// Configuration for the voting system
@@ -54,6 +54,7 @@ pub struct VotingSystem {
     votes: Arc<RwLock<HashMap<String, Vote>>>,
     current_round: Arc<RwLock<Option<VotingRound>>>,
     metrics: Arc<ConsensusMetrics>,
+    clock: SharedClock,
 }
 
 /// Active voting round
@@ -67,21 +68,26 @@ pub struct VotingRound {
 impl VotingSystem {
     /// Create a new voting system with default configuration
     pub fn new(metrics: Arc<ConsensusMetrics>) -> Self {
-        Self {
-            config: VotingConfig::default(),
-            votes: Arc::new(RwLock::new(HashMap::new())),
-            current_round: Arc::new(RwLock::new(None)),
-            metrics,
-        }
+        Self::with_config(VotingConfig::default(), metrics)
     }
 
     /// Create a new voting system with custom configuration
     pub fn with_config(config: VotingConfig, metrics: Arc<ConsensusMetrics>) -> Self {
+        Self::with_clock(config, metrics, system_clock())
+    }
+
+    /// Create a new voting system that reads time from `clock`
+    pub fn with_clock(
+        config: VotingConfig,
+        metrics: Arc<ConsensusMetrics>,
+        clock: SharedClock,
+    ) -> Self {
         Self {
             config,
             votes: Arc::new(RwLock::new(HashMap::new())),
             current_round: Arc::new(RwLock::new(None)),
             metrics,
+            clock,
         }
     }
 
@@ -99,7 +105,7 @@ impl VotingSystem {
         self.votes.write().await.clear();
 
         // Create new round
-        let now = Self::current_timestamp();
+        let now = self.clock.unix_secs();
         let round = VotingRound {
             block_hash: block.hash.clone(),
             started_at: now,
@@ -132,7 +138,7 @@ impl VotingSystem {
         }
 
         // Check if voting window is still open
-        let now = Self::current_timestamp();
+        let now = self.clock.unix_secs();
         if now > round.ends_at {
             return Err(ConsensusError::VotingError(
                 "Voting window has closed".to_string(),
@@ -140,9 +146,16 @@ impl VotingSystem {
         }
         drop(round);
 
-        // Create and store vote
-        let vote = Vote::new(block_hash.to_string(), validator_id.to_string(), score);
-        self.votes.write().await.insert(validator_id.to_string(), vote);
+        // Create and store vote; a validator votes once per round
+        let mut votes = self.votes.write().await;
+        if votes.contains_key(validator_id) {
+            return Err(ConsensusError::VotingError(format!(
+                "Duplicate vote from validator: {}",
+                validator_id
+            )));
+        }
+        let vote = Vote::new_at(block_hash.to_string(), validator_id.to_string(), score, now);
+        votes.insert(validator_id.to_string(), vote);
         self.metrics.increment_votes_cast();
 
         Ok(())
@@ -183,7 +196,12 @@ impl VotingSystem {
 
         self.metrics.observe_voting_participation_rate(vote_count as f64 / 10.0); // Assuming 10 validators
 
-        Ok(VotingResult::new(round.block_hash, vote_map, approved))
+        Ok(VotingResult::new_at(
+            round.block_hash,
+            vote_map,
+            approved,
+            self.clock.unix_secs(),
+        ))
     }
 
     /// Get the current voting round status
@@ -195,19 +213,14 @@ impl VotingSystem {
     pub async fn has_voted(&self, validator_id: &str) -> bool {
         self.votes.read().await.contains_key(validator_id)
     }
-
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::blockchain::{Block, BlockHeader, BlockMeta};
+    use crate::clock::MockClock;
+    use std::time::Duration;
 
     fn create_test_block() -> Block {
         Block {
@@ -247,8 +260,27 @@ mod tests {
         voting.start_voting_round(&block).await.unwrap();
 
         voting.cast_vote("validator-001", "test_block_hash", 75).await.unwrap();
+        assert!(voting.cast_vote("validator-001", "test_block_hash", 90).await.is_err());
 
         assert!(voting.has_voted("validator-001").await);
         assert!(!voting.has_voted("validator-002").await);
     }
+
+    #[tokio::test]
+    async fn test_voting_window_closes_on_clock() {
+        let registry = prometheus::Registry::new();
+        let metrics = Arc::new(ConsensusMetrics::new(&registry).unwrap());
+        let clock = MockClock::from_unix_secs(1704067200);
+        let voting = VotingSystem::with_clock(VotingConfig::default(), metrics, clock.shared());
+
+        voting.start_voting_round(&create_test_block()).await.unwrap();
+        assert_eq!(voting.get_current_round().await.unwrap().ends_at, 1704067260);
+
+        clock.advance(Duration::from_secs(60));
+        voting.cast_vote("validator-001", "test_block_hash", 75).await.unwrap();
+        assert!(voting.has_voted("validator-001").await);
+
+        clock.advance(Duration::from_secs(1));
+        assert!(voting.cast_vote("validator-002", "test_block_hash", 75).await.is_err());
+    }
 }
@@ -0,0 +1,190 @@
+//! Ed25519 Signatures
+//!
+//! Ed25519 key pairs used for PoAI v1 consensus messages (transactions,
+//! proposals and votes), built on `ed25519-dalek` v2.
+//!
+//! Verification uses `verify_strict`, which rejects small-order public keys
+//! and non-canonical signatures so that each message has exactly one valid
+//! signature encoding per key.
+
+use ed25519_dalek::{Signature, SigningKey, VerifyingKey, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH};
+use rand_0_8::rngs::OsRng;
+
+use crate::crypto::common::traits::{KeyPair, Signer, Verifier};
+use crate::crypto::{CryptoAlgorithm, CryptoError, CryptoResult};
+
+/// Ed25519 key pair (or verify-only public key)
+#[derive(Debug, Clone)]
+pub struct Ed25519Keys {
+    public_key: [u8; PUBLIC_KEY_LENGTH],
+    verifying_key: VerifyingKey,
+    signing_key: Option<SigningKey>,
+}
+
+impl Ed25519Keys {
+    /// Create a verify-only key from a 32-byte public key
+    pub fn from_public_key(public_key: &[u8]) -> CryptoResult<Self> {
+        let bytes: [u8; PUBLIC_KEY_LENGTH] = public_key
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeyFormat("Ed25519 public key must be 32 bytes".into()))?;
+        let verifying_key = VerifyingKey::from_bytes(&bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(e.to_string()))?;
+
+        Ok(Self {
+            public_key: bytes,
+            verifying_key,
+            signing_key: None,
+        })
+    }
+
+    /// Get the public key as a fixed-size array
+    pub fn public_key_bytes(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        self.public_key
+    }
+
+    fn from_signing_key(signing_key: SigningKey) -> Self {
+        let verifying_key = signing_key.verifying_key();
+        Self {
+            public_key: verifying_key.to_bytes(),
+            verifying_key,
+            signing_key: Some(signing_key),
+        }
+    }
+}
+
+impl KeyPair for Ed25519Keys {
+    fn new() -> CryptoResult<Self> {
+        Ok(Self::from_signing_key(SigningKey::generate(&mut OsRng)))
+    }
+
+    fn from_private_key(private_key: &[u8]) -> CryptoResult<Self> {
+        let bytes: [u8; SECRET_KEY_LENGTH] = private_key
+            .try_into()
+            .map_err(|_| CryptoError::KeyGenerationError("Invalid key length".to_string()))?;
+        Ok(Self::from_signing_key(SigningKey::from_bytes(&bytes)))
+    }
+
+    fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    fn private_key(&self) -> Option<&[u8]> {
+        self.signing_key.as_ref().map(|sk| sk.as_bytes().as_slice())
+    }
+
+    fn to_bytes(&self) -> CryptoResult<Vec<u8>> {
+        // Format: [algorithm][pubkey: 32][has_private: 1][private: 32]
+        let mut result = Vec::with_capacity(2 + PUBLIC_KEY_LENGTH + SECRET_KEY_LENGTH);
+        result.push(CryptoAlgorithm::Ed25519 as u8);
+        result.extend_from_slice(&self.public_key);
+
+        match &self.signing_key {
+            Some(sk) => {
+                result.push(1);
+                result.extend_from_slice(sk.as_bytes());
+            }
+            None => result.push(0),
+        }
+
+        Ok(result)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> CryptoResult<Self> {
+        if bytes.len() < 2 + PUBLIC_KEY_LENGTH {
+            return Err(CryptoError::SerializationError(
+                "Invalid Ed25519 key format: too short".into()
+            ));
+        }
+
+        if bytes[0] != CryptoAlgorithm::Ed25519 as u8 {
+            return Err(CryptoError::InvalidAlgorithm(
+                format!("Expected Ed25519 algorithm, got {}", bytes[0])
+            ));
+        }
+
+        let public_key = &bytes[1..1 + PUBLIC_KEY_LENGTH];
+        let rest = &bytes[1 + PUBLIC_KEY_LENGTH..];
+
+        match rest {
+            [0] => Self::from_public_key(public_key),
+            [1, private_key @ ..] => {
+                let keys = Self::from_private_key(private_key)?;
+                if keys.public_key != public_key {
+                    return Err(CryptoError::InvalidKeyFormat(
+                        "Ed25519 public key does not match private key".into()
+                    ));
+                }
+                Ok(keys)
+            }
+            _ => Err(CryptoError::SerializationError("Invalid private key flag".into())),
+        }
+    }
+
+    fn algorithm_id(&self) -> u8 {
+        CryptoAlgorithm::Ed25519 as u8
+    }
+}
+
+impl Signer for Ed25519Keys {
+    fn sign(&self, message: &[u8]) -> CryptoResult<Vec<u8>> {
+        use ed25519_dalek::Signer as _;
+
+        let signing_key = self.signing_key.as_ref().ok_or_else(|| {
+            CryptoError::SigningError("Cannot sign without private key".into())
+        })?;
+
+        Ok(signing_key.sign(message).to_bytes().to_vec())
+    }
+
+    fn algorithm_id(&self) -> u8 {
+        CryptoAlgorithm::Ed25519 as u8
+    }
+}
+
+impl Verifier for Ed25519Keys {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> CryptoResult<bool> {
+        let signature = Signature::from_slice(signature)
+            .map_err(|e| CryptoError::InvalidSignatureFormat(e.to_string()))?;
+
+        Ok(self.verifying_key.verify_strict(message, &signature).is_ok())
+    }
+
+    fn algorithm_id(&self) -> u8 {
+        CryptoAlgorithm::Ed25519 as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keys = Ed25519Keys::new().unwrap();
+        let signature = keys.sign(b"message").unwrap();
+
+        assert_eq!(signature.len(), 64);
+        assert!(keys.verify(b"message", &signature).unwrap());
+        assert!(!keys.verify(b"other message", &signature).unwrap());
+    }
+
+    #[test]
+    fn test_verify_only_key() {
+        let keys = Ed25519Keys::new().unwrap();
+        let signature = keys.sign(b"message").unwrap();
+
+        let public = Ed25519Keys::from_public_key(keys.public_key()).unwrap();
+        assert!(!public.has_private_key());
+        assert!(public.verify(b"message", &signature).unwrap());
+        assert!(public.sign(b"message").is_err());
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let keys = Ed25519Keys::new().unwrap();
+        let restored = Ed25519Keys::from_bytes(&keys.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(restored.public_key(), keys.public_key());
+        assert_eq!(restored.private_key(), keys.private_key());
+    }
+}
@@ -3,5 +3,6 @@
 /// This module provides traditional cryptographic algorithms that are used
 /// for backward compatibility during the transition to post-quantum security.
 pub mod ecdsa;
+pub mod ed25519;
 pub mod hash;
 pub mod x25519;
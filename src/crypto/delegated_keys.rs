@@ -22,14 +22,23 @@
 //! The validator key is cryptographically derived from the master key but
 //! can only perform specific operations. Even if the validator key is
 //! compromised, user funds remain safe.
+use crate::clock::{system_clock, SharedClock};
 use crate::crypto::{CryptoError, CryptoResult, PrivateKey, PublicKey, Signature};
 use crate::crypto::classic::ecdsa::ECDSAKeys;
 use crate::crypto::common::traits::{KeyPair, Signer};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Sha3_256};
-use std::time::{SystemTime, UNIX_EPOCH};
 use zeroize::Zeroize;
 
+/// Derive a wallet address from public key bytes
+///
+/// The address is `0x` followed by the hex of the last 20 bytes of
+/// SHA3-256(public_key).
+pub fn derive_address(public_key: &[u8]) -> String {
+    let hash = Sha3_256::digest(public_key);
+    format!("0x{}", hex::encode(&hash[hash.len() - 20..]))
+}
+
 /// Operation types that can be performed with keys
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KeyOperation {
@@ -64,14 +73,23 @@ pub struct MasterKey {
     
     /// Creation timestamp
     created_at: u64,
+    
+    /// Time source for timestamps, inherited by derived validator keys
+    #[zeroize(skip)]
+    clock: SharedClock,
 }
 
 impl MasterKey {
     /// Generate a new master key
     pub fn generate() -> CryptoResult<Self> {
+        Self::generate_with_clock(system_clock())
+    }
+    
+    /// Generate a new master key that reads time from `clock`
+    pub fn generate_with_clock(clock: SharedClock) -> CryptoResult<Self> {
         let ecdsa_keys = ECDSAKeys::new()?;
         
-        let address = Self::derive_address(ecdsa_keys.public_key());
+        let address = derive_address(ecdsa_keys.public_key());
         
         Ok(Self {
             private_key: ecdsa_keys.private_key()
@@ -79,22 +97,32 @@ impl MasterKey {
                 .to_vec(),
             public_key: ecdsa_keys.public_key().to_vec(),
             address,
-            created_at: Self::current_timestamp(),
+            created_at: clock.unix_secs(),
+            clock,
         })
     }
     
     /// Import master key from private key bytes
     pub fn from_private_key(private_key: PrivateKey) -> CryptoResult<Self> {
+        Self::from_private_key_with_clock(private_key, system_clock())
+    }
+    
+    /// Import master key from private key bytes, reading time from `clock`
+    pub fn from_private_key_with_clock(
+        private_key: PrivateKey,
+        clock: SharedClock,
+    ) -> CryptoResult<Self> {
         let ecdsa_keys = ECDSAKeys::from_private_key(&private_key)
             .map_err(|e| CryptoError::InvalidKeyFormat(e.to_string()))?;
         
-        let address = Self::derive_address(ecdsa_keys.public_key());
+        let address = derive_address(ecdsa_keys.public_key());
         
         Ok(Self {
             private_key,
             public_key: ecdsa_keys.public_key().to_vec(),
             address,
-            created_at: Self::current_timestamp(),
+            created_at: clock.unix_secs(),
+            clock,
         })
     }
     
@@ -129,14 +157,15 @@ impl MasterKey {
             public_key: ecdsa_keys.public_key().to_vec(),
             master_address: self.address.clone(),
             nonce: nonce.to_vec(),
-            created_at: Self::current_timestamp(),
+            created_at: self.clock.unix_secs(),
             revoked: false,
+            clock: self.clock.clone(),
         })
     }
     
     /// Sign a revocation message for a validator key
     pub fn create_revocation(&self, validator_public_key: &[u8]) -> CryptoResult<Revocation> {
-        let timestamp = Self::current_timestamp();
+        let timestamp = self.clock.unix_secs();
         
         // Create revocation message
         let mut message = Vec::new();
@@ -175,24 +204,6 @@ impl MasterKey {
     pub fn export_private_key(&self) -> PrivateKey {
         self.private_key.clone()
     }
-    
-    /// Derive wallet address from public key
-    fn derive_address(public_key: &[u8]) -> String {
-        let mut hasher = Sha3_256::new();
-        hasher.update(public_key);
-        let hash = hasher.finalize();
-        
-        // Take last 20 bytes and encode as hex
-        let address_bytes = &hash[hash.len() - 20..];
-        format!("0x{}", hex::encode(address_bytes))
-    }
-    
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }
 }
 
 /// Validator key with scope-limited permissions
@@ -218,9 +229,20 @@ pub struct ValidatorKey {
     /// Whether this key has been revoked
     #[serde(default)]
     revoked: bool,
+    
+    /// Time source for vote timestamps
+    #[serde(skip, default = "system_clock")]
+    #[zeroize(skip)]
+    clock: SharedClock,
 }
 
 impl ValidatorKey {
+    /// Replace the time source, e.g. after deserializing a stored key
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+    
     /// Check if this key can perform an operation
     pub fn can_perform(&self, operation: KeyOperation) -> bool {
         if self.revoked {
@@ -251,7 +273,7 @@ impl ValidatorKey {
         message.extend_from_slice(b"VOTE");
         message.extend_from_slice(block_hash);
         message.push(if vote { 1 } else { 0 });
-        message.extend_from_slice(&Self::current_timestamp().to_le_bytes());
+        message.extend_from_slice(&self.clock.unix_secs().to_le_bytes());
         
         self.sign(&message)
     }
@@ -311,13 +333,6 @@ impl ValidatorKey {
         let ecdsa_keys = ECDSAKeys::from_private_key(&self.private_key)?;
         ecdsa_keys.sign(data)
     }
-    
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }
 }
 
 /// Revocation certificate for a validator key
@@ -355,20 +370,27 @@ impl Revocation {
 pub struct KeyManager {
     master_key: Option<MasterKey>,
     validator_keys: Vec<ValidatorKey>,
+    clock: SharedClock,
 }
 
 impl KeyManager {
     /// Create new key manager
     pub fn new() -> Self {
+        Self::with_clock(system_clock())
+    }
+    
+    /// Create new key manager whose keys read time from `clock`
+    pub fn with_clock(clock: SharedClock) -> Self {
         Self {
             master_key: None,
             validator_keys: Vec::new(),
+            clock,
         }
     }
     
     /// Generate and store a new master key
     pub fn generate_master_key(&mut self) -> CryptoResult<String> {
-        let master_key = MasterKey::generate()?;
+        let master_key = MasterKey::generate_with_clock(self.clock.clone())?;
         let address = master_key.address().to_string();
         self.master_key = Some(master_key);
         Ok(address)
@@ -376,7 +398,7 @@ impl KeyManager {
     
     /// Import existing master key
     pub fn import_master_key(&mut self, private_key: PrivateKey) -> CryptoResult<String> {
-        let master_key = MasterKey::from_private_key(private_key)?;
+        let master_key = MasterKey::from_private_key_with_clock(private_key, self.clock.clone())?;
         let address = master_key.address().to_string();
         self.master_key = Some(master_key);
         Ok(address)
@@ -426,6 +448,8 @@ impl Default for KeyManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
+    use std::time::Duration;
     
     #[test]
     fn test_master_key_generation() {
@@ -589,5 +613,26 @@ mod tests {
         // Should have same address
         assert_eq!(address1, address2);
     }
+    
+    #[test]
+    fn test_vote_signatures_follow_clock() {
+        let clock = MockClock::from_unix_secs(1704067200);
+        let master = MasterKey::generate_with_clock(clock.shared()).unwrap();
+        let validator = master.derive_validator_key(b"nonce").unwrap();
+        
+        // Re-importing at the same time derives the same validator key
+        let imported = MasterKey::from_private_key_with_clock(
+            master.export_private_key(),
+            clock.shared(),
+        )
+        .unwrap();
+        let again = imported.derive_validator_key(b"nonce").unwrap();
+        assert_eq!(validator.public_key(), again.public_key());
+        
+        // The vote timestamp is part of the signed message
+        let first = validator.sign_vote(b"block", true).unwrap();
+        assert_eq!(first, validator.sign_vote(b"block", true).unwrap());
+        clock.advance(Duration::from_secs(1));
+        assert_ne!(first, validator.sign_vote(b"block", true).unwrap());
+    }
 }
-
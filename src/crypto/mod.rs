@@ -1,7 +1,7 @@
 /// SELF Chain Cryptography Module
 ///
 /// This module provides cryptographic primitives for SELF Chain, including:
-/// - Classic cryptography (ECDSA, SHA3-256)
+/// - Classic cryptography (ECDSA, Ed25519, SHA3-256)
 /// - Post-quantum cryptography (Kyber, SPHINCS+)
 /// - Hybrid cryptographic schemes combining both
 ///
@@ -15,11 +15,12 @@ pub mod delegated_keys;
 
 // Re-exports for convenient usage
 pub use classic::ecdsa::{ECDSAKeys, ECDSASignature};
+pub use classic::ed25519::Ed25519Keys;
 pub use quantum::kyber::KyberKeys;
 pub use quantum::sphincs::SphincsKeys;
 pub use hybrid::{HybridKeys, HybridSignature};
 pub use common::traits::{KeyPair, Signer, Verifier};
-pub use delegated_keys::{MasterKey, ValidatorKey, KeyManager, KeyOperation, Revocation};
+pub use delegated_keys::{derive_address, MasterKey, ValidatorKey, KeyManager, KeyOperation, Revocation};
 
 // Types used throughout the module
 pub type PrivateKey = Vec<u8>;
@@ -37,6 +38,9 @@ pub enum CryptoAlgorithm {
     /// X25519 key exchange
     X25519 = 20,
     
+    /// Ed25519 signatures (PoAI v1 consensus messages)
+    Ed25519 = 21,
+    
     /// Kyber-768 post-quantum key encapsulation
     Kyber768 = 2,
     
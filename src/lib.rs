@@ -22,7 +22,10 @@
 //! - **Consensus**: PoAI validation, voting, and block selection
 //! - **Crypto**: Hybrid cryptography (classic + post-quantum ready)
 //! - **Blockchain**: Block and transaction types
+//! - **Clock**: Injectable wall-clock time (system or mock)
+//! - **State**: Account state committed by a Sparse Merkle Tree
 //! - **Node**: Three node types (Validator, Builder, Coordinator)
+//! - **Network**: libp2p gossip for consensus messages (`full-node` feature)
 //!
 //! ## Quick Start
 //!
@@ -50,9 +53,13 @@
 //! All Constellations share the same PoAI consensus core.
 
 pub mod blockchain;
+pub mod clock;
 pub mod consensus;
 pub mod crypto;
+#[cfg(feature = "full-node")]
+pub mod network;
 pub mod node;
+pub mod state;
 
 // Re-export commonly used types
 pub use blockchain::{Block, BlockHeader, BlockMeta, Transaction, TransactionData};
@@ -0,0 +1,81 @@
+//! Chain Network Behaviour
+//!
+//! Combines the libp2p protocols a full node runs on every connection:
+//!
+//! - **gossipsub**: signed, strictly validated pub/sub for the constellation
+//!   topics; messages are identified by the SHA256 of their payload, so the
+//!   same message relayed by several peers is delivered once
+//! - **identify**: exchanges protocol versions and listen addresses
+//! - **ping**: keeps connections alive and detects dead peers
+//! - **mdns**: discovers peers on the local network, when enabled
+//! - **sync**: request-response block sync for nodes catching up, see
+//!   `super::sync`
+
+use super::service::{NetworkConfig, NetworkError};
+use super::sync::{SyncCodec, SYNC_PROTOCOL};
+use libp2p::gossipsub::{self, MessageAuthenticity, MessageId, ValidationMode};
+use libp2p::identity::Keypair;
+use libp2p::swarm::behaviour::toggle::Toggle;
+use libp2p::swarm::NetworkBehaviour;
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::{identify, mdns, ping};
+use sha2::{Digest, Sha256};
+
+/// Identify protocol version spoken by SELF Chain nodes
+pub const PROTOCOL_VERSION: &str = "/self-chain/1.0.0";
+
+/// libp2p behaviour of a SELF Chain node
+#[derive(NetworkBehaviour)]
+pub struct ChainBehaviour {
+    pub gossipsub: gossipsub::Behaviour,
+    pub identify: identify::Behaviour,
+    pub ping: ping::Behaviour,
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
+    pub sync: request_response::Behaviour<SyncCodec>,
+}
+
+impl ChainBehaviour {
+    /// Build the behaviour for the node identified by `keypair`
+    pub fn new(keypair: &Keypair, config: &NetworkConfig) -> Result<Self, NetworkError> {
+        let gossipsub_config = gossipsub::ConfigBuilder::default()
+            .heartbeat_interval(config.heartbeat_interval)
+            .validation_mode(ValidationMode::Strict)
+            .validate_messages()
+            .max_transmit_size(config.max_message_size)
+            .message_id_fn(|message: &gossipsub::Message| {
+                MessageId::from(Sha256::digest(&message.data).to_vec())
+            })
+            .build()
+            .map_err(|e| NetworkError::Behaviour(e.to_string()))?;
+        let authenticity = MessageAuthenticity::Signed(keypair.clone());
+        let gossipsub = gossipsub::Behaviour::new(authenticity, gossipsub_config)
+            .map_err(|e| NetworkError::Behaviour(e.to_string()))?;
+
+        let identify = identify::Behaviour::new(identify::Config::new(
+            PROTOCOL_VERSION.to_string(),
+            keypair.public(),
+        ));
+
+        let mdns = if config.enable_mdns {
+            let local_peer_id = keypair.public().to_peer_id();
+            let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)
+                .map_err(|e| NetworkError::Behaviour(e.to_string()))?;
+            Some(mdns)
+        } else {
+            None
+        };
+
+        let sync = request_response::Behaviour::new(
+            [(SYNC_PROTOCOL, ProtocolSupport::Full)],
+            request_response::Config::default().with_request_timeout(config.sync_request_timeout),
+        );
+
+        Ok(Self {
+            gossipsub,
+            identify,
+            ping: ping::Behaviour::default(),
+            mdns: mdns.into(),
+            sync,
+        })
+    }
+}
@@ -0,0 +1,410 @@
+//! Peer Discovery and Address Book
+//!
+//! A node finds peers three ways:
+//!
+//! - **Bootstrap**: `NetworkConfig::bootstrap_peers` are dialed on start and
+//!   redialed while disconnected
+//! - **mDNS**: with `NetworkConfig::enable_mdns`, peers announcing themselves
+//!   on the local network are dialed as they appear (LAN devnets)
+//! - **Address book**: peers we dialed that completed the identify handshake
+//!   with our `PROTOCOL_VERSION` are remembered with their listen addresses,
+//!   and the best of them are dialed on start, so a restarted node
+//!   reconnects even without its bootstrap list; they are redialed until the
+//!   book forgets them
+//!
+//! Peers that dialed us are never recorded, so a remote node cannot fill the
+//! book by connecting under fresh peer IDs. Outside local devnets (see
+//! `NetworkConfig::allow_private_addrs`) only addresses passing
+//! `is_routable` are kept.
+//!
+//! ## Scoring
+//!
+//! Each record keeps the Unix time the peer was first and last seen and the
+//! number of consecutive failed dials. A successful handshake resets the
+//! failures; a peer is forgotten after `MAX_DIAL_FAILURES` failures in a row.
+//! Good peers are those with the fewest failures, most recently seen first.
+//!
+//! When the book is full, the peer with the most failures is evicted, the
+//! most recently added one on ties, so long-lived peers outlast newcomers.
+//!
+//! ## Persistence
+//!
+//! `NetworkConfig::address_book_path` enables persistence. Changes are saved
+//! on every redial tick and when the service stops, by writing a temporary
+//! file and renaming it over the old one.
+//!
+//! ## Canonical Encoding Order
+//!
+//! The file is a sequence of records ordered by peer ID. Each record is:
+//!
+//! 1. `peer_id` (length-prefixed multihash bytes)
+//! 2. `addrs` (sequence of length-prefixed multiaddr bytes)
+//! 3. `first_seen` (u64, little-endian)
+//! 4. `last_seen` (u64, little-endian)
+//! 5. `failures` (u64, little-endian)
+
+use super::service::NetworkError;
+use crate::blockchain::v1::codec::{Canonical, CodecError, CodecResult, Decoder, Encoder};
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Consecutive failed dials after which a peer is forgotten
+pub const MAX_DIAL_FAILURES: u64 = 5;
+
+/// Addresses remembered per peer
+pub const MAX_ADDRS_PER_PEER: usize = 8;
+
+/// Peers remembered in the address book
+pub const MAX_KNOWN_PEERS: usize = 1024;
+
+/// What the address book knows about one peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerRecord {
+    pub peer_id: PeerId,
+
+    /// Listen addresses, most recently learned first
+    pub addrs: Vec<Multiaddr>,
+
+    /// Unix time the peer was first recorded
+    pub first_seen: u64,
+
+    /// Unix time of the last successful handshake
+    pub last_seen: u64,
+
+    /// Consecutive failed dials
+    pub failures: u64,
+}
+
+impl Canonical for PeerRecord {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_bytes(&self.peer_id.to_bytes());
+        enc.put_u64(self.addrs.len() as u64);
+        for addr in &self.addrs {
+            enc.put_bytes(&addr.to_vec());
+        }
+        enc.put_u64(self.first_seen);
+        enc.put_u64(self.last_seen);
+        enc.put_u64(self.failures);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        let peer_id = PeerId::from_bytes(&dec.get_bytes()?)
+            .map_err(|e| CodecError::NonCanonical(format!("invalid peer ID: {}", e)))?;
+        let count = dec.get_u64()?;
+        // Every address takes at least its 8-byte length prefix
+        if count > (dec.remaining() / 8) as u64 {
+            return Err(CodecError::LengthOverflow(count));
+        }
+        let addrs = (0..count)
+            .map(|_| {
+                Multiaddr::try_from(dec.get_bytes()?)
+                    .map_err(|e| CodecError::NonCanonical(format!("invalid multiaddr: {}", e)))
+            })
+            .collect::<CodecResult<_>>()?;
+        Ok(Self {
+            peer_id,
+            addrs,
+            first_seen: dec.get_u64()?,
+            last_seen: dec.get_u64()?,
+            failures: dec.get_u64()?,
+        })
+    }
+}
+
+/// Known good peers, optionally persisted to disk
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    path: Option<PathBuf>,
+    peers: BTreeMap<PeerId, PeerRecord>,
+    dirty: bool,
+}
+
+impl AddressBook {
+    /// Create an empty, in-memory address book
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the address book at `path`, or start an empty one there
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, NetworkError> {
+        let path = path.into();
+        let peers = match fs::read(&path) {
+            Ok(bytes) => decode_records(&bytes)
+                .map_err(|e| storage_error(&path, e))?
+                .into_iter()
+                .map(|record| (record.peer_id, record))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(storage_error(&path, e)),
+        };
+        Ok(Self {
+            path: Some(path),
+            peers,
+            dirty: false,
+        })
+    }
+
+    /// Record for `peer`, if known
+    pub fn get(&self, peer: &PeerId) -> Option<&PeerRecord> {
+        self.peers.get(peer)
+    }
+
+    /// Number of known peers
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Whether no peer is known
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Record a successful handshake with a peer we dialed, listening on
+    /// `addrs`
+    pub fn record_seen(&mut self, peer: PeerId, addrs: Vec<Multiaddr>, now: u64) {
+        if !self.peers.contains_key(&peer) && self.peers.len() >= MAX_KNOWN_PEERS {
+            self.evict_worst();
+        }
+        let record = self.peers.entry(peer).or_insert_with(|| PeerRecord {
+            peer_id: peer,
+            addrs: Vec::new(),
+            first_seen: now,
+            last_seen: now,
+            failures: 0,
+        });
+        for addr in addrs.into_iter().rev() {
+            record.addrs.retain(|a| *a != addr);
+            record.addrs.insert(0, addr);
+        }
+        record.addrs.truncate(MAX_ADDRS_PER_PEER);
+        record.last_seen = now;
+        record.failures = 0;
+        self.dirty = true;
+    }
+
+    /// Record a failed dial of `peer`, forgetting it after
+    /// `MAX_DIAL_FAILURES` in a row
+    pub fn record_failure(&mut self, peer: &PeerId) {
+        if let Some(record) = self.peers.get_mut(peer) {
+            record.failures += 1;
+            if record.failures >= MAX_DIAL_FAILURES {
+                self.peers.remove(peer);
+            }
+            self.dirty = true;
+        }
+    }
+
+    /// Up to `limit` peers worth dialing, best first
+    pub fn good_peers(&self, limit: usize) -> Vec<&PeerRecord> {
+        let mut peers: Vec<&PeerRecord> =
+            self.peers.values().filter(|r| !r.addrs.is_empty()).collect();
+        peers.sort_by(|a, b| {
+            a.failures
+                .cmp(&b.failures)
+                .then(b.last_seen.cmp(&a.last_seen))
+                .then(a.peer_id.cmp(&b.peer_id))
+        });
+        peers.truncate(limit);
+        peers
+    }
+
+    /// Write unsaved changes to disk, if the book is persistent
+    pub fn save(&mut self) -> Result<(), NetworkError> {
+        let Some(path) = self.path.as_ref().filter(|_| self.dirty) else {
+            return Ok(());
+        };
+        let records: Vec<PeerRecord> = self.peers.values().cloned().collect();
+        let mut enc = Encoder::new();
+        enc.put_seq(&records);
+        let bytes = enc.into_bytes();
+        let tmp = path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut file = File::create(&tmp)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+            fs::rename(&tmp, path)
+        };
+        write().map_err(|e| storage_error(path, e))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Evict the peer with the most failures, the newest one on ties
+    fn evict_worst(&mut self) {
+        let worst = self
+            .peers
+            .values()
+            .max_by(|a, b| {
+                a.failures
+                    .cmp(&b.failures)
+                    .then(a.first_seen.cmp(&b.first_seen))
+                    .then(a.peer_id.cmp(&b.peer_id))
+            })
+            .map(|r| r.peer_id);
+        if let Some(peer) = worst {
+            self.peers.remove(&peer);
+        }
+    }
+}
+
+/// Whether `addr` can be reached from the public internet
+///
+/// Loopback, private, link-local, shared, documentation and unspecified IP
+/// addresses are not; DNS names are assumed to be.
+pub fn is_routable(addr: &Multiaddr) -> bool {
+    match addr.iter().next() {
+        Some(Protocol::Ip4(ip)) => {
+            let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64;
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || shared)
+        }
+        Some(Protocol::Ip6(ip)) => {
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local())
+        }
+        Some(Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_) | Protocol::Dnsaddr(_)) => {
+            true
+        }
+        _ => false,
+    }
+}
+
+fn decode_records(bytes: &[u8]) -> CodecResult<Vec<PeerRecord>> {
+    let mut dec = Decoder::new(bytes);
+    let records = dec.get_seq()?;
+    dec.finish()?;
+    Ok(records)
+}
+
+fn storage_error(path: &Path, e: impl std::fmt::Display) -> NetworkError {
+    NetworkError::Storage(format!("address book {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    fn book_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "self-chain-address-book-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn peer() -> PeerId {
+        Keypair::generate_ed25519().public().to_peer_id()
+    }
+
+    fn addr(port: u16) -> Multiaddr {
+        format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap()
+    }
+
+    #[test]
+    fn test_address_book_persists_across_restarts() {
+        let path = book_path("persist");
+        let (a, b) = (peer(), peer());
+        let mut book = AddressBook::open(&path).unwrap();
+        book.record_seen(a, vec![addr(1), addr(2)], 100);
+        book.record_seen(b, vec![addr(3)], 200);
+        book.record_seen(a, vec![addr(2)], 150);
+        book.record_failure(&b);
+        book.save().unwrap();
+
+        let book = AddressBook::open(&path).unwrap();
+        assert_eq!(book.len(), 2);
+        let record = book.get(&a).unwrap();
+        assert_eq!(record.addrs, vec![addr(2), addr(1)]);
+        assert_eq!(record.last_seen, 150);
+        assert_eq!(book.get(&b).unwrap().failures, 1);
+
+        // Fewest failures first, then most recently seen
+        let good: Vec<PeerId> = book.good_peers(10).iter().map(|r| r.peer_id).collect();
+        assert_eq!(good, vec![a, b]);
+
+        fs::write(&path, b"garbage").unwrap();
+        assert!(matches!(AddressBook::open(&path), Err(NetworkError::Storage(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failing_peers_are_forgotten() {
+        let mut book = AddressBook::new();
+        let (flaky, dead) = (peer(), peer());
+        book.record_seen(flaky, vec![addr(1)], 100);
+        book.record_seen(dead, vec![addr(2)], 100);
+
+        for _ in 0..MAX_DIAL_FAILURES - 1 {
+            book.record_failure(&flaky);
+            book.record_failure(&dead);
+        }
+        // A successful handshake clears the failures
+        book.record_seen(flaky, vec![], 110);
+        book.record_failure(&flaky);
+        book.record_failure(&dead);
+
+        assert_eq!(book.get(&flaky).unwrap().failures, 1);
+        assert_eq!(book.get(&flaky).unwrap().addrs, vec![addr(1)]);
+        assert!(book.get(&dead).is_none());
+        // Nothing to save without a path
+        book.save().unwrap();
+    }
+
+    #[test]
+    fn test_newcomers_cannot_flush_long_lived_peers() {
+        let mut book = AddressBook::new();
+        let veterans: Vec<PeerId> = (0..MAX_KNOWN_PEERS).map(|_| peer()).collect();
+        for (i, veteran) in veterans.iter().enumerate() {
+            book.record_seen(*veteran, vec![addr(1)], i as u64);
+        }
+
+        // A stream of fresh peer IDs only ever replaces the newest entry
+        for t in 0..100 {
+            book.record_seen(peer(), vec![addr(2)], 10_000 + t);
+        }
+        assert_eq!(book.len(), MAX_KNOWN_PEERS);
+        let kept = veterans.iter().filter(|v| book.get(v).is_some()).count();
+        assert_eq!(kept, MAX_KNOWN_PEERS - 1);
+
+        // Failing peers still go first, however long they are known
+        book.record_failure(&veterans[0]);
+        let newcomer = peer();
+        book.record_seen(newcomer, vec![addr(3)], 20_000);
+        assert!(book.get(&veterans[0]).is_none());
+        assert!(book.get(&newcomer).is_some());
+    }
+
+    #[test]
+    fn test_routable_addresses() {
+        let routable = |addr: &str| is_routable(&addr.parse().unwrap());
+        assert!(routable("/ip4/8.8.8.8/tcp/30333"));
+        assert!(routable("/ip6/2001:4860::8888/tcp/30333"));
+        assert!(routable("/dns/seed.example.org/tcp/30333"));
+        for addr in [
+            "/ip4/127.0.0.1/tcp/30333",
+            "/ip4/10.1.2.3/tcp/30333",
+            "/ip4/192.168.1.5/tcp/30333",
+            "/ip4/100.64.0.1/tcp/30333",
+            "/ip4/0.0.0.0/tcp/30333",
+            "/ip6/::1/tcp/30333",
+            "/ip6/fd00::1/tcp/30333",
+            "/ip6/fe80::1/tcp/30333",
+        ] {
+            assert!(!routable(addr), "{}", addr);
+        }
+    }
+}
@@ -0,0 +1,502 @@
+//! Block Downloader
+//!
+//! Catches a node up to a target height by fetching finalized blocks from
+//! several peers at once over the sync protocol.
+//!
+//! ## Batches
+//!
+//! Heights above the finalized tip are split into batches of `batch_size`.
+//! Each wave hands the lowest pending batches to the usable peers, one batch
+//! per peer, and fetches them in parallel:
+//!
+//! 1. `GetHeaders(from, count)`: the headers must start at `from`, have
+//!    consecutive heights and link by `previous_hash`
+//! 2. `GetBlocks(hashes)`: each block must hash to its header and match the
+//!    header's `transactions_root`
+//! 3. `GetCommitCertificate(height)`, for blocks whose header carries no
+//!    commit signatures
+//!
+//! ## Applying
+//!
+//! Batches are applied in height order through `ChainTipManager::finalize`,
+//! which refuses a block unless it extends the finalized tip and its commit
+//! certificate verifies against the height's committee. Applied blocks are
+//! returned with their certificate attached.
+//!
+//! ## Peer Failures
+//!
+//! A peer that times out (`NetworkConfig::sync_request_timeout`), fails a
+//! request or serves an invalid block is dropped for the rest of the sync,
+//! and its batch goes to another peer.
+//!
+//! A peer that is simply behind is not at fault. A peer that serves only
+//! part of a batch keeps what it served, and the remainder is requeued; a
+//! peer that has none of a batch is not available for it. Either way the
+//! peer's tip is noted, and it is only handed batches below that tip from
+//! then on. Syncing stops early once no peer left can serve the next height.
+
+use super::service::NetworkHandle;
+use super::sync::{SyncRequest, SyncResponse, MAX_BLOCKS_PER_REQUEST};
+use crate::blockchain::v1::{Block, BlockHeader};
+use crate::consensus::v1::{ChainTipManager, CommitCertificate, ConsensusResult, ValidatorInfo};
+use libp2p::futures::future::join_all;
+use libp2p::PeerId;
+use std::collections::{BTreeMap, HashMap};
+
+/// Result of fetching one batch from a peer
+enum Fetch {
+    /// Verified blocks from the batch's first height, at least one
+    Blocks(Vec<Block>),
+    /// The peer has no finalized block at the batch's first height
+    NotAvailable,
+    /// The peer failed a request or served invalid data
+    Failed(String),
+}
+
+/// Outcome of a `BlockSync::sync_to` run
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    /// Finalized blocks in height order, with their certificate attached
+    pub blocks: Vec<Block>,
+
+    /// Peers dropped for failing requests or serving invalid blocks
+    pub dropped_peers: Vec<PeerId>,
+
+    /// First height that could not be synced, if the target was not reached
+    pub stalled_at: Option<u64>,
+}
+
+/// Parallel downloader of finalized blocks
+#[derive(Debug, Clone)]
+pub struct BlockSync {
+    network: NetworkHandle,
+    batch_size: u64,
+}
+
+impl BlockSync {
+    /// Create a downloader using the largest batches peers serve
+    pub fn new(network: NetworkHandle) -> Self {
+        Self {
+            network,
+            batch_size: MAX_BLOCKS_PER_REQUEST as u64,
+        }
+    }
+
+    /// Fetch `batch_size` blocks per request, capped at
+    /// `MAX_BLOCKS_PER_REQUEST`
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size.clamp(1, MAX_BLOCKS_PER_REQUEST as u64);
+        self
+    }
+
+    /// Finalize blocks from `peers` until `chain` reaches `target`
+    ///
+    /// `committee` returns the committee that certifies the block with the
+    /// given header.
+    pub async fn sync_to<F>(
+        &self,
+        chain: &mut ChainTipManager,
+        committee: F,
+        peers: &[PeerId],
+        target: u64,
+    ) -> SyncReport
+    where
+        F: Fn(&BlockHeader) -> ConsensusResult<Vec<ValidatorInfo>>,
+    {
+        let mut report = SyncReport::default();
+        let mut peers = peers.to_vec();
+        // Batches still to fetch, and fetched batches waiting for their turn,
+        // both keyed by first height
+        let mut pending = BTreeMap::new();
+        let mut fetched: BTreeMap<u64, (PeerId, Vec<Block>)> = BTreeMap::new();
+        // First height each peer is known to lack
+        let mut tips: HashMap<PeerId, u64> = HashMap::new();
+        let serves = |tips: &HashMap<PeerId, u64>, peer: &PeerId, height: u64| {
+            tips.get(peer).is_none_or(|&tip| height < tip)
+        };
+
+        let mut from = chain.finalized_height() + 1;
+        while from <= target {
+            let count = self.batch_size.min(target - from + 1);
+            pending.insert(from, count);
+            from += count;
+        }
+
+        loop {
+            let next = chain.finalized_height() + 1;
+            if next > target {
+                break;
+            }
+
+            if let Some((peer, blocks)) = fetched.remove(&next) {
+                let end = next + blocks.len() as u64;
+                for block in blocks {
+                    let height = block.height();
+                    let members = match committee(&block.header) {
+                        Ok(members) => members,
+                        Err(e) => {
+                            tracing::error!("No committee for height {}: {}", height, e);
+                            report.stalled_at = Some(height);
+                            return report;
+                        }
+                    };
+                    let certificate = CommitCertificate::from_block(&block);
+                    if let Err(e) = chain.finalize(&block, certificate, &members) {
+                        tracing::warn!("Block {} from {} refused: {}", height, peer, e);
+                        drop_peer(&mut peers, &mut report, peer);
+                        pending.insert(height, end - height);
+                        break;
+                    }
+                    report.blocks.push(block);
+                }
+                continue;
+            }
+
+            if !peers.iter().any(|peer| serves(&tips, peer, next)) {
+                tracing::warn!("Sync stalled at height {}: no peer has it", next);
+                report.stalled_at = Some(next);
+                break;
+            }
+
+            // Hand each pending batch, lowest first, to a free peer that has it
+            let mut free = peers.clone();
+            let mut wave: Vec<(u64, u64, PeerId)> = Vec::new();
+            for (&from, &count) in &pending {
+                if let Some(i) = free.iter().position(|peer| serves(&tips, peer, from)) {
+                    wave.push((from, count, free.remove(i)));
+                }
+            }
+            for (from, _, _) in &wave {
+                pending.remove(from);
+            }
+            let results = join_all(
+                wave.iter()
+                    .map(|&(from, count, peer)| self.fetch(peer, from, count)),
+            )
+            .await;
+
+            for ((from, count, peer), result) in wave.into_iter().zip(results) {
+                match result {
+                    Fetch::Blocks(blocks) => {
+                        let served = blocks.len() as u64;
+                        if served < count {
+                            tips.insert(peer, from + served);
+                            pending.insert(from + served, count - served);
+                        }
+                        fetched.insert(from, (peer, blocks));
+                    }
+                    Fetch::NotAvailable => {
+                        tracing::debug!("Peer {} has no block at height {}", peer, from);
+                        tips.insert(peer, from);
+                        pending.insert(from, count);
+                    }
+                    Fetch::Failed(reason) => {
+                        tracing::warn!("Sync from {} failed at height {}: {}", peer, from, reason);
+                        drop_peer(&mut peers, &mut report, peer);
+                        pending.insert(from, count);
+                    }
+                }
+            }
+            // Spread the next wave's first batch to another peer
+            if !peers.is_empty() {
+                peers.rotate_left(1);
+            }
+        }
+        report
+    }
+
+    /// Fetch up to `count` blocks from `from` with their certificates
+    async fn fetch(&self, peer: PeerId, from: u64, count: u64) -> Fetch {
+        match self.fetch_blocks(peer, from, count).await {
+            Ok(blocks) if blocks.is_empty() => Fetch::NotAvailable,
+            Ok(blocks) => Fetch::Blocks(blocks),
+            Err(reason) => Fetch::Failed(reason),
+        }
+    }
+
+    /// Verified blocks from `from`, none if the peer has no headers there
+    async fn fetch_blocks(
+        &self,
+        peer: PeerId,
+        from: u64,
+        count: u64,
+    ) -> Result<Vec<Block>, String> {
+        let request = SyncRequest::GetHeaders { from, count };
+        let headers = match self.request(peer, request).await? {
+            SyncResponse::Headers(headers) => headers,
+            _ => return Err("unexpected response to GetHeaders".to_string()),
+        };
+        if headers.is_empty() {
+            return Ok(Vec::new());
+        }
+        if headers.len() as u64 > count {
+            return Err(format!("served {} headers for {} requested", headers.len(), count));
+        }
+        for (i, header) in headers.iter().enumerate() {
+            if header.height != from + i as u64 {
+                return Err(format!("header at height {} is out of order", header.height));
+            }
+            if i > 0 && header.previous_hash != headers[i - 1].hash() {
+                return Err(format!("header {} does not link to its parent", header.height));
+            }
+        }
+
+        let hashes: Vec<[u8; 32]> = headers.iter().map(BlockHeader::hash).collect();
+        let request = SyncRequest::GetBlocks {
+            hashes: hashes.clone(),
+        };
+        let blocks = match self.request(peer, request).await? {
+            SyncResponse::Blocks(blocks) => blocks,
+            _ => return Err("unexpected response to GetBlocks".to_string()),
+        };
+        if blocks.len() != hashes.len() {
+            return Err(format!("served {} of {} blocks", blocks.len(), hashes.len()));
+        }
+
+        let mut verified = Vec::with_capacity(blocks.len());
+        for (mut block, hash) in blocks.into_iter().zip(hashes) {
+            let height = block.height();
+            if block.hash() != hash {
+                return Err(format!("block {} does not match its header", height));
+            }
+            if block.header.transactions_root != block.compute_transactions_root() {
+                return Err(format!("transactions of block {} do not match the root", height));
+            }
+            if block.header.commit_signatures.is_empty() {
+                let request = SyncRequest::GetCommitCertificate { height };
+                match self.request(peer, request).await? {
+                    SyncResponse::CommitCertificate(Some(certificate))
+                        if certifies(&certificate, &block) =>
+                    {
+                        certificate.attach_to(&mut block);
+                    }
+                    SyncResponse::CommitCertificate(_) => {
+                        return Err(format!("no matching certificate for block {}", height));
+                    }
+                    _ => return Err("unexpected response to GetCommitCertificate".to_string()),
+                }
+            }
+            verified.push(block);
+        }
+        Ok(verified)
+    }
+
+    async fn request(&self, peer: PeerId, request: SyncRequest) -> Result<SyncResponse, String> {
+        self.network
+            .sync_request(peer, request)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Whether `certificate` is for `block`; its signatures are checked when
+/// the block is finalized
+fn certifies(certificate: &CommitCertificate, block: &Block) -> bool {
+    certificate.height == block.height()
+        && certificate.round >= block.round()
+        && certificate.block_hash == block.hash()
+        && certificate.efficiency_score == block.header.efficiency_score
+}
+
+fn drop_peer(peers: &mut Vec<PeerId>, report: &mut SyncReport, peer: PeerId) {
+    peers.retain(|p| *p != peer);
+    if !report.dropped_peers.contains(&peer) {
+        report.dropped_peers.push(peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::v1::ConsensusConfig;
+    use crate::consensus::v1::test_support::{certify, chain, committee};
+    use crate::crypto::Ed25519Keys;
+    use crate::network::service::{NetworkConfig, NetworkService};
+    use crate::network::sync::{BlockSource, MemoryBlockStore};
+    use libp2p::identity::Keypair;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    /// Store of `blocks` certified by `signers`, optionally carrying the
+    /// certificate in the block headers
+    fn store(
+        blocks: &[Block],
+        keys: &[Ed25519Keys],
+        signers: &[usize],
+        attached: bool,
+    ) -> Arc<MemoryBlockStore> {
+        let store = MemoryBlockStore::new();
+        for block in blocks {
+            let certificate = certify(block, keys, signers);
+            let mut block = block.clone();
+            if attached {
+                certificate.attach_to(&mut block);
+            }
+            store.insert(block, certificate);
+        }
+        Arc::new(store)
+    }
+
+    /// Block source that answers slower than the sync timeout
+    struct SlowStore(Arc<MemoryBlockStore>);
+
+    impl BlockSource for SlowStore {
+        fn headers(&self, from: u64, count: u64) -> Vec<BlockHeader> {
+            std::thread::sleep(Duration::from_secs(1));
+            self.0.headers(from, count)
+        }
+
+        fn blocks(&self, hashes: &[[u8; 32]]) -> Vec<Block> {
+            self.0.blocks(hashes)
+        }
+
+        fn certificate(&self, height: u64) -> Option<CommitCertificate> {
+            self.0.certificate(height)
+        }
+    }
+
+    async fn node(store: Arc<dyn BlockSource>) -> NetworkHandle {
+        let mut config = NetworkConfig::new("/ip4/127.0.0.1/tcp/0".parse().unwrap(), "devnet");
+        config.sync_request_timeout = Duration::from_millis(300);
+        let (handle, mut events) =
+            NetworkService::start_with_store(config, Keypair::generate_ed25519(), store)
+                .await
+                .unwrap();
+        tokio::spawn(async move { while events.recv().await.is_some() {} });
+        handle
+    }
+
+    /// Connect `from` to every peer and return their IDs
+    async fn connect(from: &NetworkHandle, peers: &[&NetworkHandle]) -> Vec<PeerId> {
+        for peer in peers {
+            from.dial(peer.listen_addrs().await.unwrap().remove(0))
+                .await
+                .unwrap();
+        }
+        let ids: Vec<PeerId> = peers.iter().map(|p| p.local_peer_id()).collect();
+        timeout(Duration::from_secs(10), async {
+            loop {
+                let connected = from.connected_peers().await.unwrap();
+                if ids.iter().all(|id| connected.contains(id)) {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("peers connected");
+        ids
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_catches_up_from_several_peers() {
+        let (infos, keys) = committee(4);
+        let blocks = chain(20);
+        // One peer carries certificates in its headers, the other serves
+        // them through GetCommitCertificate; a third is only at height 5
+        let attached = node(store(&blocks, &keys, &[0, 1, 2], true)).await;
+        let bare = node(store(&blocks, &keys, &[1, 2, 3], false)).await;
+        let behind = node(store(&blocks[..5], &keys, &[0, 1, 2], true)).await;
+        let syncing = node(Arc::new(MemoryBlockStore::new())).await;
+        let peers = connect(&syncing, &[&attached, &bare, &behind]).await;
+
+        let mut tip = ChainTipManager::new(ConsensusConfig::default(), BlockHeader::genesis("c"));
+        let report = BlockSync::new(syncing)
+            .with_batch_size(3)
+            .sync_to(&mut tip, |_| Ok(infos.clone()), &peers, 20)
+            .await;
+
+        assert_eq!(report.stalled_at, None);
+        assert_eq!(tip.finalized_height(), 20);
+        assert_eq!(report.blocks.len(), 20);
+        for (synced, block) in report.blocks.iter().zip(&blocks) {
+            assert_eq!(synced.hash(), block.hash());
+            assert_eq!(synced.header.commit_signatures.len(), 3);
+        }
+        // The peer at height 5 is behind, not faulty
+        assert!(report.dropped_peers.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_drops_slow_and_invalid_peers() {
+        let (infos, keys) = committee(4);
+        let blocks = chain(12);
+        let good = node(store(&blocks, &keys, &[0, 1, 2], false)).await;
+        // Two signatures of four are short of the quorum
+        let forged = node(store(&blocks, &keys, &[0, 1], true)).await;
+        let slow = node(Arc::new(SlowStore(store(&blocks, &keys, &[0, 1, 2], true)))).await;
+        let syncing = node(Arc::new(MemoryBlockStore::new())).await;
+        let peers = connect(&syncing, &[&forged, &slow, &good]).await;
+
+        let mut tip = ChainTipManager::new(ConsensusConfig::default(), BlockHeader::genesis("c"));
+        let sync = BlockSync::new(syncing).with_batch_size(4);
+        let report = sync
+            .sync_to(&mut tip, |_| Ok(infos.clone()), &peers, 12)
+            .await;
+
+        assert_eq!(report.stalled_at, None);
+        assert_eq!(tip.finalized_height(), 12);
+        assert!(report.dropped_peers.contains(&forged.local_peer_id()));
+        assert!(report.dropped_peers.contains(&slow.local_peer_id()));
+        assert!(!report.dropped_peers.contains(&good.local_peer_id()));
+
+        // No peer has height 13, so the sync stops there without blaming
+        // the peer for being behind
+        let report = sync
+            .sync_to(&mut tip, |_| Ok(infos.clone()), &[good.local_peer_id()], 13)
+            .await;
+        assert_eq!(report.stalled_at, Some(13));
+        assert!(report.dropped_peers.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_slow_store_does_not_block_the_swarm() {
+        let (_, keys) = committee(4);
+        let blocks = chain(4);
+        let slow = node(Arc::new(SlowStore(store(&blocks, &keys, &[0, 1, 2], true)))).await;
+        let syncing = node(Arc::new(MemoryBlockStore::new())).await;
+        let peers = connect(&syncing, &[&slow]).await;
+
+        let request = SyncRequest::GetHeaders { from: 1, count: 4 };
+        let pending = tokio::spawn({
+            let syncing = syncing.clone();
+            async move { syncing.sync_request(peers[0], request).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // The slow node keeps handling commands while its store is read
+        let answered = timeout(Duration::from_millis(500), slow.connected_peers()).await;
+        assert_eq!(answered.unwrap().unwrap(), vec![syncing.local_peer_id()]);
+        assert!(pending.await.unwrap().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_busy_store_answers_empty() {
+        let (_, keys) = committee(4);
+        let blocks = chain(4);
+        let mut config = NetworkConfig::new("/ip4/127.0.0.1/tcp/0".parse().unwrap(), "devnet");
+        config.max_concurrent_sync_reads = 1;
+        let store = Arc::new(SlowStore(store(&blocks, &keys, &[0, 1, 2], true)));
+        let (slow, mut events) =
+            NetworkService::start_with_store(config, Keypair::generate_ed25519(), store)
+                .await
+                .unwrap();
+        tokio::spawn(async move { while events.recv().await.is_some() {} });
+        let syncing = node(Arc::new(MemoryBlockStore::new())).await;
+        let peer = connect(&syncing, &[&slow]).await[0];
+
+        let request = SyncRequest::GetHeaders { from: 1, count: 4 };
+        let pending = tokio::spawn({
+            let syncing = syncing.clone();
+            let request = request.clone();
+            async move { syncing.sync_request(peer, request).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // The only read permit is taken, so the second request is not queued
+        let busy = syncing.sync_request(peer, request).await.unwrap();
+        assert!(matches!(busy, SyncResponse::Headers(headers) if headers.is_empty()));
+        assert!(pending.await.unwrap().is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! Peer-to-Peer Networking
+//!
+//! libp2p transport for full nodes, available with the `full-node` feature.
+//!
+//! ## Stack
+//!
+//! TCP with Noise encryption and Yamux multiplexing, running `ChainBehaviour`:
+//! gossipsub for the constellation topics, identify, ping, optional mDNS and
+//! the block sync request-response protocol.
+//!
+//! ## Discovery
+//!
+//! Nodes dial their bootstrap peers, mDNS neighbours on LAN devnets, and the
+//! good peers remembered in their persistent `AddressBook` (see `discovery`).
+//!
+//! ## Gossip
+//!
+//! Proposals, ranked votes, commits and transactions each have a topic per
+//! constellation (see `Topics`). Payloads are canonical v1 encodings of
+//! `ConsensusMessage` and `Transaction`. Only messages whose signature the
+//! service verified, against the committee and builders set with
+//! `NetworkHandle::set_committee` and `NetworkHandle::set_builders` for
+//! consensus messages, are relayed.
+//!
+//! ## Block Sync
+//!
+//! Nodes serve finalized blocks from a `BlockSource` over the sync protocol
+//! (see `sync`). A node that fell behind runs `BlockSync`, which downloads
+//! from several peers in parallel and finalizes each block only after its
+//! linkage and commit certificate check out.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use self_chain_core::network::{NetworkConfig, NetworkEvent, NetworkService};
+//!
+//! let mut config = NetworkConfig::from_node_config(&node_config, "mainnet")?;
+//! config.address_book_path = Some(data_dir.join("peers"));
+//! let (network, mut events) = NetworkService::start(config, keypair).await?;
+//! network.set_committee(committee).await?;
+//! network.set_builders(builders).await?;
+//!
+//! network.publish_consensus(&message).await?;
+//! while let Some(NetworkEvent::Consensus { message, .. }) = events.recv().await {
+//!     engine.handle_message(message)?;
+//! }
+//! ```
+
+pub mod behaviour;
+pub mod discovery;
+pub mod downloader;
+pub mod service;
+pub mod sync;
+pub mod topics;
+
+pub use behaviour::{ChainBehaviour, PROTOCOL_VERSION};
+pub use discovery::{AddressBook, PeerRecord};
+pub use downloader::{BlockSync, SyncReport};
+pub use service::{NetworkConfig, NetworkError, NetworkEvent, NetworkHandle, NetworkService};
+pub use sync::{BlockSource, MemoryBlockStore, SyncRequest, SyncResponse, SYNC_PROTOCOL};
+pub use topics::{TopicKind, Topics};
@@ -0,0 +1,1270 @@
+//! Network Service
+//!
+//! Owns the libp2p `Swarm` on a tokio task and exposes it to the consensus
+//! layer through channels.
+//!
+//! ## Channel API
+//!
+//! `NetworkService::start` binds the listen address, joins the constellation
+//! topics and returns a cloneable `NetworkHandle` for outbound commands plus a
+//! receiver of `NetworkEvent`s. The task stops once every handle is dropped
+//! or the event receiver is closed.
+//!
+//! ## Inbound Validation
+//!
+//! Each gossip message is decoded canonically and must match its topic (a
+//! ranked vote on the proposals topic is invalid). Only messages whose
+//! signature verifies are accepted and relayed:
+//!
+//! - Transactions must be signed by the key their sender is derived from
+//! - Ranked votes must verify against the key of their validator in the
+//!   committee given to `NetworkHandle::set_committee`
+//! - Proposals must verify against the key of their proposer, a committee
+//!   member or a builder given to `NetworkHandle::set_builders`
+//! - Commits must carry a `CommitCertificate` that verifies against the
+//!   committee. Commit signatures cover the block's efficiency score, which
+//!   is taken from the block's accepted proposal.
+//!
+//! Invalid messages are rejected and not delivered, which also lowers the
+//! relaying peer's gossipsub score. Consensus messages the service cannot
+//! check (from signers it does not know, or commits for blocks whose
+//! proposal it has not seen) are ignored: delivered for the consensus layer
+//! to validate, but not relayed.
+//!
+//! Events are delivered without waiting; if the receiver falls
+//! `channel_capacity` events behind, new events are dropped and counted.
+//!
+//! ## Peer Churn
+//!
+//! `PeerConnected` and `PeerDisconnected` are emitted on a peer's first and
+//! last connection. Every `redial_interval` the service redials, while they
+//! are disconnected:
+//!
+//! - Addresses passed to `NetworkHandle::dial` and `bootstrap_peers`
+//! - Address book peers dialed on start, until the book evicts them
+//!
+//! Peers found by mDNS or that dialed us are not redialed. The gossipsub
+//! mesh is repaired on every heartbeat.
+//!
+//! ## Discovery
+//!
+//! On start the service dials `bootstrap_peers` and the best peers of its
+//! address book; with `enable_mdns` it also dials peers found on the local
+//! network. Only peers it dialed are added to the address book. See
+//! `super::discovery`.
+//!
+//! ## Block Sync
+//!
+//! Inbound sync requests are answered from the `BlockSource` given to
+//! `NetworkService::start_with_store`. The store is read on tokio's blocking
+//! pool and the response is sent once it is ready, so a slow store never
+//! holds up gossip or other peers. At most `max_concurrent_sync_reads`
+//! requests are read at once; while they are all taken, requests are
+//! answered empty, which the requester treats as the peer not having the
+//! data rather than as a fault. `NetworkHandle::sync_request` sends a
+//! request to one peer and fails with `NetworkError::Sync` if the peer does
+//! not answer within `sync_request_timeout`.
+
+use super::behaviour::{ChainBehaviour, ChainBehaviourEvent, PROTOCOL_VERSION};
+use super::discovery::{self, AddressBook, PeerRecord};
+use super::sync::{self, BlockSource, MemoryBlockStore, SyncRequest, SyncResponse};
+use super::topics::{TopicKind, Topics};
+use crate::blockchain::v1::codec::Canonical;
+use crate::blockchain::v1::{Block, BlockProposal, RankedVote, Transaction};
+use crate::clock::{system_clock, SharedClock};
+use crate::consensus::v1::{
+    constants, CommitCertificate, ConsensusConfig, ConsensusMessage, ValidatorInfo,
+};
+use crate::crypto::derive_address;
+use crate::node::NodeConfig;
+use libp2p::core::ConnectedPoint;
+use libp2p::futures::StreamExt;
+use libp2p::gossipsub::{self, MessageAcceptance, PublishError};
+use libp2p::identity::Keypair;
+use libp2p::multiaddr::Protocol;
+use libp2p::request_response::{self, OutboundRequestId, ResponseChannel};
+use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
+use libp2p::swarm::SwarmEvent;
+use libp2p::{identify, mdns, noise, tcp, yamux, Multiaddr, PeerId, Swarm, SwarmBuilder};
+use lru::LruCache;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::time::MissedTickBehavior;
+
+/// Efficiency scores of recently proposed blocks kept to check commits
+const PROPOSED_SCORES: usize = 1024;
+
+/// Network service configuration
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// Address to listen on, e.g. `/ip4/0.0.0.0/tcp/30333`
+    pub listen_addr: Multiaddr,
+
+    /// Constellation whose topics are joined
+    pub constellation_id: String,
+
+    /// Gossipsub heartbeat interval
+    pub heartbeat_interval: Duration,
+
+    /// Largest gossip message accepted or published, in bytes
+    pub max_message_size: usize,
+
+    /// How often disconnected bootstrap, explicitly dialed and address
+    /// book peers are redialed
+    pub redial_interval: Duration,
+
+    /// Connections without active streams are closed after this long
+    pub idle_connection_timeout: Duration,
+
+    /// Capacity of the command and event channels
+    pub channel_capacity: usize,
+
+    /// How long a peer has to answer a sync request
+    pub sync_request_timeout: Duration,
+
+    /// Inbound sync requests read from the store at once; further requests
+    /// are answered empty
+    pub max_concurrent_sync_reads: usize,
+
+    /// Peers dialed on start and redialed while disconnected
+    pub bootstrap_peers: Vec<Multiaddr>,
+
+    /// Discover peers on the local network with mDNS
+    pub enable_mdns: bool,
+
+    /// File the address book is kept in; in memory only if unset
+    pub address_book_path: Option<PathBuf>,
+
+    /// Address book peers dialed on start
+    pub address_book_dials: usize,
+
+    /// Keep loopback and private addresses in the address book, for local
+    /// devnets
+    pub allow_private_addrs: bool,
+
+    /// Consensus parameters gossiped commits are verified with
+    pub consensus: ConsensusConfig,
+}
+
+impl NetworkConfig {
+    /// Default configuration listening on `listen_addr`
+    pub fn new(listen_addr: Multiaddr, constellation_id: impl Into<String>) -> Self {
+        Self {
+            listen_addr,
+            constellation_id: constellation_id.into(),
+            heartbeat_interval: Duration::from_secs(1),
+            // A full block plus the proposal envelope
+            max_message_size: constants::MAX_BLOCK_SIZE + 64 * 1024,
+            redial_interval: Duration::from_secs(10),
+            idle_connection_timeout: Duration::from_secs(60),
+            channel_capacity: 1024,
+            sync_request_timeout: Duration::from_secs(10),
+            max_concurrent_sync_reads: 4,
+            bootstrap_peers: Vec::new(),
+            enable_mdns: false,
+            address_book_path: None,
+            address_book_dials: 16,
+            allow_private_addrs: false,
+            consensus: ConsensusConfig::default(),
+        }
+    }
+
+    /// Configuration binding `node.listen_addr` and dialing
+    /// `node.bootstrap_peers`
+    ///
+    /// Addresses are multiaddrs or `host:port` TCP addresses.
+    pub fn from_node_config(
+        node: &NodeConfig,
+        constellation_id: impl Into<String>,
+    ) -> Result<Self, NetworkError> {
+        let mut config = Self::new(parse_addr(&node.listen_addr)?, constellation_id);
+        config.bootstrap_peers = node
+            .bootstrap_peers
+            .iter()
+            .map(|addr| parse_addr(addr))
+            .collect::<Result<_, _>>()?;
+        Ok(config)
+    }
+}
+
+/// Parse a multiaddr or a `host:port` TCP address
+fn parse_addr(addr: &str) -> Result<Multiaddr, NetworkError> {
+    let invalid = || NetworkError::InvalidAddress(addr.to_string());
+    if addr.starts_with('/') {
+        return addr.parse().map_err(|_| invalid());
+    }
+    let (host, port) = addr.rsplit_once(':').ok_or_else(invalid)?;
+    let port: u16 = port.parse().map_err(|_| invalid())?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = match host.parse::<IpAddr>() {
+        Ok(ip) => Protocol::from(ip),
+        Err(_) if !host.is_empty() => Protocol::Dns(host.into()),
+        Err(_) => return Err(invalid()),
+    };
+    Ok(Multiaddr::empty().with(host).with(Protocol::Tcp(port)))
+}
+
+/// Errors returned by the network layer
+#[derive(Debug, Error)]
+pub enum NetworkError {
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    #[error("Behaviour error: {0}")]
+    Behaviour(String),
+
+    #[error("Dial failed: {0}")]
+    Dial(String),
+
+    #[error("No peers subscribed to {0}")]
+    NoPeers(String),
+
+    #[error("Publish failed: {0}")]
+    Publish(String),
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("Sync request to {peer} failed: {reason}")]
+    Sync { peer: PeerId, reason: String },
+
+    #[error("Network service stopped")]
+    Stopped,
+}
+
+/// Event delivered to the consensus layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkEvent {
+    /// Valid consensus message from `source`
+    Consensus {
+        source: PeerId,
+        message: ConsensusMessage,
+    },
+    /// Valid transaction from `source`
+    Transaction {
+        source: PeerId,
+        transaction: Transaction,
+    },
+    /// First connection to a peer was established
+    PeerConnected(PeerId),
+    /// Last connection to a peer was closed
+    PeerDisconnected(PeerId),
+}
+
+/// Request from a handle to the service task
+enum Command {
+    Publish {
+        kind: TopicKind,
+        data: Vec<u8>,
+        reply: oneshot::Sender<Result<(), NetworkError>>,
+    },
+    Dial {
+        addr: Multiaddr,
+        reply: oneshot::Sender<Result<(), NetworkError>>,
+    },
+    ConnectedPeers {
+        reply: oneshot::Sender<Vec<PeerId>>,
+    },
+    ListenAddrs {
+        reply: oneshot::Sender<Vec<Multiaddr>>,
+    },
+    Sync {
+        peer: PeerId,
+        request: SyncRequest,
+        reply: SyncReply,
+    },
+    KnownPeers {
+        reply: oneshot::Sender<Vec<PeerRecord>>,
+    },
+    SetCommittee {
+        committee: Vec<ValidatorInfo>,
+        reply: oneshot::Sender<()>,
+    },
+    SetBuilders {
+        builders: Vec<ValidatorInfo>,
+        reply: oneshot::Sender<()>,
+    },
+}
+
+type SyncReply = oneshot::Sender<Result<SyncResponse, NetworkError>>;
+
+/// Response to an inbound sync request, read from the store off the swarm
+/// task
+struct ServedSync {
+    peer: PeerId,
+    channel: ResponseChannel<SyncResponse>,
+    response: SyncResponse,
+}
+
+/// Cloneable handle to a running `NetworkService`
+#[derive(Debug, Clone)]
+pub struct NetworkHandle {
+    local_peer_id: PeerId,
+    commands: mpsc::Sender<Command>,
+}
+
+impl NetworkHandle {
+    /// Peer ID of the local node
+    pub fn local_peer_id(&self) -> PeerId {
+        self.local_peer_id
+    }
+
+    /// Gossip a consensus message on its constellation topic
+    pub async fn publish_consensus(&self, message: &ConsensusMessage) -> Result<(), NetworkError> {
+        let kind = TopicKind::of(message);
+        let data = message.to_canonical_bytes();
+        self.request(|reply| Command::Publish { kind, data, reply })
+            .await?
+    }
+
+    /// Gossip a transaction on the constellation's transaction topic
+    pub async fn publish_transaction(&self, transaction: &Transaction) -> Result<(), NetworkError> {
+        let data = transaction.to_canonical_bytes();
+        self.request(|reply| Command::Publish {
+            kind: TopicKind::Transactions,
+            data,
+            reply,
+        })
+        .await?
+    }
+
+    /// Dial `addr` and keep redialing it while disconnected
+    pub async fn dial(&self, addr: Multiaddr) -> Result<(), NetworkError> {
+        self.request(|reply| Command::Dial { addr, reply }).await?
+    }
+
+    /// Currently connected peers
+    pub async fn connected_peers(&self) -> Result<Vec<PeerId>, NetworkError> {
+        self.request(|reply| Command::ConnectedPeers { reply }).await
+    }
+
+    /// Addresses the node is listening on
+    pub async fn listen_addrs(&self) -> Result<Vec<Multiaddr>, NetworkError> {
+        self.request(|reply| Command::ListenAddrs { reply }).await
+    }
+
+    /// Peers in the address book, best first
+    pub async fn known_peers(&self) -> Result<Vec<PeerRecord>, NetworkError> {
+        self.request(|reply| Command::KnownPeers { reply }).await
+    }
+
+    /// Verify gossiped proposals, votes and commits against `committee`
+    /// from now on
+    pub async fn set_committee(&self, committee: Vec<ValidatorInfo>) -> Result<(), NetworkError> {
+        self.request(|reply| Command::SetCommittee { committee, reply })
+            .await
+    }
+
+    /// Also accept gossiped proposals signed by `builders` from now on
+    pub async fn set_builders(&self, builders: Vec<ValidatorInfo>) -> Result<(), NetworkError> {
+        self.request(|reply| Command::SetBuilders { builders, reply })
+            .await
+    }
+
+    /// Send a block sync request to `peer` and wait for its response
+    pub async fn sync_request(
+        &self,
+        peer: PeerId,
+        request: SyncRequest,
+    ) -> Result<SyncResponse, NetworkError> {
+        self.request(|reply| Command::Sync {
+            peer,
+            request,
+            reply,
+        })
+        .await?
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> Command,
+    ) -> Result<T, NetworkError> {
+        let (reply, response) = oneshot::channel();
+        self.commands
+            .send(command(reply))
+            .await
+            .map_err(|_| NetworkError::Stopped)?;
+        response.await.map_err(|_| NetworkError::Stopped)
+    }
+}
+
+/// libp2p swarm driver for one node
+pub struct NetworkService {
+    swarm: Swarm<ChainBehaviour>,
+    topics: Topics,
+    config: NetworkConfig,
+    commands: mpsc::Receiver<Command>,
+    events: mpsc::Sender<NetworkEvent>,
+    dropped_events: u64,
+    committee: Vec<ValidatorInfo>,
+    builders: Vec<ValidatorInfo>,
+    /// Efficiency scores of blocks from accepted proposals
+    proposed_scores: LruCache<[u8; 32], u64>,
+    connected: HashSet<PeerId>,
+    /// Connected peers we dialed, the only ones the address book learns
+    dialed: HashSet<PeerId>,
+    /// Bootstrap and explicitly dialed addresses, with the peer found there
+    pinned: HashMap<Multiaddr, Option<PeerId>>,
+    /// Address book peers dialed on start
+    book_dials: HashSet<PeerId>,
+    store: Arc<dyn BlockSource>,
+    served_tx: mpsc::Sender<ServedSync>,
+    served: mpsc::Receiver<ServedSync>,
+    /// Permits for store reads of inbound sync requests
+    sync_reads: Arc<Semaphore>,
+    sync_requests: HashMap<OutboundRequestId, SyncReply>,
+    book: AddressBook,
+    clock: SharedClock,
+}
+
+impl NetworkService {
+    /// Start a node identified by `keypair` and spawn its service task
+    ///
+    /// Returns once the listen address is bound, so it can be dialed at once.
+    /// Sync requests are answered from an empty store.
+    pub async fn start(
+        config: NetworkConfig,
+        keypair: Keypair,
+    ) -> Result<(NetworkHandle, mpsc::Receiver<NetworkEvent>), NetworkError> {
+        Self::start_with_store(config, keypair, Arc::new(MemoryBlockStore::new())).await
+    }
+
+    /// Start a node that serves sync requests from `store`
+    pub async fn start_with_store(
+        config: NetworkConfig,
+        keypair: Keypair,
+        store: Arc<dyn BlockSource>,
+    ) -> Result<(NetworkHandle, mpsc::Receiver<NetworkEvent>), NetworkError> {
+        let book = match &config.address_book_path {
+            Some(path) => AddressBook::open(path)?,
+            None => AddressBook::new(),
+        };
+        let behaviour = ChainBehaviour::new(&keypair, &config)?;
+        let mut swarm = SwarmBuilder::with_existing_identity(keypair)
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default().nodelay(true),
+                noise::Config::new,
+                yamux::Config::default,
+            )
+            .map_err(|e| NetworkError::Transport(e.to_string()))?
+            .with_behaviour(|_| behaviour)
+            .unwrap_or_else(|never| match never {})
+            .with_swarm_config(|c| c.with_idle_connection_timeout(config.idle_connection_timeout))
+            .build();
+
+        let topics = Topics::new(&config.constellation_id);
+        for topic in topics.iter() {
+            swarm
+                .behaviour_mut()
+                .gossipsub
+                .subscribe(topic)
+                .map_err(|e| NetworkError::Behaviour(e.to_string()))?;
+        }
+
+        swarm
+            .listen_on(config.listen_addr.clone())
+            .map_err(|e| NetworkError::Transport(e.to_string()))?;
+        loop {
+            match swarm.select_next_some().await {
+                SwarmEvent::NewListenAddr { address, .. } => {
+                    tracing::info!("Listening on {}", address);
+                    break;
+                }
+                SwarmEvent::ListenerError { error, .. } => {
+                    return Err(NetworkError::Transport(error.to_string()));
+                }
+                SwarmEvent::ListenerClosed { reason, .. } => {
+                    let reason = reason.err().map(|e| e.to_string());
+                    return Err(NetworkError::Transport(
+                        reason.unwrap_or_else(|| "listener closed".to_string()),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        for addr in &config.bootstrap_peers {
+            if let Err(e) = swarm.dial(addr.clone()) {
+                tracing::warn!("Dial of bootstrap peer {} failed: {}", addr, e);
+            }
+        }
+        let pinned = config.bootstrap_peers.iter().map(|addr| (addr.clone(), None)).collect();
+        let mut book_dials = HashSet::new();
+        for record in book.good_peers(config.address_book_dials) {
+            book_dials.insert(record.peer_id);
+            if let Err(e) = swarm.dial(book_dial(record)) {
+                tracing::debug!("Dial of known peer {} failed: {}", record.peer_id, e);
+            }
+        }
+
+        let (command_tx, command_rx) = mpsc::channel(config.channel_capacity);
+        let (event_tx, event_rx) = mpsc::channel(config.channel_capacity);
+        let (served_tx, served) = mpsc::channel(config.channel_capacity);
+        let sync_reads = Arc::new(Semaphore::new(config.max_concurrent_sync_reads));
+        let handle = NetworkHandle {
+            local_peer_id: *swarm.local_peer_id(),
+            commands: command_tx,
+        };
+        let service = Self {
+            swarm,
+            topics,
+            config,
+            commands: command_rx,
+            events: event_tx,
+            dropped_events: 0,
+            committee: Vec::new(),
+            builders: Vec::new(),
+            proposed_scores: LruCache::new(NonZeroUsize::new(PROPOSED_SCORES).unwrap()),
+            connected: HashSet::new(),
+            dialed: HashSet::new(),
+            pinned,
+            book_dials,
+            store,
+            served_tx,
+            served,
+            sync_reads,
+            sync_requests: HashMap::new(),
+            book,
+            clock: system_clock(),
+        };
+        tokio::spawn(service.run());
+        Ok((handle, event_rx))
+    }
+
+    async fn run(mut self) {
+        let mut redial = tokio::time::interval(self.config.redial_interval);
+        redial.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let running = tokio::select! {
+                event = self.swarm.select_next_some() => self.on_swarm_event(event),
+                command = self.commands.recv() => match command {
+                    Some(command) => {
+                        self.on_command(command);
+                        true
+                    }
+                    None => false,
+                },
+                Some(served) = self.served.recv() => {
+                    self.send_sync_response(served);
+                    true
+                }
+                _ = redial.tick() => {
+                    self.redial();
+                    self.save_address_book();
+                    true
+                }
+            };
+            if !running {
+                self.save_address_book();
+                tracing::debug!("Network service for {} stopped", self.swarm.local_peer_id());
+                return;
+            }
+        }
+    }
+
+    fn on_command(&mut self, command: Command) {
+        match command {
+            Command::Publish { kind, data, reply } => {
+                let topic = self.topics.topic(kind).clone();
+                let gossipsub = &mut self.swarm.behaviour_mut().gossipsub;
+                let result = match gossipsub.publish(topic.hash(), data) {
+                    // Already seen, so peers already have it
+                    Ok(_) | Err(PublishError::Duplicate) => Ok(()),
+                    Err(PublishError::NoPeersSubscribedToTopic) => {
+                        Err(NetworkError::NoPeers(topic.to_string()))
+                    }
+                    Err(e) => Err(NetworkError::Publish(e.to_string())),
+                };
+                let _ = reply.send(result);
+            }
+            Command::Dial { addr, reply } => {
+                let result = self
+                    .swarm
+                    .dial(addr.clone())
+                    .map_err(|e| NetworkError::Dial(e.to_string()));
+                if result.is_ok() {
+                    self.pinned.entry(addr).or_insert(None);
+                }
+                let _ = reply.send(result);
+            }
+            Command::ConnectedPeers { reply } => {
+                let _ = reply.send(self.connected.iter().copied().collect());
+            }
+            Command::ListenAddrs { reply } => {
+                let _ = reply.send(self.swarm.listeners().cloned().collect());
+            }
+            Command::Sync {
+                peer,
+                request,
+                reply,
+            } => {
+                let request_id = self.swarm.behaviour_mut().sync.send_request(&peer, request);
+                self.sync_requests.insert(request_id, reply);
+            }
+            Command::KnownPeers { reply } => {
+                let peers = self.book.good_peers(usize::MAX).into_iter().cloned().collect();
+                let _ = reply.send(peers);
+            }
+            Command::SetCommittee { committee, reply } => {
+                self.committee = committee;
+                let _ = reply.send(());
+            }
+            Command::SetBuilders { builders, reply } => {
+                self.builders = builders;
+                let _ = reply.send(());
+            }
+        }
+    }
+
+    /// Handle one swarm event; false once the event receiver is gone
+    fn on_swarm_event(&mut self, event: SwarmEvent<ChainBehaviourEvent>) -> bool {
+        match event {
+            SwarmEvent::Behaviour(ChainBehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                propagation_source,
+                message_id,
+                message,
+            })) => {
+                let source = message.source.unwrap_or(propagation_source);
+                let (acceptance, event) = self.decode(&message, source);
+                if matches!(acceptance, MessageAcceptance::Reject) {
+                    tracing::debug!("Rejected invalid gossip from {}", propagation_source);
+                }
+                self.swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    acceptance,
+                );
+                match event {
+                    Some(event) => self.emit(event),
+                    None => true,
+                }
+            }
+            SwarmEvent::Behaviour(ChainBehaviourEvent::Sync(event)) => {
+                self.on_sync_event(event);
+                !self.events.is_closed()
+            }
+            SwarmEvent::Behaviour(ChainBehaviourEvent::Identify(
+                identify::Event::Received { peer_id, info, .. },
+            )) => {
+                if info.protocol_version == PROTOCOL_VERSION && self.dialed.contains(&peer_id) {
+                    let allow_private = self.config.allow_private_addrs;
+                    let addrs = info
+                        .listen_addrs
+                        .into_iter()
+                        .filter(|addr| allow_private || discovery::is_routable(addr))
+                        .collect();
+                    let now = self.clock.unix_secs();
+                    self.book.record_seen(peer_id, addrs, now);
+                }
+                !self.events.is_closed()
+            }
+            SwarmEvent::Behaviour(ChainBehaviourEvent::Mdns(mdns::Event::Discovered(peers))) => {
+                for (peer_id, addr) in peers {
+                    let opts = DialOpts::peer_id(peer_id)
+                        .condition(PeerCondition::DisconnectedAndNotDialing)
+                        .addresses(vec![addr])
+                        .build();
+                    if let Err(e) = self.swarm.dial(opts) {
+                        tracing::debug!("Dial of mDNS peer {} failed: {}", peer_id, e);
+                    }
+                }
+                !self.events.is_closed()
+            }
+            SwarmEvent::ConnectionEstablished {
+                peer_id, endpoint, ..
+            } => {
+                if let ConnectedPoint::Dialer { address, .. } = endpoint {
+                    if let Some(peer) = self.pinned.get_mut(&address) {
+                        *peer = Some(peer_id);
+                    }
+                    self.dialed.insert(peer_id);
+                }
+                if self.connected.insert(peer_id) {
+                    return self.emit(NetworkEvent::PeerConnected(peer_id));
+                }
+                true
+            }
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                num_established: 0,
+                ..
+            } => {
+                self.dialed.remove(&peer_id);
+                if self.connected.remove(&peer_id) {
+                    return self.emit(NetworkEvent::PeerDisconnected(peer_id));
+                }
+                true
+            }
+            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                tracing::debug!("Dial to {:?} failed: {}", peer_id, error);
+                if let Some(peer_id) = peer_id {
+                    self.book.record_failure(&peer_id);
+                }
+                true
+            }
+            _ => !self.events.is_closed(),
+        }
+    }
+
+    /// Deliver `event` without blocking the swarm; false once the receiver
+    /// is gone
+    fn emit(&mut self, event: NetworkEvent) -> bool {
+        match self.events.try_send(event) {
+            Ok(()) => true,
+            Err(TrySendError::Full(event)) => {
+                self.dropped_events += 1;
+                tracing::warn!(
+                    "Event receiver is full, dropped {:?} ({} dropped so far)",
+                    event,
+                    self.dropped_events
+                );
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+
+    fn on_sync_event(&mut self, event: request_response::Event<SyncRequest, SyncResponse>) {
+        match event {
+            request_response::Event::Message { peer, message, .. } => match message {
+                request_response::Message::Request {
+                    request, channel, ..
+                } => {
+                    let Ok(permit) = Arc::clone(&self.sync_reads).try_acquire_owned() else {
+                        tracing::debug!("Sync store busy, answering {} empty", peer);
+                        self.send_sync_response(ServedSync {
+                            peer,
+                            channel,
+                            response: sync::empty(&request),
+                        });
+                        return;
+                    };
+                    let store = Arc::clone(&self.store);
+                    let served = self.served_tx.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let response = sync::respond(store.as_ref(), request);
+                        drop(permit);
+                        let _ = served.blocking_send(ServedSync {
+                            peer,
+                            channel,
+                            response,
+                        });
+                    });
+                }
+                request_response::Message::Response {
+                    request_id,
+                    response,
+                } => {
+                    if let Some(reply) = self.sync_requests.remove(&request_id) {
+                        let _ = reply.send(Ok(response));
+                    }
+                }
+            },
+            request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+                ..
+            } => {
+                if let Some(reply) = self.sync_requests.remove(&request_id) {
+                    let reason = error.to_string();
+                    let _ = reply.send(Err(NetworkError::Sync { peer, reason }));
+                }
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                tracing::debug!("Sync request from {} failed: {}", peer, error);
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    fn send_sync_response(&mut self, served: ServedSync) {
+        let ServedSync {
+            peer,
+            channel,
+            response,
+        } = served;
+        if self.swarm.behaviour_mut().sync.send_response(channel, response).is_err() {
+            tracing::debug!("Sync peer {} left before its response", peer);
+        }
+    }
+
+    /// Decode and validate a gossip message
+    ///
+    /// Rejected messages yield no event; accepted and ignored ones do.
+    fn decode(
+        &mut self,
+        message: &gossipsub::Message,
+        source: PeerId,
+    ) -> (MessageAcceptance, Option<NetworkEvent>) {
+        let event = match self.topics.kind(&message.topic) {
+            Some(TopicKind::Transactions) => Transaction::from_canonical_bytes(&message.data)
+                .ok()
+                .map(|transaction| NetworkEvent::Transaction {
+                    source,
+                    transaction,
+                }),
+            Some(kind) => ConsensusMessage::from_canonical_bytes(&message.data)
+                .ok()
+                .filter(|message| TopicKind::of(message) == kind)
+                .map(|message| NetworkEvent::Consensus { source, message }),
+            None => None,
+        };
+        let acceptance = match &event {
+            Some(NetworkEvent::Transaction { transaction, .. }) => {
+                let valid = transaction.verify_signature().is_ok()
+                    && transaction.sender == derive_address(&transaction.public_key);
+                if valid {
+                    MessageAcceptance::Accept
+                } else {
+                    MessageAcceptance::Reject
+                }
+            }
+            Some(NetworkEvent::Consensus { message, .. }) => self.check_signature(message),
+            _ => MessageAcceptance::Reject,
+        };
+        match acceptance {
+            MessageAcceptance::Reject => (acceptance, None),
+            _ => (acceptance, event),
+        }
+    }
+
+    /// Verify a proposal, vote or commit from known signers
+    ///
+    /// Accepted proposals record their block's efficiency score for the
+    /// commits that follow.
+    fn check_signature(&mut self, message: &ConsensusMessage) -> MessageAcceptance {
+        let valid = match message {
+            ConsensusMessage::Proposal {
+                height,
+                round,
+                proposer_id,
+                block_hash,
+                block_data,
+                signature,
+                ..
+            } => {
+                let signer = self.committee.iter().chain(&self.builders).find(|v| {
+                    &v.validator_id == proposer_id
+                });
+                let Some(public_key) = signer.map(|v| v.public_key) else {
+                    return MessageAcceptance::Ignore;
+                };
+                let Ok(block) = Block::from_canonical_bytes(block_data) else {
+                    return MessageAcceptance::Reject;
+                };
+                let proposal = BlockProposal {
+                    height: *height,
+                    round: *round,
+                    proposer_id: proposer_id.clone(),
+                    block,
+                    signature: *signature,
+                };
+                let valid = proposal.block.hash() == *block_hash
+                    && proposal.verify_signature(&public_key).is_ok();
+                if valid {
+                    let score = proposal.block.header.efficiency_score;
+                    self.proposed_scores.put(*block_hash, score);
+                }
+                valid
+            }
+            ConsensusMessage::RankedVote {
+                height,
+                round,
+                block_hash,
+                efficiency_score,
+                validator_id,
+                signature,
+            } => {
+                let Some(member) = self.committee.iter().find(|v| &v.validator_id == validator_id)
+                else {
+                    return MessageAcceptance::Ignore;
+                };
+                let vote = RankedVote {
+                    height: *height,
+                    round: *round,
+                    block_hash: *block_hash,
+                    efficiency_score: *efficiency_score,
+                    validator_id: validator_id.clone(),
+                    signature: *signature,
+                };
+                vote.verify_signature(&member.public_key).is_ok()
+            }
+            ConsensusMessage::Commit {
+                height,
+                round,
+                block_hash,
+                signatures,
+            } => {
+                if self.committee.is_empty() {
+                    return MessageAcceptance::Ignore;
+                }
+                let Some(&efficiency_score) = self.proposed_scores.get(block_hash) else {
+                    return MessageAcceptance::Ignore;
+                };
+                CommitCertificate::from_commit(
+                    *height,
+                    *round,
+                    *block_hash,
+                    efficiency_score,
+                    signatures,
+                )
+                .verify(&self.committee, &self.config.consensus)
+                .is_ok()
+            }
+        };
+        if valid {
+            MessageAcceptance::Accept
+        } else {
+            MessageAcceptance::Reject
+        }
+    }
+
+    fn save_address_book(&mut self) {
+        if let Err(e) = self.book.save() {
+            tracing::warn!("{}", e);
+        }
+    }
+
+    /// Dial pinned addresses and book peers that are not connected
+    fn redial(&mut self) {
+        for (addr, peer) in &self.pinned {
+            if peer.is_some_and(|peer| self.connected.contains(&peer)) {
+                continue;
+            }
+            tracing::debug!("Redialing {}", addr);
+            if let Err(e) = self.swarm.dial(addr.clone()) {
+                tracing::debug!("Redial of {} failed: {}", addr, e);
+            }
+        }
+
+        let book = &self.book;
+        self.book_dials.retain(|peer| book.get(peer).is_some());
+        for record in self.book_dials.iter().filter_map(|peer| book.get(peer)) {
+            if self.connected.contains(&record.peer_id) {
+                continue;
+            }
+            if let Err(e) = self.swarm.dial(book_dial(record)) {
+                tracing::debug!("Redial of known peer {} failed: {}", record.peer_id, e);
+            }
+        }
+    }
+}
+
+/// Dial options for an address book peer, skipped while it is connected or
+/// being dialed
+fn book_dial(record: &PeerRecord) -> DialOpts {
+    DialOpts::peer_id(record.peer_id)
+        .condition(PeerCondition::DisconnectedAndNotDialing)
+        .addresses(record.addrs.clone())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::common::traits::KeyPair;
+    use crate::crypto::Ed25519Keys;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    fn config() -> NetworkConfig {
+        let mut config = NetworkConfig::new("/ip4/127.0.0.1/tcp/0".parse().unwrap(), "devnet");
+        config.heartbeat_interval = Duration::from_millis(100);
+        config.redial_interval = Duration::from_millis(200);
+        config.allow_private_addrs = true;
+        config
+    }
+
+    async fn node() -> (NetworkHandle, mpsc::Receiver<NetworkEvent>) {
+        NetworkService::start(config(), Keypair::generate_ed25519())
+            .await
+            .unwrap()
+    }
+
+    async fn connect(from: &NetworkHandle, to: &NetworkHandle) {
+        let addr = to.listen_addrs().await.unwrap().remove(0);
+        from.dial(addr).await.unwrap();
+    }
+
+    /// Committee of one validator, `v0`
+    fn committee() -> (Vec<ValidatorInfo>, Ed25519Keys) {
+        let keys = Ed25519Keys::new().unwrap();
+        let public_key = keys.public_key().try_into().unwrap();
+        (vec![ValidatorInfo::new("v0".to_string(), public_key, "c".to_string())], keys)
+    }
+
+    /// Ranked vote from `validator_id` signed with `keys`
+    fn vote(keys: &Ed25519Keys, validator_id: &str) -> ConsensusMessage {
+        let mut vote = RankedVote::new(1, 0, [7u8; 32], 4200, validator_id.to_string());
+        vote.sign(keys).unwrap();
+        ConsensusMessage::RankedVote {
+            height: vote.height,
+            round: vote.round,
+            block_hash: vote.block_hash,
+            efficiency_score: vote.efficiency_score,
+            validator_id: vote.validator_id,
+            signature: vote.signature,
+        }
+    }
+
+    /// Next consensus or transaction event, skipping peer events
+    async fn next_gossip(events: &mut mpsc::Receiver<NetworkEvent>) -> NetworkEvent {
+        timeout(Duration::from_secs(10), async {
+            loop {
+                match events.recv().await.expect("service running") {
+                    NetworkEvent::PeerConnected(_) | NetworkEvent::PeerDisconnected(_) => {}
+                    event => return event,
+                }
+            }
+        })
+        .await
+        .expect("gossip delivered")
+    }
+
+    async fn next(events: &mut mpsc::Receiver<NetworkEvent>) -> Option<NetworkEvent> {
+        timeout(Duration::from_secs(10), events.recv()).await.expect("event delivered")
+    }
+
+    /// Publish once the topic has subscribers
+    async fn publish(handle: &NetworkHandle, message: &ConsensusMessage) {
+        timeout(Duration::from_secs(10), async {
+            while handle.publish_consensus(message).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("peers subscribed");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_gossip_reaches_every_node() {
+        let (hub, mut hub_events) = node().await;
+        let (a, mut a_events) = node().await;
+        let (b, mut b_events) = node().await;
+        let (validators, keys) = committee();
+        for handle in [&hub, &a, &b] {
+            handle.set_committee(validators.clone()).await.unwrap();
+        }
+        connect(&a, &hub).await;
+        connect(&b, &hub).await;
+
+        // b reaches a only through the hub's mesh
+        let message = vote(&keys, "v0");
+        publish(&b, &message).await;
+        for events in [&mut hub_events, &mut a_events] {
+            assert_eq!(
+                next_gossip(events).await,
+                NetworkEvent::Consensus {
+                    source: b.local_peer_id(),
+                    message: message.clone(),
+                }
+            );
+        }
+
+        let wallet = Ed25519Keys::new().unwrap();
+        let sender = derive_address(wallet.public_key());
+        let mut tx = Transaction::new(1, "devnet".to_string(), sender, None, vec![], 10, 1);
+        tx.sign(&wallet).unwrap();
+        timeout(Duration::from_secs(10), async {
+            while a.publish_transaction(&tx).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(matches!(
+            next_gossip(&mut b_events).await,
+            NetworkEvent::Transaction { transaction, .. } if transaction == tx
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_builder_proposals_and_commits_cross_hops() {
+        let (hub, _hub_events) = node().await;
+        let (a, mut a_events) = node().await;
+        let (b, _b_events) = node().await;
+        let (validators, keys) = committee();
+        let builder_keys = Ed25519Keys::new().unwrap();
+        let builder_key = builder_keys.public_key().try_into().unwrap();
+        let builders = vec![ValidatorInfo::new("builder".to_string(), builder_key, "c".to_string())];
+        for handle in [&hub, &a, &b] {
+            handle.set_committee(validators.clone()).await.unwrap();
+            handle.set_builders(builders.clone()).await.unwrap();
+        }
+        connect(&a, &hub).await;
+        connect(&b, &hub).await;
+
+        let mut header = crate::blockchain::v1::BlockHeader::genesis("c");
+        header.height = 1;
+        header.efficiency_score = 4200;
+        header.proposer_id = "builder".to_string();
+        let block = Block::new(header, vec![]);
+        let mut proposal = BlockProposal::new(1, 0, "builder".to_string(), block.clone());
+        proposal.sign(&builder_keys).unwrap();
+        let proposal = ConsensusMessage::Proposal {
+            height: 1,
+            round: 0,
+            proposer_id: proposal.proposer_id.clone(),
+            block_hash: block.hash(),
+            efficiency_score: 4200,
+            block_data: block.to_canonical_bytes(),
+            signature: proposal.signature,
+        };
+
+        let mut vote = RankedVote::new(1, 0, block.hash(), 4200, "v0".to_string());
+        vote.sign(&keys).unwrap();
+        let commit = CommitCertificate::from_votes(1, 0, block.hash(), 4200, &[vote]).to_message();
+
+        // b reaches a only through the hub, which must verify and relay both
+        for message in [proposal, commit] {
+            publish(&b, &message).await;
+            assert_eq!(
+                next_gossip(&mut a_events).await,
+                NetworkEvent::Consensus {
+                    source: b.local_peer_id(),
+                    message,
+                }
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_peer_churn_is_reported() {
+        let (hub, mut hub_events) = node().await;
+        let (leaf, leaf_events) = node().await;
+        connect(&leaf, &hub).await;
+
+        let leaf_id = leaf.local_peer_id();
+        assert_eq!(next(&mut hub_events).await, Some(NetworkEvent::PeerConnected(leaf_id)));
+        assert_eq!(hub.connected_peers().await.unwrap(), vec![leaf_id]);
+
+        // Stopping the leaf's service drops its connections
+        drop(leaf);
+        drop(leaf_events);
+        assert_eq!(next(&mut hub_events).await, Some(NetworkEvent::PeerDisconnected(leaf_id)));
+        assert!(hub.connected_peers().await.unwrap().is_empty());
+        let message = vote(&Ed25519Keys::new().unwrap(), "v0");
+        assert!(matches!(hub.publish_consensus(&message).await, Err(NetworkError::NoPeers(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_unverified_gossip_is_not_relayed() {
+        let (hub, mut hub_events) = node().await;
+        let (a, mut a_events) = node().await;
+        let (b, _b_events) = node().await;
+        let (validators, keys) = committee();
+        hub.set_committee(validators).await.unwrap();
+        connect(&a, &hub).await;
+        connect(&b, &hub).await;
+
+        let forged = vote(&Ed25519Keys::new().unwrap(), "v0");
+        let outsider = vote(&Ed25519Keys::new().unwrap(), "builder");
+        let valid = vote(&keys, "v0");
+        publish(&b, &forged).await;
+        publish(&b, &outsider).await;
+        publish(&b, &valid).await;
+
+        // The forgery is dropped; the outsider is delivered but not relayed
+        let delivered = |message: &ConsensusMessage| NetworkEvent::Consensus {
+            source: b.local_peer_id(),
+            message: message.clone(),
+        };
+        assert_eq!(next_gossip(&mut hub_events).await, delivered(&outsider));
+        assert_eq!(next_gossip(&mut hub_events).await, delivered(&valid));
+        assert_eq!(next_gossip(&mut a_events).await, delivered(&valid));
+    }
+
+    #[test]
+    fn test_node_config_addresses() {
+        let node = NodeConfig {
+            node_id: "v1".to_string(),
+            node_type: crate::node::NodeType::Validator,
+            listen_addr: "0.0.0.0:30333".to_string(),
+            bootstrap_peers: vec![
+                "/ip4/10.0.0.1/tcp/30333".to_string(),
+                "seed.example.org:30333".to_string(),
+                "[::1]:30334".to_string(),
+            ],
+        };
+        let config = NetworkConfig::from_node_config(&node, "devnet").unwrap();
+        assert_eq!(config.listen_addr.to_string(), "/ip4/0.0.0.0/tcp/30333");
+        let bootstrap: Vec<String> = config.bootstrap_peers.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            bootstrap,
+            vec!["/ip4/10.0.0.1/tcp/30333", "/dns/seed.example.org/tcp/30333", "/ip6/::1/tcp/30334"]
+        );
+
+        for addr in ["127.0.0.1", "127.0.0.1:port", ":30333", "/not/a/multiaddr"] {
+            let node = NodeConfig {
+                listen_addr: addr.to_string(),
+                ..node.clone()
+            };
+            assert!(matches!(
+                NetworkConfig::from_node_config(&node, "devnet"),
+                Err(NetworkError::InvalidAddress(_))
+            ));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_restart_reconnects_from_address_book() {
+        let path = std::env::temp_dir().join(format!(
+            "self-chain-network-book-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let (seed, _seed_events) = node().await;
+        let seed_addr = seed.listen_addrs().await.unwrap().remove(0);
+
+        // First run: only the bootstrap list knows the seed
+        let mut first = config();
+        first.bootstrap_peers = vec![seed_addr];
+        first.address_book_path = Some(path.clone());
+        let keypair = Keypair::generate_ed25519();
+        let (handle, mut events) = NetworkService::start(first, keypair.clone()).await.unwrap();
+        let connected = Some(NetworkEvent::PeerConnected(seed.local_peer_id()));
+        assert_eq!(next(&mut events).await, connected);
+        timeout(Duration::from_secs(10), async {
+            while handle.known_peers().await.unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("seed identified");
+        // The seed was dialed, not dialing, so it learns nothing
+        assert!(seed.known_peers().await.unwrap().is_empty());
+        drop(handle);
+        drop(events);
+
+        // The stopped service saved the seed to the address book
+        timeout(Duration::from_secs(10), async {
+            while AddressBook::open(&path).unwrap().get(&seed.local_peer_id()).is_none() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("address book saved");
+
+        // Second run: no bootstrap peers, the address book is enough
+        let mut second = config();
+        second.address_book_path = Some(path.clone());
+        let (_handle, mut events) = NetworkService::start(second, keypair).await.unwrap();
+        let connected = Some(NetworkEvent::PeerConnected(seed.local_peer_id()));
+        assert_eq!(next(&mut events).await, connected);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,421 @@
+//! Block Sync Protocol
+//!
+//! Request-response protocol that lets a node that joined late or restarted
+//! fetch the finalized blocks it missed from its peers.
+//!
+//! ## Requests
+//!
+//! | Request | Response |
+//! |---------|----------|
+//! | `GetHeaders { from, count }` | Consecutive finalized headers starting at `from` |
+//! | `GetBlocks { hashes }` | The finalized blocks with these hashes, in request order |
+//! | `GetCommitCertificate { height }` | The finality proof of the block at `height` |
+//!
+//! Responses are capped at `MAX_HEADERS_PER_REQUEST` headers and
+//! `MAX_BLOCKS_PER_REQUEST` blocks; a peer serves what it has, so a response
+//! may be shorter than asked for.
+//!
+//! ## Wire Format
+//!
+//! Each request and response is one canonical encoding on its own stream,
+//! limited to `MAX_SYNC_MESSAGE_SIZE` bytes.
+//!
+//! ## Canonical Encoding Order
+//!
+//! 1. Variant tag (u8: 0 = GetHeaders / Headers, 1 = GetBlocks / Blocks,
+//!    2 = GetCommitCertificate / CommitCertificate)
+//! 2. Variant fields in declaration order
+
+use crate::blockchain::v1::codec::{Canonical, CodecError, CodecResult, Decoder, Encoder};
+use crate::blockchain::v1::{Block, BlockHeader};
+use crate::consensus::v1::CommitCertificate;
+use async_trait::async_trait;
+use libp2p::futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::request_response;
+use libp2p::StreamProtocol;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::RwLock;
+
+/// Protocol name of block sync
+pub const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/self-chain/sync/1");
+
+/// Most headers returned for one `GetHeaders`
+pub const MAX_HEADERS_PER_REQUEST: u64 = 256;
+
+/// Most blocks returned for one `GetBlocks`
+pub const MAX_BLOCKS_PER_REQUEST: usize = 8;
+
+/// Largest sync request or response, in bytes
+pub const MAX_SYNC_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Block sync request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncRequest {
+    /// Up to `count` finalized headers starting at height `from`
+    GetHeaders { from: u64, count: u64 },
+    /// Finalized blocks by hash
+    GetBlocks { hashes: Vec<[u8; 32]> },
+    /// Commit certificate of the finalized block at `height`
+    GetCommitCertificate { height: u64 },
+}
+
+/// Block sync response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncResponse {
+    Headers(Vec<BlockHeader>),
+    Blocks(Vec<Block>),
+    CommitCertificate(Option<CommitCertificate>),
+}
+
+impl Canonical for SyncRequest {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            SyncRequest::GetHeaders { from, count } => {
+                enc.put_u8(0);
+                enc.put_u64(*from);
+                enc.put_u64(*count);
+            }
+            SyncRequest::GetBlocks { hashes } => {
+                enc.put_u8(1);
+                enc.put_seq(hashes);
+            }
+            SyncRequest::GetCommitCertificate { height } => {
+                enc.put_u8(2);
+                enc.put_u64(*height);
+            }
+        }
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        match dec.get_u8()? {
+            0 => Ok(SyncRequest::GetHeaders {
+                from: dec.get_u64()?,
+                count: dec.get_u64()?,
+            }),
+            1 => Ok(SyncRequest::GetBlocks {
+                hashes: dec.get_seq()?,
+            }),
+            2 => Ok(SyncRequest::GetCommitCertificate {
+                height: dec.get_u64()?,
+            }),
+            tag => Err(CodecError::InvalidTag {
+                field: "sync_request",
+                tag,
+            }),
+        }
+    }
+}
+
+impl Canonical for SyncResponse {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            SyncResponse::Headers(headers) => {
+                enc.put_u8(0);
+                enc.put_seq(headers);
+            }
+            SyncResponse::Blocks(blocks) => {
+                enc.put_u8(1);
+                enc.put_seq(blocks);
+            }
+            SyncResponse::CommitCertificate(certificate) => {
+                enc.put_u8(2);
+                enc.put_option(certificate.as_ref());
+            }
+        }
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        match dec.get_u8()? {
+            0 => Ok(SyncResponse::Headers(dec.get_seq()?)),
+            1 => Ok(SyncResponse::Blocks(dec.get_seq()?)),
+            2 => Ok(SyncResponse::CommitCertificate(dec.get_option()?)),
+            tag => Err(CodecError::InvalidTag {
+                field: "sync_response",
+                tag,
+            }),
+        }
+    }
+}
+
+/// Canonical codec for the sync protocol
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncCodec;
+
+impl SyncCodec {
+    async fn read<M, T>(io: &mut T) -> io::Result<M>
+    where
+        M: Canonical,
+        T: AsyncRead + Unpin + Send,
+    {
+        let mut bytes = Vec::new();
+        io.take(MAX_SYNC_MESSAGE_SIZE as u64 + 1)
+            .read_to_end(&mut bytes)
+            .await?;
+        if bytes.len() > MAX_SYNC_MESSAGE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "sync message too large",
+            ));
+        }
+        M::from_canonical_bytes(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn write<M, T>(io: &mut T, message: &M) -> io::Result<()>
+    where
+        M: Canonical,
+        T: AsyncWrite + Unpin + Send,
+    {
+        let bytes = message.to_canonical_bytes();
+        if bytes.len() > MAX_SYNC_MESSAGE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "sync message too large",
+            ));
+        }
+        io.write_all(&bytes).await
+    }
+}
+
+#[async_trait]
+impl request_response::Codec for SyncCodec {
+    type Protocol = StreamProtocol;
+    type Request = SyncRequest;
+    type Response = SyncResponse;
+
+    async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<SyncRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        Self::read(io).await
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &StreamProtocol,
+        io: &mut T,
+    ) -> io::Result<SyncResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        Self::read(io).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &StreamProtocol,
+        io: &mut T,
+        request: SyncRequest,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        Self::write(io, &request).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &StreamProtocol,
+        io: &mut T,
+        response: SyncResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        Self::write(io, &response).await
+    }
+}
+
+/// Finalized blocks served to syncing peers
+///
+/// The network service calls it from tokio's blocking pool, so
+/// implementations may block on disk or database reads.
+pub trait BlockSource: Send + Sync {
+    /// Up to `count` consecutive finalized headers starting at `from`
+    fn headers(&self, from: u64, count: u64) -> Vec<BlockHeader>;
+
+    /// Finalized blocks with the given hashes, skipping unknown ones
+    fn blocks(&self, hashes: &[[u8; 32]]) -> Vec<Block>;
+
+    /// Commit certificate of the finalized block at `height`
+    fn certificate(&self, height: u64) -> Option<CommitCertificate>;
+}
+
+/// Answer `request` from `source`, applying the per-request caps
+pub fn respond(source: &dyn BlockSource, request: SyncRequest) -> SyncResponse {
+    match request {
+        SyncRequest::GetHeaders { from, count } => {
+            SyncResponse::Headers(source.headers(from, count.min(MAX_HEADERS_PER_REQUEST)))
+        }
+        SyncRequest::GetBlocks { mut hashes } => {
+            hashes.truncate(MAX_BLOCKS_PER_REQUEST);
+            SyncResponse::Blocks(source.blocks(&hashes))
+        }
+        SyncRequest::GetCommitCertificate { height } => {
+            SyncResponse::CommitCertificate(source.certificate(height))
+        }
+    }
+}
+
+/// Response to `request` that serves nothing
+pub fn empty(request: &SyncRequest) -> SyncResponse {
+    match request {
+        SyncRequest::GetHeaders { .. } => SyncResponse::Headers(Vec::new()),
+        SyncRequest::GetBlocks { .. } => SyncResponse::Blocks(Vec::new()),
+        SyncRequest::GetCommitCertificate { .. } => SyncResponse::CommitCertificate(None),
+    }
+}
+
+/// In-memory `BlockSource` of finalized blocks and their certificates
+#[derive(Debug, Default)]
+pub struct MemoryBlockStore {
+    inner: RwLock<StoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct StoreInner {
+    blocks: BTreeMap<u64, (Block, CommitCertificate)>,
+    heights: HashMap<[u8; 32], u64>,
+}
+
+impl MemoryBlockStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a finalized block and its certificate, replacing any block
+    /// stored at the same height
+    pub fn insert(&self, block: Block, certificate: CommitCertificate) {
+        let mut inner = self.inner.write().expect("block store poisoned");
+        let height = block.height();
+        if let Some((old, _)) = inner.blocks.get(&height) {
+            let old_hash = old.hash();
+            inner.heights.remove(&old_hash);
+        }
+        inner.heights.insert(block.hash(), height);
+        inner.blocks.insert(height, (block, certificate));
+    }
+
+    /// Highest stored height
+    pub fn tip_height(&self) -> Option<u64> {
+        let inner = self.inner.read().expect("block store poisoned");
+        inner.blocks.keys().next_back().copied()
+    }
+}
+
+impl BlockSource for MemoryBlockStore {
+    fn headers(&self, from: u64, count: u64) -> Vec<BlockHeader> {
+        let inner = self.inner.read().expect("block store poisoned");
+        (from..from.saturating_add(count))
+            .map_while(|height| inner.blocks.get(&height))
+            .map(|(block, _)| block.header.clone())
+            .collect()
+    }
+
+    fn blocks(&self, hashes: &[[u8; 32]]) -> Vec<Block> {
+        let inner = self.inner.read().expect("block store poisoned");
+        hashes
+            .iter()
+            .filter_map(|hash| inner.heights.get(hash))
+            .filter_map(|height| inner.blocks.get(height))
+            .map(|(block, _)| block.clone())
+            .collect()
+    }
+
+    fn certificate(&self, height: u64) -> Option<CommitCertificate> {
+        let inner = self.inner.read().expect("block store poisoned");
+        inner.blocks.get(&height).map(|(_, cert)| cert.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::v1::test_support::chain;
+
+    fn certificate(block: &Block) -> CommitCertificate {
+        CommitCertificate {
+            height: block.height(),
+            round: block.round(),
+            block_hash: block.hash(),
+            efficiency_score: block.header.efficiency_score,
+            signatures: vec![],
+        }
+    }
+
+    #[test]
+    fn test_sync_messages_roundtrip() {
+        let blocks = chain(2);
+        let requests = [
+            SyncRequest::GetHeaders { from: 1, count: 10 },
+            SyncRequest::GetBlocks {
+                hashes: blocks.iter().map(Block::hash).collect(),
+            },
+            SyncRequest::GetCommitCertificate { height: 7 },
+        ];
+        for request in requests {
+            let bytes = request.to_canonical_bytes();
+            assert_eq!(SyncRequest::from_canonical_bytes(&bytes).unwrap(), request);
+        }
+
+        let responses = [
+            SyncResponse::Headers(blocks.iter().map(|b| b.header.clone()).collect()),
+            SyncResponse::Blocks(blocks.clone()),
+            SyncResponse::CommitCertificate(Some(certificate(&blocks[0]))),
+            SyncResponse::CommitCertificate(None),
+        ];
+        for response in responses {
+            let bytes = response.to_canonical_bytes();
+            assert_eq!(SyncResponse::from_canonical_bytes(&bytes).unwrap(), response);
+        }
+
+        // A hash count larger than the input is refused before allocating
+        let mut enc = Encoder::new();
+        enc.put_u8(1);
+        enc.put_u64(u64::MAX);
+        assert!(matches!(
+            SyncRequest::from_canonical_bytes(&enc.into_bytes()),
+            Err(CodecError::LengthOverflow(_))
+        ));
+    }
+
+    #[test]
+    fn test_store_serves_capped_responses() {
+        let store = MemoryBlockStore::new();
+        let blocks = chain(300);
+        for block in &blocks {
+            store.insert(block.clone(), certificate(block));
+        }
+        assert_eq!(store.tip_height(), Some(300));
+
+        let SyncResponse::Headers(headers) =
+            respond(&store, SyncRequest::GetHeaders { from: 10, count: 1000 })
+        else {
+            panic!("expected headers");
+        };
+        assert_eq!(headers.len() as u64, MAX_HEADERS_PER_REQUEST);
+        assert_eq!(headers[0].height, 10);
+
+        // Headers stop at the store's tip
+        let response = respond(&store, SyncRequest::GetHeaders { from: 299, count: 10 });
+        assert!(matches!(response, SyncResponse::Headers(h) if h.len() == 2));
+
+        let hashes: Vec<[u8; 32]> = blocks.iter().rev().map(Block::hash).collect();
+        let SyncResponse::Blocks(served) = respond(&store, SyncRequest::GetBlocks { hashes })
+        else {
+            panic!("expected blocks");
+        };
+        assert_eq!(served.len(), MAX_BLOCKS_PER_REQUEST);
+        assert_eq!(served[0].height(), 300);
+
+        assert_eq!(
+            respond(&store, SyncRequest::GetCommitCertificate { height: 5 }),
+            SyncResponse::CommitCertificate(Some(certificate(&blocks[4])))
+        );
+        assert_eq!(
+            respond(&store, SyncRequest::GetCommitCertificate { height: 301 }),
+            SyncResponse::CommitCertificate(None)
+        );
+    }
+}
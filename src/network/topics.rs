@@ -0,0 +1,106 @@
+//! Gossip Topics
+//!
+//! Every constellation gossips on its own set of topics, so deployments
+//! sharing peers never see each other's traffic:
+//!
+//! ```text
+//! /self-chain/<constellation_id>/proposals/1
+//! /self-chain/<constellation_id>/votes/1
+//! /self-chain/<constellation_id>/commits/1
+//! /self-chain/<constellation_id>/transactions/1
+//! ```
+
+use crate::consensus::v1::ConsensusMessage;
+use libp2p::gossipsub::{IdentTopic, TopicHash};
+
+/// Kind of payload carried on a topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TopicKind {
+    /// `ConsensusMessage::Proposal`
+    Proposals,
+    /// `ConsensusMessage::RankedVote`
+    Votes,
+    /// `ConsensusMessage::Commit`
+    Commits,
+    /// v1 `Transaction`
+    Transactions,
+}
+
+impl TopicKind {
+    /// All topic kinds, in subscription order
+    pub const ALL: [TopicKind; 4] = [
+        TopicKind::Proposals,
+        TopicKind::Votes,
+        TopicKind::Commits,
+        TopicKind::Transactions,
+    ];
+
+    /// Topic a consensus message is published on
+    pub fn of(message: &ConsensusMessage) -> Self {
+        match message {
+            ConsensusMessage::Proposal { .. } => TopicKind::Proposals,
+            ConsensusMessage::RankedVote { .. } => TopicKind::Votes,
+            ConsensusMessage::Commit { .. } => TopicKind::Commits,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            TopicKind::Proposals => "proposals",
+            TopicKind::Votes => "votes",
+            TopicKind::Commits => "commits",
+            TopicKind::Transactions => "transactions",
+        }
+    }
+}
+
+/// The gossip topics of one constellation
+#[derive(Debug, Clone)]
+pub struct Topics {
+    topics: [IdentTopic; 4],
+}
+
+impl Topics {
+    /// Topics for `constellation_id`
+    pub fn new(constellation_id: &str) -> Self {
+        Self {
+            topics: TopicKind::ALL.map(|kind| {
+                IdentTopic::new(format!("/self-chain/{}/{}/1", constellation_id, kind.name()))
+            }),
+        }
+    }
+
+    /// Topic for `kind`
+    pub fn topic(&self, kind: TopicKind) -> &IdentTopic {
+        &self.topics[kind as usize]
+    }
+
+    /// Kind of a received topic, if it belongs to this constellation
+    pub fn kind(&self, hash: &TopicHash) -> Option<TopicKind> {
+        TopicKind::ALL
+            .into_iter()
+            .find(|kind| self.topic(*kind).hash() == *hash)
+    }
+
+    /// All topics
+    pub fn iter(&self) -> impl Iterator<Item = &IdentTopic> {
+        self.topics.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics_are_per_constellation() {
+        let topics = Topics::new("devnet");
+        let votes = topics.topic(TopicKind::Votes);
+        assert_eq!(votes.to_string(), "/self-chain/devnet/votes/1");
+        assert_eq!(topics.kind(&votes.hash()), Some(TopicKind::Votes));
+
+        let other = Topics::new("mainnet");
+        assert_eq!(topics.kind(&other.topic(TopicKind::Votes).hash()), None);
+        assert_eq!(topics.iter().count(), 4);
+    }
+}
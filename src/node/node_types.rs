@@ -5,17 +5,18 @@
 //! 2. Block Builder Nodes (Full nodes) - Build blocks and earn rewards
 //! 3. PoAI Coordinator - Organize voting and generate reference blocks
 
-use crate::blockchain::{Block, BlockHeader, BlockMeta, Transaction};
+use crate::blockchain::{v1, Block, BlockHeader, BlockMeta, Transaction};
+use crate::clock::{system_clock, SharedClock};
 use crate::consensus::{
     TransactionSelector, TransactionSelectorConfig, ConsensusMetrics, ValidationCache,
 };
-use crate::consensus::validator::Validator;
+use crate::consensus::v1::{generate_reference_block, ConsensusConfig};
+use crate::consensus::validator::{Validator, ValidatorConfig};
 use crate::crypto::{MasterKey, ValidatorKey, KeyManager};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Node type enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -59,22 +60,35 @@ pub struct ValidatorNode {
 
     /// Validator key for signing votes
     validator_key: Option<ValidatorKey>,
+
+    clock: SharedClock,
 }
 
 impl ValidatorNode {
     pub fn new(config: NodeConfig) -> Result<Self> {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Create a validator node whose votes and caches read time from `clock`
+    pub fn with_clock(config: NodeConfig, clock: SharedClock) -> Result<Self> {
         let registry = prometheus::Registry::new();
         let metrics = Arc::new(ConsensusMetrics::new(&registry)?);
-        let cache = Arc::new(ValidationCache::new(metrics.clone()));
-        let validator = Arc::new(Validator::new(metrics, cache));
+        let cache = Arc::new(ValidationCache::with_clock(metrics.clone(), clock.clone()));
+        let validator = Arc::new(Validator::with_clock(
+            ValidatorConfig::default(),
+            metrics,
+            cache,
+            clock.clone(),
+        ));
 
         Ok(Self {
             config,
-            key_manager: KeyManager::new(),
+            key_manager: KeyManager::with_clock(clock.clone()),
             validator,
             wallet_colors: HashMap::new(),
             voting_history: Vec::new(),
             validator_key: None,
+            clock,
         })
     }
 
@@ -86,7 +100,7 @@ impl ValidatorNode {
         // Derive validator key
         let nonce = rand::random::<[u8; 32]>();
         let validator_key = master_key.derive_validator_key(&nonce)?;
-        self.validator_key = Some(validator_key);
+        self.validator_key = Some(validator_key.with_clock(self.clock.clone()));
 
         tracing::info!("Validator initialized for address: {}", address);
         Ok(())
@@ -113,7 +127,7 @@ impl ValidatorNode {
             block_hash: hex::encode(block_hash),
             approve,
             signature,
-            timestamp: Self::current_timestamp(),
+            timestamp: self.clock.unix_secs(),
         };
 
         // Record in history
@@ -173,13 +187,6 @@ impl ValidatorNode {
             wallet_colors_stored: self.wallet_colors.len(),
         }
     }
-
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }
 }
 
 /// Block Builder Node (Full Node)
@@ -200,10 +207,17 @@ pub struct BlockBuilderNode {
 
     /// Builder statistics
     stats: BlockBuilderStats,
+
+    clock: SharedClock,
 }
 
 impl BlockBuilderNode {
     pub fn new(config: NodeConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Create a builder that timestamps blocks and proposals with `clock`
+    pub fn with_clock(config: NodeConfig, clock: SharedClock) -> Self {
         let selector_config = TransactionSelectorConfig::default();
         let transaction_selector = TransactionSelector::new(selector_config);
 
@@ -213,6 +227,7 @@ impl BlockBuilderNode {
             mempool: Vec::new(),
             blocks_built: Vec::new(),
             stats: BlockBuilderStats::default(),
+            clock,
         }
     }
 
@@ -243,7 +258,7 @@ impl BlockBuilderNode {
         let block = Block {
             header: BlockHeader {
                 index: self.blocks_built.len() as u64,
-                timestamp: Self::current_timestamp(),
+                timestamp: self.clock.unix_secs(),
                 previous_hash,
                 ai_threshold: 5,
             },
@@ -270,7 +285,7 @@ impl BlockBuilderNode {
             builder_id: self.config.node_id.clone(),
             block,
             efficiency: efficiency.efficiency_score,
-            timestamp: Self::current_timestamp(),
+            timestamp: self.clock.unix_secs(),
         })
     }
 
@@ -278,13 +293,6 @@ impl BlockBuilderNode {
     pub fn get_stats(&self) -> &BlockBuilderStats {
         &self.stats
     }
-
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }
 }
 
 /// PoAI Coordinator Node
@@ -296,7 +304,7 @@ impl BlockBuilderNode {
 /// - Cannot influence voting results
 pub struct CoordinatorNode {
     config: NodeConfig,
-    transaction_selector: TransactionSelector,
+    consensus_config: ConsensusConfig,
 
     /// Active voting round
     current_round: Option<VotingRound>,
@@ -305,45 +313,50 @@ pub struct CoordinatorNode {
     pub completed_rounds: Vec<VotingRound>,
 
     /// Reference block for current round
-    reference_block: Option<Block>,
+    reference_block: Option<v1::Block>,
+
+    clock: SharedClock,
 }
 
 impl CoordinatorNode {
     pub fn new(config: NodeConfig) -> Self {
-        let selector_config = TransactionSelectorConfig::default();
-        let transaction_selector = TransactionSelector::new(selector_config);
+        Self::with_clock(config, system_clock())
+    }
 
+    /// Create a coordinator that timestamps rounds with `clock`
+    pub fn with_clock(config: NodeConfig, clock: SharedClock) -> Self {
         Self {
             config,
-            transaction_selector,
+            consensus_config: ConsensusConfig::default(),
             current_round: None,
             completed_rounds: Vec::new(),
             reference_block: None,
+            clock,
         }
     }
 
-    /// Start a new voting round
+    /// Generate reference blocks with `consensus_config`
+    pub fn with_consensus_config(mut self, consensus_config: ConsensusConfig) -> Self {
+        self.consensus_config = consensus_config;
+        self
+    }
+
+    /// Start a new voting round for `height` on top of `parent`
+    ///
+    /// The reference block is the one every validator derives with
+    /// `generate_reference_block` from the same mempool snapshot, so
+    /// `reference_efficiency` is the integer score proposals are checked
+    /// against.
     pub fn start_voting_round(
         &mut self,
         proposals: Vec<BlockProposal>,
-        mempool: Vec<Transaction>,
-        previous_hash: String,
+        mempool: &[v1::Transaction],
+        height: u64,
+        parent: &v1::BlockHeader,
     ) -> Result<VotingRound> {
-        // Generate reference block using same algorithm as builders
-        let selected = self.transaction_selector.select_transactions(mempool)?;
-        let efficiency = self.transaction_selector.calculate_block_efficiency(&selected)?;
-
-        let reference_block = Block {
-            header: BlockHeader {
-                index: 0,
-                timestamp: Self::current_timestamp(),
-                previous_hash,
-                ai_threshold: 5,
-            },
-            transactions: selected.into_transactions(),
-            meta: BlockMeta::default(),
-            hash: String::new(),
-        };
+        let reference_block =
+            generate_reference_block(mempool, height, parent, &self.consensus_config);
+        let reference_efficiency = reference_block.header.efficiency_score;
 
         self.reference_block = Some(reference_block.clone());
 
@@ -351,9 +364,9 @@ impl CoordinatorNode {
             round_id: self.completed_rounds.len() as u64,
             proposals,
             reference_block,
-            reference_efficiency: efficiency.efficiency_score,
+            reference_efficiency,
             votes: HashMap::new(),
-            started_at: Self::current_timestamp(),
+            started_at: self.clock.unix_secs(),
             ended_at: None,
             winner: None,
         };
@@ -390,7 +403,7 @@ impl CoordinatorNode {
             .max_by_key(|(_, count)| *count)
             .map(|(hash, _)| hash.clone());
 
-        round.ended_at = Some(Self::current_timestamp());
+        round.ended_at = Some(self.clock.unix_secs());
         round.winner = winner.clone();
 
         self.completed_rounds.push(round);
@@ -401,13 +414,6 @@ impl CoordinatorNode {
             total_votes: vote_counts.values().sum(),
         })
     }
-
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-    }
 }
 
 /// Vote from a validator
@@ -462,8 +468,9 @@ pub struct BlockBuilderStats {
 pub struct VotingRound {
     pub round_id: u64,
     pub proposals: Vec<BlockProposal>,
-    pub reference_block: Block,
-    pub reference_efficiency: f64,
+    #[serde(with = "v1::codec::serde_canonical")]
+    pub reference_block: v1::Block,
+    pub reference_efficiency: u64,
     pub votes: HashMap<String, Vote>,
     pub started_at: u64,
     pub ended_at: Option<u64>,
@@ -481,6 +488,8 @@ pub struct VotingResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
+    use std::time::Duration;
 
     #[test]
     fn test_block_builder_creation() {
@@ -509,4 +518,65 @@ mod tests {
         assert!(coordinator.current_round.is_none());
         assert!(coordinator.completed_rounds.is_empty());
     }
+
+    #[test]
+    fn test_coordinator_reference_matches_validators() {
+        let config = NodeConfig {
+            node_id: "coordinator1".to_string(),
+            node_type: NodeType::Coordinator,
+            listen_addr: "127.0.0.1:10001".to_string(),
+            bootstrap_peers: vec![],
+        };
+        let mempool: Vec<v1::Transaction> = (0..20)
+            .map(|i| {
+                let sender = format!("s{}", i);
+                v1::Transaction::new(1, "c".to_string(), sender, None, vec![], 900 + i * 7, 100 + i)
+            })
+            .collect();
+        let mut parent = v1::BlockHeader::genesis("c");
+        parent.timestamp = 1704067200;
+
+        // The coordinator's clock has no say in the reference block
+        let clock = MockClock::from_unix_secs(1800000000);
+        let mut coordinator = CoordinatorNode::with_clock(config, clock.shared());
+        let round = coordinator.start_voting_round(vec![], &mempool, 1, &parent).unwrap();
+
+        let expected = generate_reference_block(&mempool, 1, &parent, &ConsensusConfig::default());
+        assert_eq!(round.reference_block, expected);
+        assert_eq!(round.reference_efficiency, expected.header.efficiency_score);
+        assert_eq!(round.reference_block.header.height, 1);
+        assert_eq!(round.started_at, 1800000000);
+
+        let json = serde_json::to_string(&round).unwrap();
+        let restored: VotingRound = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.reference_block, expected);
+    }
+
+    #[tokio::test]
+    async fn test_validator_votes_use_injected_clock() {
+        let config = NodeConfig {
+            node_id: "validator1".to_string(),
+            node_type: NodeType::Validator,
+            listen_addr: "127.0.0.1:8001".to_string(),
+            bootstrap_peers: vec![],
+        };
+        let clock = MockClock::from_unix_secs(1704067200);
+        let mut node = ValidatorNode::with_clock(config, clock.shared()).unwrap();
+        node.initialize_with_master_key(MasterKey::generate().unwrap()).unwrap();
+
+        let block = Block {
+            header: BlockHeader {
+                index: 1,
+                timestamp: 1704067200,
+                previous_hash: "0000000000".to_string(),
+                ai_threshold: 5,
+            },
+            transactions: vec![],
+            meta: BlockMeta::default(),
+            hash: "test_block_hash".to_string(),
+        };
+        clock.advance(Duration::from_secs(30));
+        let vote = node.vote_on_block(&block, true).await.unwrap();
+        assert_eq!(vote.timestamp, 1704067230);
+    }
 }
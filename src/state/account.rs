@@ -0,0 +1,187 @@
+//! Account State
+//!
+//! The account model from the "State Machine" section of
+//! `docs/POAI_SPECIFICATION.md`.
+
+use crate::blockchain::v1::codec::{
+    domain_hash, Canonical, CodecError, CodecResult, Decoder, Encoder,
+};
+use std::collections::BTreeMap;
+
+/// Color assigned to wallets that have never transacted
+pub const INITIAL_COLOR: &str = "000000";
+
+/// Account state committed to by `state_root`
+///
+/// ## Canonical Encoding Order
+///
+/// 1. `address` (string, length-prefixed)
+/// 2. `constellation_id` (string, length-prefixed)
+/// 3. `native_coin_balance` (Option<u64>)
+/// 4. `token_balances` (count, then `(token_id, balance)` pairs in ascending key order)
+/// 5. `nonce` (u64, little-endian)
+/// 6. `color` (string, length-prefixed)
+/// 7. `is_validator` (u8: 0 or 1)
+/// 8. `bond_amount` (Option<u64>)
+///
+/// `token_balances` is a `BTreeMap` (not the spec's `HashMap`) so that the
+/// encoding, and therefore the state root, is deterministic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// Account address (hex)
+    pub address: String,
+
+    /// Constellation this account belongs to
+    pub constellation_id: String,
+
+    /// Native coin balance (if the constellation has one)
+    pub native_coin_balance: Option<u64>,
+
+    /// Application token balances (token ID -> balance)
+    pub token_balances: BTreeMap<String, u64>,
+
+    /// Nonce of the last applied transaction (0 = none yet)
+    pub nonce: u64,
+
+    /// HEX wallet color (6 chars)
+    pub color: String,
+
+    /// Validator eligibility
+    pub is_validator: bool,
+
+    /// Bond amount (if validator)
+    pub bond_amount: Option<u64>,
+}
+
+impl Account {
+    /// Domain separation prefix for account value hashes
+    pub const DOMAIN_PREFIX: &'static [u8] = b"self-chain-account-v1";
+
+    /// Create an empty account with the initial wallet color
+    pub fn new(address: String, constellation_id: String) -> Self {
+        Self {
+            address,
+            constellation_id,
+            native_coin_balance: None,
+            token_balances: BTreeMap::new(),
+            nonce: 0,
+            color: INITIAL_COLOR.to_string(),
+            is_validator: false,
+            bond_amount: None,
+        }
+    }
+
+    /// Native coin balance, treating `None` as zero
+    pub fn native_balance(&self) -> u64 {
+        self.native_coin_balance.unwrap_or(0)
+    }
+
+    /// Token balance, treating missing tokens as zero
+    pub fn token_balance(&self, token_id: &str) -> u64 {
+        self.token_balances.get(token_id).copied().unwrap_or(0)
+    }
+
+    /// Value hash stored in the state tree leaf
+    pub fn hash(&self) -> [u8; 32] {
+        domain_hash(Self::DOMAIN_PREFIX, &self.to_canonical_bytes())
+    }
+}
+
+impl Canonical for Account {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_str(&self.address);
+        enc.put_str(&self.constellation_id);
+        enc.put_option(self.native_coin_balance.as_ref());
+        enc.put_u64(self.token_balances.len() as u64);
+        for (token_id, balance) in &self.token_balances {
+            enc.put_str(token_id);
+            enc.put_u64(*balance);
+        }
+        enc.put_u64(self.nonce);
+        enc.put_str(&self.color);
+        enc.put_bool(self.is_validator);
+        enc.put_option(self.bond_amount.as_ref());
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        let address = dec.get_string()?;
+        let constellation_id = dec.get_string()?;
+        let native_coin_balance = dec.get_option()?;
+
+        let count = dec.get_u64()?;
+        let mut token_balances = BTreeMap::new();
+        let mut previous: Option<String> = None;
+        for _ in 0..count {
+            let token_id = dec.get_string()?;
+            if previous.as_ref().is_some_and(|p| *p >= token_id) {
+                return Err(CodecError::NonCanonical(
+                    "token balances must be in strictly ascending order".to_string(),
+                ));
+            }
+            let balance = dec.get_u64()?;
+            previous = Some(token_id.clone());
+            token_balances.insert(token_id, balance);
+        }
+
+        Ok(Self {
+            address,
+            constellation_id,
+            native_coin_balance,
+            token_balances,
+            nonce: dec.get_u64()?,
+            color: dec.get_string()?,
+            is_validator: dec.get_bool()?,
+            bond_amount: dec.get_option()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_account() {
+        let account = Account::new("a1b2".to_string(), "self-app".to_string());
+
+        assert_eq!(account.nonce, 0);
+        assert_eq!(account.color, INITIAL_COLOR);
+        assert_eq!(account.native_balance(), 0);
+        assert_eq!(account.token_balance("SELF"), 0);
+    }
+
+    #[test]
+    fn test_canonical_roundtrip() {
+        let mut account = Account::new("a1b2".to_string(), "self-app".to_string());
+        account.native_coin_balance = Some(500);
+        account.token_balances.insert("b-token".to_string(), 2);
+        account.token_balances.insert("a-token".to_string(), 1);
+        account.is_validator = true;
+        account.bond_amount = Some(100);
+
+        let bytes = account.to_canonical_bytes();
+        assert_eq!(Account::from_canonical_bytes(&bytes).unwrap(), account);
+    }
+
+    #[test]
+    fn test_unsorted_tokens_rejected() {
+        let mut enc = Encoder::new();
+        enc.put_str("a1b2");
+        enc.put_str("self-app");
+        enc.put_u8(0);
+        enc.put_u64(2);
+        enc.put_str("b");
+        enc.put_u64(1);
+        enc.put_str("a");
+        enc.put_u64(1);
+        enc.put_u64(0);
+        enc.put_str(INITIAL_COLOR);
+        enc.put_bool(false);
+        enc.put_u8(0);
+
+        assert!(matches!(
+            Account::from_canonical_bytes(&enc.into_bytes()),
+            Err(CodecError::NonCanonical(_))
+        ));
+    }
+}
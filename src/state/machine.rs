@@ -0,0 +1,649 @@
+//! Account State Machine
+//!
+//! Applies `v1::Transaction`s and `v1::Block`s to the account state following
+//! the "Deterministic Rules" and "Block Application" sections of
+//! `docs/POAI_SPECIFICATION.md`.
+//!
+//! ## Transaction Rules
+//!
+//! A transaction is applied only if, in this order:
+//!
+//! 1. Its canonical encoding is at most `MAX_TX_SIZE` bytes
+//! 2. `point_price > 0`
+//! 3. `chain_id` matches the state machine's chain
+//! 4. The Ed25519 signature verifies against `public_key`
+//! 5. `sender` is the address of `public_key` (`crypto::derive_address`)
+//! 6. `nonce == sender.nonce + 1`
+//! 7. For transfers, `data` decodes as a `TransferPayload`
+//! 8. The sender can pay `amount + point_price` (fee in native coin)
+//!
+//! Applying it bumps the sender's nonce, deducts the fee, moves the transfer
+//! amount (creating the recipient account if needed) and advances the
+//! sender's wallet color by the transaction's HEX (see `consensus::Validator`).
+//! Fees are removed from circulation; distributing them is left to the
+//! constellation's reward mechanism.
+//!
+//! ## Atomicity
+//!
+//! Changes are staged in an overlay and written to the tree in a single
+//! batch. A failing transaction leaves the state untouched, and a block is
+//! committed only if every transaction applies and the resulting root matches
+//! `header.state_root`. That root, like `compute_state_root` and `dry_run`,
+//! comes from `SparseMerkleTree::root_after`, which hashes only the changed
+//! paths over the cached tree instead of copying it.
+//!
+//! ```rust,ignore
+//! use self_chain_core::state::StateMachine;
+//!
+//! let mut state = StateMachine::with_genesis("self-chain-mainnet", genesis_accounts);
+//! let root = state.apply_block(&block)?;
+//! assert_eq!(root.root_hash, block.header.state_root);
+//! ```
+
+use super::account::Account;
+use super::smt::SparseMerkleTree;
+use super::transfer::TransferPayload;
+use super::StateRoot;
+use crate::blockchain::v1::codec::Canonical;
+use crate::blockchain::v1::{Block, Transaction};
+use crate::consensus::v1::constants::MAX_TX_SIZE;
+use crate::consensus::validator::Validator;
+use crate::crypto::derive_address;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Errors returned when applying transactions or blocks
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum StateError {
+    #[error("Transaction too large: {size} bytes (max {max})")]
+    TransactionTooLarge { size: usize, max: usize },
+
+    #[error("PointPrice must be greater than zero")]
+    ZeroPointPrice,
+
+    #[error("Chain ID mismatch: expected {expected}, got {actual}")]
+    ChainIdMismatch { expected: String, actual: String },
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Sender {sender} is not the address of the signing key")]
+    SenderKeyMismatch { sender: String },
+
+    #[error("Invalid nonce for {address}: expected {expected}, got {actual}")]
+    InvalidNonce { address: String, expected: u64, actual: u64 },
+
+    #[error("Invalid transfer payload: {0}")]
+    InvalidPayload(String),
+
+    #[error("Insufficient {asset} balance for {address}: required {required}, available {available}")]
+    InsufficientBalance {
+        address: String,
+        asset: String,
+        required: u64,
+        available: u64,
+    },
+
+    #[error("Balance overflow for {address}")]
+    BalanceOverflow { address: String },
+
+    #[error("Invalid wallet color: {0}")]
+    InvalidColor(String),
+
+    #[error("Wrong block height: expected {expected}, got {actual}")]
+    WrongHeight { expected: u64, actual: u64 },
+
+    #[error("Transaction {index} failed: {source}")]
+    TransactionFailed {
+        index: usize,
+        #[source]
+        source: Box<StateError>,
+    },
+
+    #[error("State root mismatch: header {}, computed {}", hex::encode(.expected), hex::encode(.computed))]
+    StateRootMismatch { expected: [u8; 32], computed: [u8; 32] },
+}
+
+/// Result type for state transitions
+pub type StateResult<T> = Result<T, StateError>;
+
+/// Asset name used in balance errors for the native coin
+const NATIVE_ASSET: &str = "native";
+
+/// Account state for one constellation chain
+#[derive(Debug, Clone)]
+pub struct StateMachine {
+    /// Chain identifier transactions must carry
+    chain_id: String,
+
+    /// Committed account state
+    tree: SparseMerkleTree,
+
+    /// Height of the last applied block (0 = genesis)
+    height: u64,
+}
+
+impl StateMachine {
+    /// Create an empty state at genesis
+    pub fn new(chain_id: impl Into<String>) -> Self {
+        Self::with_genesis(chain_id, Vec::new())
+    }
+
+    /// Create a state at genesis holding the given accounts
+    pub fn with_genesis(
+        chain_id: impl Into<String>,
+        accounts: impl IntoIterator<Item = Account>,
+    ) -> Self {
+        Self {
+            chain_id: chain_id.into(),
+            tree: SparseMerkleTree::from_accounts(accounts),
+            height: 0,
+        }
+    }
+
+    /// Chain identifier
+    pub fn chain_id(&self) -> &str {
+        &self.chain_id
+    }
+
+    /// Height of the last applied block
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Current state commitment
+    pub fn state_root(&self) -> StateRoot {
+        StateRoot {
+            root_hash: self.tree.root(),
+            height: self.height,
+        }
+    }
+
+    /// Underlying tree (for proofs)
+    pub fn tree(&self) -> &SparseMerkleTree {
+        &self.tree
+    }
+
+    /// Look up an account by address
+    pub fn account(&self, address: &str) -> Option<&Account> {
+        self.tree.get(address)
+    }
+
+    /// Apply a single transaction outside of a block
+    ///
+    /// Returns the new root hash. On error the state is unchanged.
+    pub fn apply_transaction(&mut self, tx: &Transaction) -> StateResult<[u8; 32]> {
+        let mut overlay = Overlay::new(&self.tree);
+        self.stage_transaction(&mut overlay, tx)?;
+        let changes = overlay.into_changes();
+        Ok(self.tree.apply_batch(changes))
+    }
+
+    /// Compute the state root a list of transactions would produce
+    ///
+    /// Block builders use this to fill `header.state_root`. The state is not
+    /// modified.
+    pub fn compute_state_root(&self, transactions: &[Transaction]) -> StateResult<[u8; 32]> {
+        Ok(self.tree.root_after(self.execute(transactions)?))
+    }
+
+    /// Execute transactions without committing, collecting every failure
+    ///
+    /// Unlike `compute_state_root`, a failing transaction is skipped rather
+    /// than aborting, so validators can report all bad transactions in a
+    /// block at once. `state_root` reflects only the transactions that
+    /// applied.
+    pub fn dry_run(&self, transactions: &[Transaction]) -> DryRun {
+        let mut overlay = Overlay::new(&self.tree);
+        let mut failures = Vec::new();
+        for (index, tx) in transactions.iter().enumerate() {
+            if let Err(e) = self.stage_transaction(&mut overlay, tx) {
+                failures.push((index, e));
+            }
+        }
+
+        DryRun {
+            state_root: self.tree.root_after(overlay.into_changes()),
+            failures,
+        }
+    }
+
+    /// Apply a block atomically
+    ///
+    /// The block must be the next height on this chain. Every transaction must
+    /// apply and the resulting root must equal `header.state_root`; otherwise
+    /// the state is unchanged.
+    pub fn apply_block(&mut self, block: &Block) -> StateResult<StateRoot> {
+        let expected_height = self.height + 1;
+        if block.header.height != expected_height {
+            return Err(StateError::WrongHeight {
+                expected: expected_height,
+                actual: block.header.height,
+            });
+        }
+        if block.header.chain_id != self.chain_id {
+            return Err(StateError::ChainIdMismatch {
+                expected: self.chain_id.clone(),
+                actual: block.header.chain_id.clone(),
+            });
+        }
+
+        let changes = self.execute(&block.transactions)?;
+        let computed = self.tree.root_after(changes.iter().cloned());
+        if computed != block.header.state_root {
+            return Err(StateError::StateRootMismatch {
+                expected: block.header.state_root,
+                computed,
+            });
+        }
+
+        self.tree.apply_batch(changes);
+        self.height = block.header.height;
+        Ok(self.state_root())
+    }
+
+    /// Stage transactions and return the account changes they make
+    fn execute(&self, transactions: &[Transaction]) -> StateResult<Vec<(String, Option<Account>)>> {
+        let mut overlay = Overlay::new(&self.tree);
+        for (index, tx) in transactions.iter().enumerate() {
+            self.stage_transaction(&mut overlay, tx)
+                .map_err(|e| StateError::TransactionFailed {
+                    index,
+                    source: Box::new(e),
+                })?;
+        }
+        Ok(overlay.into_changes())
+    }
+
+    /// Validate a transaction and stage its effects in the overlay
+    ///
+    /// Nothing is written to the overlay unless every check passes.
+    fn stage_transaction(&self, overlay: &mut Overlay<'_>, tx: &Transaction) -> StateResult<()> {
+        let size = tx.to_canonical_bytes().len();
+        if size > MAX_TX_SIZE {
+            return Err(StateError::TransactionTooLarge { size, max: MAX_TX_SIZE });
+        }
+        if tx.point_price == 0 {
+            return Err(StateError::ZeroPointPrice);
+        }
+        if tx.chain_id != self.chain_id {
+            return Err(StateError::ChainIdMismatch {
+                expected: self.chain_id.clone(),
+                actual: tx.chain_id.clone(),
+            });
+        }
+        tx.verify_signature()
+            .map_err(|e| StateError::InvalidSignature(e.to_string()))?;
+        if tx.sender != derive_address(&tx.public_key) {
+            return Err(StateError::SenderKeyMismatch { sender: tx.sender.clone() });
+        }
+
+        let mut sender = overlay
+            .get(&tx.sender)
+            .unwrap_or_else(|| Account::new(tx.sender.clone(), String::new()));
+
+        let expected_nonce = sender.nonce.checked_add(1).ok_or_else(|| StateError::InvalidNonce {
+            address: tx.sender.clone(),
+            expected: u64::MAX,
+            actual: tx.nonce,
+        })?;
+        if tx.nonce != expected_nonce {
+            return Err(StateError::InvalidNonce {
+                address: tx.sender.clone(),
+                expected: expected_nonce,
+                actual: tx.nonce,
+            });
+        }
+
+        let transfer = match &tx.recipient {
+            Some(recipient) => {
+                let payload = TransferPayload::from_canonical_bytes(&tx.data)
+                    .map_err(|e| StateError::InvalidPayload(e.to_string()))?;
+                Some((recipient, payload))
+            }
+            None => None,
+        };
+
+        // Debit the sender: fee always in native coin, amount in the payload's asset
+        let mut native_debit = tx.point_price;
+        if let Some((_, payload)) = &transfer {
+            match &payload.token_id {
+                None => {
+                    native_debit = native_debit.checked_add(payload.amount).ok_or_else(|| {
+                        StateError::BalanceOverflow { address: tx.sender.clone() }
+                    })?;
+                }
+                Some(token_id) => {
+                    let available = sender.token_balance(token_id);
+                    if available < payload.amount {
+                        return Err(StateError::InsufficientBalance {
+                            address: tx.sender.clone(),
+                            asset: token_id.clone(),
+                            required: payload.amount,
+                            available,
+                        });
+                    }
+                    sender.token_balances.insert(token_id.clone(), available - payload.amount);
+                }
+            }
+        }
+
+        let available = sender.native_balance();
+        if available < native_debit {
+            return Err(StateError::InsufficientBalance {
+                address: tx.sender.clone(),
+                asset: NATIVE_ASSET.to_string(),
+                required: native_debit,
+                available,
+            });
+        }
+        sender.native_coin_balance = Some(available - native_debit);
+        sender.nonce = expected_nonce;
+
+        let hex_tx = Validator::hex_transaction_from_hash(&hex::encode(tx.hash()))
+            .map_err(|e| StateError::InvalidColor(e.to_string()))?;
+        sender.color = Validator::next_color(&sender.color, &hex_tx)
+            .map_err(|e| StateError::InvalidColor(e.to_string()))?;
+
+        // Credit the recipient (which may be the sender itself)
+        let credited = match &transfer {
+            Some((recipient, payload)) => {
+                let mut account = if *recipient == &tx.sender {
+                    sender.clone()
+                } else {
+                    overlay.get(recipient).unwrap_or_else(|| {
+                        Account::new((*recipient).clone(), sender.constellation_id.clone())
+                    })
+                };
+                credit(&mut account, payload)?;
+                Some(account)
+            }
+            None => None,
+        };
+
+        match credited {
+            Some(account) if account.address == sender.address => overlay.put(account),
+            Some(account) => {
+                overlay.put(sender);
+                overlay.put(account);
+            }
+            None => overlay.put(sender),
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of `StateMachine::dry_run`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRun {
+    /// Root after applying every transaction that succeeded
+    pub state_root: [u8; 32],
+
+    /// `(index, error)` for each transaction that failed
+    pub failures: Vec<(usize, StateError)>,
+}
+
+impl DryRun {
+    /// Whether every transaction applied
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Add a transfer amount to an account
+fn credit(account: &mut Account, payload: &TransferPayload) -> StateResult<()> {
+    let overflow = || StateError::BalanceOverflow {
+        address: account.address.clone(),
+    };
+
+    match &payload.token_id {
+        None => {
+            let balance = account
+                .native_balance()
+                .checked_add(payload.amount)
+                .ok_or_else(overflow)?;
+            account.native_coin_balance = Some(balance);
+        }
+        Some(token_id) => {
+            let balance = account
+                .token_balance(token_id)
+                .checked_add(payload.amount)
+                .ok_or_else(overflow)?;
+            account.token_balances.insert(token_id.clone(), balance);
+        }
+    }
+    Ok(())
+}
+
+/// Uncommitted account changes layered over the tree
+struct Overlay<'a> {
+    base: &'a SparseMerkleTree,
+    changes: BTreeMap<String, Account>,
+}
+
+impl<'a> Overlay<'a> {
+    fn new(base: &'a SparseMerkleTree) -> Self {
+        Self {
+            base,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, address: &str) -> Option<Account> {
+        self.changes
+            .get(address)
+            .or_else(|| self.base.get(address))
+            .cloned()
+    }
+
+    fn put(&mut self, account: Account) {
+        self.changes.insert(account.address.clone(), account);
+    }
+
+    fn into_changes(self) -> Vec<(String, Option<Account>)> {
+        self.changes
+            .into_iter()
+            .map(|(address, account)| (address, Some(account)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::v1::BlockHeader;
+    use crate::crypto::common::traits::KeyPair;
+    use crate::crypto::Ed25519Keys;
+
+    const CHAIN: &str = "test-chain";
+
+    fn funded(address: &str, balance: u64) -> Account {
+        let mut account = Account::new(address.to_string(), "self-app".to_string());
+        account.native_coin_balance = Some(balance);
+        account
+    }
+
+    /// Fresh key pair and its address
+    fn wallet() -> (Ed25519Keys, String) {
+        let keys = Ed25519Keys::new().unwrap();
+        let address = derive_address(keys.public_key());
+        (keys, address)
+    }
+
+    fn transfer(
+        keys: &Ed25519Keys,
+        nonce: u64,
+        to: &str,
+        payload: TransferPayload,
+        fee: u64,
+    ) -> Transaction {
+        let mut tx = Transaction::new(
+            nonce,
+            CHAIN.to_string(),
+            derive_address(keys.public_key()),
+            Some(to.to_string()),
+            payload.to_canonical_bytes(),
+            fee,
+            1704067200,
+        );
+        tx.sign(keys).unwrap();
+        tx
+    }
+
+    fn block_at(height: u64, state_root: [u8; 32], transactions: Vec<Transaction>) -> Block {
+        let mut header = BlockHeader::genesis(CHAIN);
+        header.height = height;
+        header.state_root = state_root;
+        Block::new(header, transactions)
+    }
+
+    #[test]
+    fn test_apply_transfer() {
+        let (keys, alice) = wallet();
+        let mut state = StateMachine::with_genesis(CHAIN, [funded(&alice, 1_000)]);
+
+        let tx = transfer(&keys, 1, "bob", TransferPayload::native(300), 10);
+        state.apply_transaction(&tx).unwrap();
+
+        let alice = state.account(&alice).unwrap();
+        assert_eq!(alice.native_balance(), 690);
+        assert_eq!(alice.nonce, 1);
+        assert_ne!(alice.color, "000000");
+
+        let bob = state.account("bob").unwrap();
+        assert_eq!(bob.native_balance(), 300);
+        assert_eq!(bob.constellation_id, "self-app");
+        assert_eq!(bob.nonce, 0);
+    }
+
+    #[test]
+    fn test_rule_violations_leave_state_unchanged() {
+        let (keys, alice) = wallet();
+        let mut state = StateMachine::with_genesis(CHAIN, [funded(&alice, 100)]);
+        let root = state.state_root();
+
+        // A validly signed transaction from a foreign key cannot spend alice's funds
+        let (mallory, _) = wallet();
+        let mut theft = Transaction::new(
+            1,
+            CHAIN.to_string(),
+            alice.clone(),
+            Some("mallory".to_string()),
+            TransferPayload::native(90).to_canonical_bytes(),
+            1,
+            1,
+        );
+        theft.sign(&mallory).unwrap();
+        theft.verify_signature().unwrap();
+        assert!(matches!(
+            state.apply_transaction(&theft),
+            Err(StateError::SenderKeyMismatch { sender }) if sender == alice
+        ));
+
+        let stale = transfer(&keys, 2, "bob", TransferPayload::native(1), 1);
+        assert!(matches!(
+            state.apply_transaction(&stale),
+            Err(StateError::InvalidNonce { expected: 1, actual: 2, .. })
+        ));
+
+        let too_much = transfer(&keys, 1, "bob", TransferPayload::native(100), 1);
+        assert!(matches!(
+            state.apply_transaction(&too_much),
+            Err(StateError::InsufficientBalance { required: 101, available: 100, .. })
+        ));
+
+        let free = transfer(&keys, 1, "bob", TransferPayload::native(1), 0);
+        assert_eq!(state.apply_transaction(&free), Err(StateError::ZeroPointPrice));
+
+        let mut tampered = transfer(&keys, 1, "bob", TransferPayload::native(1), 1);
+        tampered.recipient = Some("mallory".to_string());
+        assert!(matches!(
+            state.apply_transaction(&tampered),
+            Err(StateError::InvalidSignature(_))
+        ));
+
+        let mut garbage = Transaction::new(
+            1,
+            CHAIN.to_string(),
+            alice.clone(),
+            Some("bob".to_string()),
+            vec![1],
+            1,
+            1,
+        );
+        garbage.sign(&keys).unwrap();
+        assert!(matches!(
+            state.apply_transaction(&garbage),
+            Err(StateError::InvalidPayload(_))
+        ));
+
+        assert_eq!(state.state_root(), root);
+    }
+
+    #[test]
+    fn test_token_transfer_and_self_transfer() {
+        let (keys, address) = wallet();
+        let mut alice = funded(&address, 10);
+        alice.token_balances.insert("SELF".to_string(), 50);
+        let mut state = StateMachine::with_genesis(CHAIN, [alice]);
+
+        state
+            .apply_transaction(&transfer(&keys, 1, "bob", TransferPayload::token("SELF", 20), 1))
+            .unwrap();
+        state
+            .apply_transaction(&transfer(&keys, 2, &address, TransferPayload::native(5), 1))
+            .unwrap();
+
+        let alice = state.account(&address).unwrap();
+        assert_eq!(alice.token_balance("SELF"), 30);
+        assert_eq!(alice.native_balance(), 8);
+        assert_eq!(alice.nonce, 2);
+        assert_eq!(state.account("bob").unwrap().token_balance("SELF"), 20);
+    }
+
+    #[test]
+    fn test_apply_block_atomic() {
+        let (keys, alice) = wallet();
+        let mut state = StateMachine::with_genesis(CHAIN, [funded(&alice, 1_000)]);
+        let genesis = state.state_root();
+
+        let txs = vec![
+            transfer(&keys, 1, "bob", TransferPayload::native(100), 1),
+            transfer(&keys, 2, "carol", TransferPayload::native(100), 1),
+        ];
+        let expected = state.compute_state_root(&txs).unwrap();
+        assert_eq!(state.state_root(), genesis);
+
+        // Wrong header root is rejected without applying anything
+        let bad = block_at(1, [9u8; 32], txs.clone());
+        assert!(matches!(state.apply_block(&bad), Err(StateError::StateRootMismatch { .. })));
+        assert_eq!(state.state_root(), genesis);
+
+        // A failing transaction rejects the whole block
+        let mut failing = txs.clone();
+        failing.push(transfer(&keys, 9, "dave", TransferPayload::native(1), 1));
+        assert!(matches!(
+            state.apply_block(&block_at(1, expected, failing)),
+            Err(StateError::TransactionFailed { index: 2, .. })
+        ));
+        assert_eq!(state.state_root(), genesis);
+
+        let root = state.apply_block(&block_at(1, expected, txs)).unwrap();
+        assert_eq!(root, StateRoot { root_hash: expected, height: 1 });
+        assert_eq!(state.account(&alice).unwrap().native_balance(), 798);
+
+        let run = state.dry_run(&[
+            transfer(&keys, 5, "bob", TransferPayload::native(1), 1),
+            transfer(&keys, 3, "bob", TransferPayload::native(1), 1),
+            transfer(&keys, 4, "bob", TransferPayload::native(10_000), 1),
+        ]);
+        assert_eq!(run.failures.len(), 2);
+        assert_eq!(run.failures[0].0, 0);
+        assert_eq!(run.failures[1].0, 2);
+
+        assert!(matches!(
+            state.apply_block(&block_at(3, expected, vec![])),
+            Err(StateError::WrongHeight { expected: 2, actual: 3 })
+        ));
+    }
+}
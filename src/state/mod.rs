@@ -0,0 +1,38 @@
+//! Account State
+//!
+//! Implements the "State Machine" section of `docs/POAI_SPECIFICATION.md`:
+//! accounts with constellation-specific balances, committed to by a Sparse
+//! Merkle Tree whose root is `v1::BlockHeader.state_root`.
+//!
+//! ## State Transitions
+//!
+//! `StateMachine` applies v1 transactions and blocks atomically, enforcing the
+//! spec's nonce, balance, fee and size rules and checking the resulting root
+//! against `header.state_root`. Transfers carry a `TransferPayload` in
+//! `Transaction.data`.
+//!
+//! ## Light Clients
+//!
+//! `SparseMerkleTree::prove` returns an `SmtProof` that lets a light client
+//! holding only a trusted `state_root` verify an account's balances, nonce and
+//! wallet color, or that an address has no account at all.
+
+pub mod account;
+pub mod machine;
+pub mod smt;
+pub mod transfer;
+
+pub use account::{Account, INITIAL_COLOR};
+pub use machine::{DryRun, StateError, StateMachine, StateResult};
+pub use smt::{SmtLeaf, SmtProof, SparseMerkleTree};
+pub use transfer::TransferPayload;
+
+/// State commitment at a given block height
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateRoot {
+    /// SMT root hash
+    pub root_hash: [u8; 32],
+
+    /// Block height this root represents
+    pub height: u64,
+}
@@ -0,0 +1,612 @@
+//! Sparse Merkle Tree
+//!
+//! Commits to every account in a constellation under a single 32-byte
+//! `state_root` (see "State Root (Sparse Merkle Tree)" in
+//! `docs/POAI_SPECIFICATION.md`).
+//!
+//! ## Tree Construction
+//!
+//! The tree has 256 levels, one per bit of the account key:
+//!
+//! ```text
+//! key   = SHA256("self-chain-smt-key-v1" || address)
+//! leaf  = SHA256(0x00 || key || account_hash)
+//! node  = SHA256(0x01 || left || right)
+//! empty = [0u8; 32]
+//! ```
+//!
+//! Bit `i` of the key (most significant bit of byte 0 first) selects the
+//! left (0) or right (1) child at depth `i`. A subtree holding exactly one
+//! account is replaced by that account's leaf hash, so the tree is only as
+//! deep as needed to separate keys (typically ~log2(n) levels) and a proof
+//! carries one sibling per level on the path.
+//!
+//! ## Proofs
+//!
+//! An `SmtProof` proves either that an account is present with a given value
+//! (membership) or that no account exists for an address (non-membership).
+//! A non-membership proof ends in either an empty subtree or a different
+//! account whose key shares the same path prefix.
+//!
+//! ```rust,ignore
+//! use self_chain_core::state::{Account, SparseMerkleTree};
+//!
+//! let mut tree = SparseMerkleTree::new();
+//! tree.apply_batch(vec![(account.address.clone(), Some(account.clone()))]);
+//!
+//! let proof = tree.prove(&account.address);
+//! assert!(proof.verify(&tree.root(), &account.address, Some(&account)));
+//! ```
+
+use super::account::Account;
+use crate::blockchain::merkle::{EMPTY_ROOT, LEAF_PREFIX, NODE_PREFIX};
+use crate::blockchain::v1::codec::{
+    domain_hash, Canonical, CodecError, CodecResult, Decoder, Encoder,
+};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Domain separation prefix for account keys
+pub const KEY_PREFIX: &[u8] = b"self-chain-smt-key-v1";
+
+/// Number of levels in the tree (bits per key)
+pub const TREE_DEPTH: usize = 256;
+
+/// Derive the tree key for an account address
+pub fn account_key(address: &str) -> [u8; 32] {
+    domain_hash(KEY_PREFIX, address.as_bytes())
+}
+
+/// Hash a key and account value hash into a leaf
+pub fn leaf_hash(key: &[u8; 32], value_hash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(key);
+    hasher.update(value_hash);
+    hasher.finalize().into()
+}
+
+/// Hash two children into an inner node
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Bit `depth` of a key (0 = most significant bit of the first byte)
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    (key[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// A stored account with its cached value hash
+#[derive(Debug, Clone)]
+struct Entry {
+    account: Account,
+    value_hash: [u8; 32],
+}
+
+/// Contents of one subtree
+enum Subtree {
+    Empty,
+    Leaf([u8; 32]),
+    Inner,
+}
+
+/// Sparse Merkle Tree over accounts, keyed by address
+///
+/// Inner node hashes are cached, so `apply_batch` only rehashes the paths to
+/// the updated keys: `O(k log n)` hashes for `k` updates over `n` accounts.
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree {
+    /// Entries sorted by key (matches left-to-right leaf order)
+    entries: BTreeMap<[u8; 32], Entry>,
+
+    /// Hash of every inner node, keyed by `(lowest key below it, depth)`
+    nodes: BTreeMap<([u8; 32], usize), [u8; 32]>,
+
+    /// Cached root over `entries`
+    root: [u8; 32],
+}
+
+impl Default for SparseMerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SparseMerkleTree {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            root: EMPTY_ROOT,
+        }
+    }
+
+    /// Build a tree from a set of accounts
+    pub fn from_accounts(accounts: impl IntoIterator<Item = Account>) -> Self {
+        let mut tree = Self::new();
+        tree.apply_batch(accounts.into_iter().map(|a| (a.address.clone(), Some(a))));
+        tree
+    }
+
+    /// Current root hash
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// Number of accounts in the tree
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the tree holds no accounts
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Look up an account by address
+    pub fn get(&self, address: &str) -> Option<&Account> {
+        self.entries.get(&account_key(address)).map(|e| &e.account)
+    }
+
+    /// Iterate over all accounts in key order
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.entries.values().map(|e| &e.account)
+    }
+
+    /// Insert or replace a single account and return the new root
+    pub fn insert(&mut self, account: Account) -> [u8; 32] {
+        self.apply_batch([(account.address.clone(), Some(account))])
+    }
+
+    /// Remove a single account and return the new root
+    pub fn remove(&mut self, address: &str) -> [u8; 32] {
+        self.apply_batch([(address.to_string(), None)])
+    }
+
+    /// Apply a batch of updates and recompute the root once
+    ///
+    /// `Some(account)` inserts or replaces the account at the address, `None`
+    /// removes it. Later updates to the same address win.
+    pub fn apply_batch(
+        &mut self,
+        updates: impl IntoIterator<Item = (String, Option<Account>)>,
+    ) -> [u8; 32] {
+        let mut touched = Vec::new();
+        for (address, update) in updates {
+            let key = account_key(&address);
+            touched.push(key);
+            match update {
+                Some(account) => {
+                    let value_hash = account.hash();
+                    self.entries.insert(key, Entry { account, value_hash });
+                }
+                None => {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+
+        touched.sort_unstable();
+        touched.dedup();
+        self.root = self.update_subtree([0u8; 32], 0, &touched);
+        self.root
+    }
+
+    /// Root the tree would have after `apply_batch(updates)`, leaving the
+    /// tree unchanged
+    ///
+    /// Like `apply_batch`, only the paths to the updated keys are hashed;
+    /// every other subtree hash comes from the cache.
+    pub fn root_after(
+        &self,
+        updates: impl IntoIterator<Item = (String, Option<Account>)>,
+    ) -> [u8; 32] {
+        let pending: BTreeMap<[u8; 32], Option<[u8; 32]>> = updates
+            .into_iter()
+            .map(|(address, update)| (account_key(&address), update.map(|a| a.hash())))
+            .collect();
+        self.pending_hash(&[0u8; 32], 0, &pending)
+    }
+
+    /// Build a membership or non-membership proof for an address
+    pub fn prove(&self, address: &str) -> SmtProof {
+        let key = account_key(address);
+        let mut siblings = Vec::new();
+        let mut depth = 0;
+        loop {
+            let leaf = match self.subtree(&key, depth) {
+                Subtree::Empty => None,
+                Subtree::Leaf(leaf_key) => Some(SmtLeaf {
+                    key: leaf_key,
+                    value_hash: self.entries[&leaf_key].value_hash,
+                }),
+                Subtree::Inner => {
+                    siblings.push(self.subtree_hash(&with_bit_flipped(&key, depth), depth + 1));
+                    depth += 1;
+                    continue;
+                }
+            };
+            return SmtProof { leaf, siblings };
+        }
+    }
+
+    /// What the subtree at `depth` containing `key` holds
+    fn subtree(&self, key: &[u8; 32], depth: usize) -> Subtree {
+        let (lo, hi) = subtree_range(key, depth);
+        let mut keys = self.entries.range(lo..=hi).map(|(k, _)| *k);
+        match (keys.next(), keys.next()) {
+            (None, _) => Subtree::Empty,
+            (Some(leaf_key), None) => Subtree::Leaf(leaf_key),
+            _ => Subtree::Inner,
+        }
+    }
+
+    /// Hash of the subtree at `depth` containing `key`, from the cache
+    fn subtree_hash(&self, key: &[u8; 32], depth: usize) -> [u8; 32] {
+        match self.subtree(key, depth) {
+            Subtree::Empty => EMPTY_ROOT,
+            Subtree::Leaf(leaf_key) => leaf_hash(&leaf_key, &self.entries[&leaf_key].value_hash),
+            Subtree::Inner => self.nodes[&(subtree_range(key, depth).0, depth)],
+        }
+    }
+
+    /// Hash of the subtree at `depth` containing `key` with the `pending`
+    /// value hashes (`None` = removed) laid over the stored entries
+    fn pending_hash(
+        &self,
+        key: &[u8; 32],
+        depth: usize,
+        pending: &BTreeMap<[u8; 32], Option<[u8; 32]>>,
+    ) -> [u8; 32] {
+        let (lo, hi) = subtree_range(key, depth);
+        if pending.range(lo..=hi).next().is_none() {
+            return self.subtree_hash(key, depth);
+        }
+        let stored = self
+            .entries
+            .range(lo..=hi)
+            .filter(|(k, _)| !pending.contains_key(*k))
+            .map(|(k, e)| (*k, e.value_hash));
+        let updated = pending
+            .range(lo..=hi)
+            .filter_map(|(k, update)| update.map(|value_hash| (*k, value_hash)));
+        let mut leaves = stored.chain(updated);
+        match (leaves.next(), leaves.next()) {
+            (None, _) => EMPTY_ROOT,
+            (Some((leaf_key, value_hash)), None) => leaf_hash(&leaf_key, &value_hash),
+            _ => node_hash(
+                &self.pending_hash(&lo, depth + 1, pending),
+                &self.pending_hash(&with_bit_flipped(&lo, depth), depth + 1, pending),
+            ),
+        }
+    }
+
+    /// Rehash the subtree at `depth` containing `key` along the paths to
+    /// `touched` (sorted keys below it), reusing cached hashes elsewhere
+    fn update_subtree(&mut self, key: [u8; 32], depth: usize, touched: &[[u8; 32]]) -> [u8; 32] {
+        let (lo, hi) = subtree_range(&key, depth);
+        if touched.is_empty() {
+            return self.subtree_hash(&key, depth);
+        }
+        if !matches!(self.subtree(&key, depth), Subtree::Inner) {
+            // Inner nodes below a collapsed subtree are gone
+            let below = (lo, depth)..=(hi, TREE_DEPTH);
+            let stale: Vec<_> = self.nodes.range(below).map(|(node, _)| *node).collect();
+            for node in stale {
+                self.nodes.remove(&node);
+            }
+            return self.subtree_hash(&key, depth);
+        }
+
+        let split = touched.partition_point(|k| !bit(k, depth));
+        let (left, right) = (&touched[..split], &touched[split..]);
+        let left = self.update_subtree(lo, depth + 1, left);
+        let right = self.update_subtree(with_bit_flipped(&lo, depth), depth + 1, right);
+        let hash = node_hash(&left, &right);
+        self.nodes.insert((lo, depth), hash);
+        hash
+    }
+}
+
+/// Lowest and highest keys in the subtree at `depth` containing `key`
+fn subtree_range(key: &[u8; 32], depth: usize) -> ([u8; 32], [u8; 32]) {
+    let (mut lo, mut hi) = (*key, *key);
+    if depth < TREE_DEPTH {
+        let free = 0xFFu8 >> (depth % 8);
+        lo[depth / 8] &= !free;
+        hi[depth / 8] |= free;
+        for byte in depth / 8 + 1..32 {
+            lo[byte] = 0x00;
+            hi[byte] = 0xFF;
+        }
+    }
+    (lo, hi)
+}
+
+/// `key` with bit `depth` inverted: the same position in the sibling subtree
+fn with_bit_flipped(key: &[u8; 32], depth: usize) -> [u8; 32] {
+    let mut flipped = *key;
+    flipped[depth / 8] ^= 1 << (7 - depth % 8);
+    flipped
+}
+
+/// Leaf reached at the end of a proof path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtLeaf {
+    /// Key of the account stored at this position
+    pub key: [u8; 32],
+
+    /// Hash of that account's canonical encoding
+    pub value_hash: [u8; 32],
+}
+
+/// Membership or non-membership proof for one address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtProof {
+    /// Leaf at the end of the path (`None` = empty subtree)
+    pub leaf: Option<SmtLeaf>,
+
+    /// Sibling hashes from the root down to the leaf
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl SmtProof {
+    /// Recompute the root implied by this proof along `key`'s path
+    ///
+    /// Returns `None` if the proof is malformed (too many siblings, or a leaf
+    /// that could not sit on this path).
+    pub fn compute_root(&self, key: &[u8; 32]) -> Option<[u8; 32]> {
+        if self.siblings.len() > TREE_DEPTH {
+            return None;
+        }
+
+        let mut hash = match &self.leaf {
+            None => EMPTY_ROOT,
+            Some(leaf) => {
+                // The stored leaf must share the path prefix it is proven at
+                if (0..self.siblings.len()).any(|d| bit(&leaf.key, d) != bit(key, d)) {
+                    return None;
+                }
+                leaf_hash(&leaf.key, &leaf.value_hash)
+            }
+        };
+
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            hash = if bit(key, depth) {
+                node_hash(sibling, &hash)
+            } else {
+                node_hash(&hash, sibling)
+            };
+        }
+        Some(hash)
+    }
+
+    /// Verify this proof against `root`
+    ///
+    /// With `Some(account)` this checks that the address holds exactly that
+    /// account; with `None` it checks that the address has no account.
+    pub fn verify(&self, root: &[u8; 32], address: &str, account: Option<&Account>) -> bool {
+        let key = account_key(address);
+        let leaf_matches = match (account, &self.leaf) {
+            (Some(account), Some(leaf)) => leaf.key == key && leaf.value_hash == account.hash(),
+            (Some(_), None) => false,
+            (None, Some(leaf)) => leaf.key != key,
+            (None, None) => true,
+        };
+
+        leaf_matches && self.compute_root(&key).as_ref() == Some(root)
+    }
+}
+
+impl Canonical for SmtLeaf {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_fixed(&self.key);
+        enc.put_fixed(&self.value_hash);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            key: dec.get_fixed()?,
+            value_hash: dec.get_fixed()?,
+        })
+    }
+}
+
+impl Canonical for SmtProof {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_option(self.leaf.as_ref());
+        enc.put_seq(&self.siblings);
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        let leaf = dec.get_option()?;
+        let siblings: Vec<[u8; 32]> = dec.get_seq()?;
+        if siblings.len() > TREE_DEPTH {
+            return Err(CodecError::NonCanonical(format!(
+                "proof has {} siblings, tree depth is {}",
+                siblings.len(),
+                TREE_DEPTH
+            )));
+        }
+        Ok(Self { leaf, siblings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(i: u64) -> Account {
+        let mut account = Account::new(format!("addr-{}", i), "self-app".to_string());
+        account.native_coin_balance = Some(i * 100);
+        account
+    }
+
+    #[test]
+    fn test_empty_and_single_root() {
+        let mut tree = SparseMerkleTree::new();
+        assert_eq!(tree.root(), EMPTY_ROOT);
+
+        let a = account(1);
+        let root = tree.insert(a.clone());
+        assert_eq!(root, leaf_hash(&account_key(&a.address), &a.hash()));
+
+        assert_eq!(tree.remove(&a.address), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn test_root_independent_of_update_order() {
+        let forward = SparseMerkleTree::from_accounts((0..20).map(account));
+        let mut backward = SparseMerkleTree::new();
+        for i in (0..20).rev() {
+            backward.insert(account(i));
+        }
+
+        assert_eq!(forward.root(), backward.root());
+        assert_eq!(forward.len(), 20);
+    }
+
+    #[test]
+    fn test_batch_update_changes_root() {
+        let mut tree = SparseMerkleTree::from_accounts((0..5).map(account));
+        let before = tree.root();
+
+        let mut changed = account(2);
+        changed.nonce = 1;
+        changed.color = "a1b2c3".to_string();
+        let after = tree.apply_batch(vec![
+            (changed.address.clone(), Some(changed.clone())),
+            ("addr-4".to_string(), None),
+        ]);
+
+        assert_ne!(before, after);
+        assert_eq!(tree.get("addr-2"), Some(&changed));
+        assert!(tree.get("addr-4").is_none());
+
+        let rebuilt = SparseMerkleTree::from_accounts([account(0), account(1), changed, account(3)]);
+        assert_eq!(after, rebuilt.root());
+    }
+
+    /// Root rebuilt from every leaf, without the node cache
+    fn full_root(tree: &SparseMerkleTree) -> [u8; 32] {
+        fn root(leaves: &[([u8; 32], [u8; 32])], depth: usize) -> [u8; 32] {
+            match leaves {
+                [] => EMPTY_ROOT,
+                [(_, leaf)] => *leaf,
+                _ => {
+                    let split = leaves.partition_point(|(k, _)| !bit(k, depth));
+                    let (left, right) = leaves.split_at(split);
+                    node_hash(&root(left, depth + 1), &root(right, depth + 1))
+                }
+            }
+        }
+        let leaves: Vec<_> = tree
+            .entries
+            .iter()
+            .map(|(key, entry)| (*key, leaf_hash(key, &entry.value_hash)))
+            .collect();
+        root(&leaves, 0)
+    }
+
+    #[test]
+    fn test_incremental_root_matches_full_rebuild() {
+        let mut tree = SparseMerkleTree::from_accounts((0..64).map(account));
+        assert_eq!(tree.root(), full_root(&tree));
+
+        for step in 0..40u64 {
+            let mut changed = account(step * 7 % 80);
+            changed.nonce = step;
+            let batch = vec![
+                (changed.address.clone(), Some(changed)),
+                (format!("addr-{}", step * 13 % 80), None),
+                (format!("addr-{}", step * 5 % 80), None),
+            ];
+            assert_eq!(tree.apply_batch(batch), full_root(&tree));
+            // No cached node is left behind by removals
+            let rebuilt = SparseMerkleTree::from_accounts(tree.accounts().cloned());
+            assert_eq!(tree.nodes, rebuilt.nodes);
+        }
+
+        for i in 0..80 {
+            tree.remove(&format!("addr-{}", i));
+        }
+        assert_eq!(tree.root(), EMPTY_ROOT);
+        assert!(tree.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_root_after_matches_apply_batch() {
+        let tree = SparseMerkleTree::from_accounts((0..64).map(account));
+        let root = tree.root();
+
+        for step in 0..20u64 {
+            let mut changed = account(step * 7 % 80);
+            changed.nonce = step + 1;
+            let batch = vec![
+                (changed.address.clone(), Some(changed)),
+                (format!("addr-{}", step * 13 % 80), None),
+                (format!("addr-{}", step * 5 % 80), Some(account(step))),
+            ];
+            let mut applied = tree.clone();
+            assert_eq!(tree.root_after(batch.clone()), applied.apply_batch(batch));
+        }
+        let everything = (0..64).map(|i| (format!("addr-{}", i), None));
+        assert_eq!(tree.root_after(everything), EMPTY_ROOT);
+        assert_eq!(tree.root(), root);
+    }
+
+    #[test]
+    fn test_membership_proofs() {
+        let tree = SparseMerkleTree::from_accounts((0..16).map(account));
+        let root = tree.root();
+
+        for i in 0..16 {
+            let a = account(i);
+            let proof = tree.prove(&a.address);
+            assert!(proof.verify(&root, &a.address, Some(&a)));
+
+            // Wrong value and wrong claim of absence both fail
+            let mut wrong = a.clone();
+            wrong.native_coin_balance = Some(1);
+            assert!(!proof.verify(&root, &a.address, Some(&wrong)));
+            assert!(!proof.verify(&root, &a.address, None));
+        }
+    }
+
+    #[test]
+    fn test_non_membership_proofs() {
+        let tree = SparseMerkleTree::from_accounts((0..16).map(account));
+        let root = tree.root();
+
+        for i in 16..48 {
+            let missing = account(i);
+            let proof = tree.prove(&missing.address);
+            assert!(proof.verify(&root, &missing.address, None));
+            assert!(!proof.verify(&root, &missing.address, Some(&missing)));
+        }
+
+        let empty = SparseMerkleTree::new();
+        assert!(empty.prove("addr-0").verify(&EMPTY_ROOT, "addr-0", None));
+    }
+
+    #[test]
+    fn test_tampered_proof_fails() {
+        let tree = SparseMerkleTree::from_accounts((0..8).map(account));
+        let root = tree.root();
+        let a = account(3);
+
+        let mut proof = tree.prove(&a.address);
+        proof.siblings[0][0] ^= 1;
+        assert!(!proof.verify(&root, &a.address, Some(&a)));
+
+        let proof = tree.prove(&a.address);
+        let decoded = SmtProof::from_canonical_bytes(&proof.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(!decoded.verify(&root, "addr-4", Some(&a)));
+    }
+}
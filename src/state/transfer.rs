@@ -0,0 +1,53 @@
+//! Transfer Payload
+//!
+//! `v1::Transaction` has no amount field; a transfer (a transaction with a
+//! recipient) carries a canonically encoded `TransferPayload` in `data`.
+
+use crate::blockchain::v1::codec::{Canonical, CodecResult, Decoder, Encoder};
+
+/// Asset movement carried in `v1::Transaction.data` for transfers
+///
+/// ## Canonical Encoding Order
+///
+/// 1. `amount` (u64, little-endian)
+/// 2. `token_id` (Option<string>, None = native coin)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferPayload {
+    /// Amount to move from sender to recipient
+    pub amount: u64,
+
+    /// Application token to move, or `None` for the native coin
+    pub token_id: Option<String>,
+}
+
+impl TransferPayload {
+    /// Native coin transfer
+    pub fn native(amount: u64) -> Self {
+        Self {
+            amount,
+            token_id: None,
+        }
+    }
+
+    /// Application token transfer
+    pub fn token(token_id: impl Into<String>, amount: u64) -> Self {
+        Self {
+            amount,
+            token_id: Some(token_id.into()),
+        }
+    }
+}
+
+impl Canonical for TransferPayload {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.put_u64(self.amount);
+        enc.put_option(self.token_id.as_ref());
+    }
+
+    fn decode(dec: &mut Decoder<'_>) -> CodecResult<Self> {
+        Ok(Self {
+            amount: dec.get_u64()?,
+            token_id: dec.get_option()?,
+        })
+    }
+}